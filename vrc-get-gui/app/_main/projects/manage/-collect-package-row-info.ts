@@ -47,6 +47,7 @@ export interface PackageRowInfo {
 	installed: null | {
 		version: TauriVersion;
 		yanked: boolean;
+		yankReason: string | null;
 	};
 	latest: PackageLatestInfo;
 	stableLatest: PackageLatestInfo;
@@ -89,7 +90,8 @@ export function combinePackagesAndProjectDetails(
 		return compareUnityVersion(pkg.unity, unityVersion) <= 0;
 	}
 
-	const yankedVersions = new Set<`${string}:${string}`>();
+	// the reasons of yanked versions, null if no reason is provided
+	const yankedVersions = new Map<`${string}:${string}`, string | null>();
 	const knownPackages = new Set<string>();
 	const packagesPerRepository = new Map<string, TauriPackage[]>();
 	const userPackages: TauriPackage[] = [];
@@ -98,7 +100,10 @@ export function combinePackagesAndProjectDetails(
 		if (!showPrereleasePackages && pkg.version.pre) continue;
 
 		if (pkg.is_yanked) {
-			yankedVersions.add(`${pkg.name}:${toVersionString(pkg.version)}`);
+			yankedVersions.set(
+				`${pkg.name}:${toVersionString(pkg.version)}`,
+				pkg.yank_reason,
+			);
 			continue;
 		}
 
@@ -293,11 +298,11 @@ export function combinePackagesAndProjectDetails(
 			// if installed, use the installed version to get the display name
 			packageRowInfo.displayName = pkg.display_name ?? pkg.name;
 			packageRowInfo.keywords = [...pkg.keywords, ...packageRowInfo.keywords];
+			const versionKey = `${pkg.name}:${toVersionString(pkg.version)}` as const;
 			packageRowInfo.installed = {
 				version: pkg.version,
-				yanked:
-					pkg.is_yanked ||
-					yankedVersions.has(`${pkg.name}:${toVersionString(pkg.version)}`),
+				yanked: pkg.is_yanked || yankedVersions.has(versionKey),
+				yankReason: pkg.yank_reason ?? yankedVersions.get(versionKey) ?? null,
 			};
 			packageRowInfo.isThereSource = knownPackages.has(pkg.name);
 
@@ -6,6 +6,7 @@ import {
 	useQueryClient,
 } from "@tanstack/react-query";
 import {
+	CircleAlert,
 	CircleArrowUp,
 	CircleMinus,
 	CirclePlus,
@@ -861,7 +862,15 @@ const PackageRow = memo(function PackageRow({
 						<div
 							className={`flex flex-col ${pkg.installed ? "" : "opacity-50"}`}
 						>
-							<p className="font-normal">{pkg.displayName}</p>
+							<p className="font-normal">
+								{pkg.displayName}
+								{pkg.installed?.yanked && (
+									<span className="ml-2 inline-flex items-center gap-1 text-sm text-destructive">
+										<CircleAlert className="size-4" />
+										{tc("projects:manage:yanked badge")}
+									</span>
+								)}
+							</p>
 							<p className="font-normal opacity-50 text-sm compact:hidden">
 								{pkg.id}
 							</p>
@@ -874,6 +883,18 @@ const PackageRow = memo(function PackageRow({
 							{pkg.description}
 						</p>
 						<p className="font-normal opacity-50 text-sm">{pkg.id}</p>
+						{pkg.installed?.yanked && (
+							<p className="whitespace-normal text-destructive">
+								{pkg.installed.yankReason
+									? tc("projects:manage:tooltip:yanked with reason", {
+											version: toVersionString(pkg.installed.version),
+											reason: pkg.installed.yankReason,
+										})
+									: tc("projects:manage:tooltip:yanked", {
+											version: toVersionString(pkg.installed.version),
+										})}
+							</p>
+						)}
 					</TooltipContent>
 				</Tooltip>
 			</td>
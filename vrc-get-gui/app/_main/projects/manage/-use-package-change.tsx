@@ -2,7 +2,7 @@ import type { DefaultError } from "@tanstack/query-core";
 import { queryOptions, type UseMutationOptions } from "@tanstack/react-query";
 import { CircleAlert } from "lucide-react";
 import type React from "react";
-import { Fragment } from "react";
+import { Fragment, useEffect, useState } from "react";
 import { DelayedButton } from "@/components/DelayedButton";
 import { ExternalLink } from "@/components/ExternalLink";
 import { Button } from "@/components/ui/button";
@@ -12,16 +12,21 @@ import {
 	DialogHeader,
 	DialogTitle,
 } from "@/components/ui/dialog";
+import { Progress } from "@/components/ui/progress";
 import { ScrollArea } from "@/components/ui/scroll-area";
 import { assertNever } from "@/lib/assert-never";
 import type {
+	HandleableRustError,
 	TauriBasePackageInfo,
+	TauriDerivationStep,
+	TauriInstallProgress,
 	TauriPackage,
 	TauriPackageChange,
 	TauriPendingProjectChanges,
 	TauriVersion,
 } from "@/lib/bindings";
 import { commands } from "@/lib/bindings";
+import { callAsyncCommand } from "@/lib/call-async-command";
 import { type DialogContext, openSingleDialog } from "@/lib/dialog";
 import { isHandleable } from "@/lib/errors";
 import { tc, tt } from "@/lib/i18n";
@@ -126,22 +131,110 @@ export async function applyChanges(
 			// close window
 			return;
 		}
-		await commands.projectApplyPendingChanges(
+		await openSingleDialog(ApplyingChangesDialog, {
 			projectPath,
-			changes.changes_version,
-		);
+			changesVersion: changes.changes_version,
+		});
 		showToast(operation);
 	} catch (e) {
 		if (isHandleable(e) && e.body.type === "MissingDependencies") {
 			await openSingleDialog(MissingDependenciesDialog, {
 				dependencies: e.body.dependencies,
 			});
+		} else if (isHandleable(e) && e.body.type === "PackageHashMismatch") {
+			await openSingleDialog(PackageHashMismatchDialog, {
+				mismatch: e.body,
+			});
 		} else {
 			throw e;
 		}
 	}
 }
 
+function ApplyingChangesDialog({
+	projectPath,
+	changesVersion,
+	dialog,
+}: {
+	projectPath: string;
+	changesVersion: number;
+	dialog: DialogContext<void>;
+}) {
+	const [progress, setProgress] = useState<Map<string, TauriInstallProgress>>(
+		new Map(),
+	);
+
+	useEffect(() => {
+		const [, promise] = callAsyncCommand(
+			commands.projectApplyPendingChanges,
+			[projectPath, changesVersion],
+			(event) => {
+				setProgress((prev) => new Map(prev).set(event.package, event));
+			},
+		);
+
+		promise.then(() => dialog.close(), dialog.error);
+	}, [projectPath, changesVersion, dialog.close, dialog.error]);
+
+	return (
+		<>
+			<DialogTitle>
+				{tc("projects:manage:dialog:applying changes")}
+			</DialogTitle>
+			<div>
+				<ScrollArea type="always" className={"max-h-[50vh] flex flex-col pr-2"}>
+					{[...progress.values()].map((packageProgress) => (
+						<div key={packageProgress.package} className={"mb-2"}>
+							<p>{installProgressMessage(packageProgress)}</p>
+							<InstallProgressBar progress={packageProgress} />
+						</div>
+					))}
+				</ScrollArea>
+				<p>{tc("projects:do not close")}</p>
+			</div>
+		</>
+	);
+}
+
+function installProgressMessage(
+	progress: TauriInstallProgress,
+): React.ReactNode {
+	switch (progress.type) {
+		case "Downloading":
+			return tc("projects:manage:dialog:downloading package", {
+				name: progress.package,
+			});
+		case "Extracting":
+			return tc("projects:manage:dialog:extracting package", {
+				name: progress.package,
+			});
+		case "Installed":
+			return tc("projects:manage:dialog:installed package", {
+				name: progress.package,
+			});
+		default:
+			assertNever(progress);
+	}
+}
+
+function InstallProgressBar({ progress }: { progress: TauriInstallProgress }) {
+	switch (progress.type) {
+		case "Downloading":
+			// the server may not tell the size of the package
+			return progress.total == null ? (
+				<Progress />
+			) : (
+				<Progress value={progress.downloaded} max={progress.total} />
+			);
+		case "Extracting":
+			return <Progress value={progress.extracted} max={progress.total} />;
+		case "Installed":
+			return <Progress value={1} max={1} />;
+		default:
+			assertNever(progress);
+	}
+}
+
 function createChanges(
 	projectPath: string,
 	operation: RequestedOperation,
@@ -374,6 +467,21 @@ function ProjectChangesDialog({
 								</div>
 							</>
 						) : null}
+						{changes.resolution_conflict != null ? (
+							<>
+								<p className={"text-destructive"}>
+									{tc("projects:manage:dialog:resolution conflict")}
+								</p>
+								<div className={"flex flex-col gap-1 p-2"}>
+									{changes.resolution_conflict.map((step, i) => (
+										// biome-ignore lint/suspicious/noArrayIndexKey: steps are not reordered
+										<TypographyItem key={i}>
+											{derivationStepText(step, getPackageDisplayName)}
+										</TypographyItem>
+									))}
+								</div>
+							</>
+						) : null}
 						{unityConflicts.length > 0 ? (
 							<>
 								<p className={"text-destructive"}>
@@ -761,6 +869,55 @@ function ChangelogButton({ url }: { url?: string | null }) {
 	return null;
 }
 
+function derivationStepText(
+	step: TauriDerivationStep,
+	getPackageDisplayName: (id: string) => string,
+): React.ReactNode {
+	switch (step.type) {
+		case "Requires":
+			if (step.dependant == null) {
+				return tc("projects:manage:dialog:derivation:project requires", {
+					dependency: getPackageDisplayName(step.dependency),
+					range: step.range,
+				});
+			}
+			return tc("projects:manage:dialog:derivation:requires", {
+				pkg: getPackageDisplayName(step.dependant[0]),
+				version: toVersionString(step.dependant[1]),
+				dependency: getPackageDisplayName(step.dependency),
+				range: step.range,
+			});
+		case "RequiresOtherVersion":
+			return tc("projects:manage:dialog:derivation:requires other version", {
+				pkg: getPackageDisplayName(step.dependant[0]),
+				version: toVersionString(step.dependant[1]),
+				dependency: getPackageDisplayName(step.dependency),
+				range: step.range,
+				selected: toVersionString(step.selected),
+			});
+		case "LockedVersion":
+			return tc("projects:manage:dialog:derivation:locked version", {
+				pkg: getPackageDisplayName(step.package),
+				version: toVersionString(step.version),
+			});
+		case "NoMatchingVersion":
+			return tc("projects:manage:dialog:derivation:no matching version", {
+				pkg: getPackageDisplayName(step.package),
+			});
+		case "AllVersionsConflict":
+			return tc("projects:manage:dialog:derivation:all versions conflict", {
+				pkg: getPackageDisplayName(step.package),
+				versions: step.versions.map(toVersionString).join(", "),
+			});
+		case "TooComplex":
+			return tc("projects:manage:dialog:derivation:too complex");
+		case "Other":
+			return step.message;
+		default:
+			assertNever(step);
+	}
+}
+
 function MissingDependenciesDialog({
 	dependencies,
 	dialog,
@@ -794,3 +951,47 @@ function MissingDependenciesDialog({
 		</div>
 	);
 }
+
+function PackageHashMismatchDialog({
+	mismatch,
+	dialog,
+}: {
+	mismatch: Extract<HandleableRustError, { type: "PackageHashMismatch" }>;
+	dialog: DialogContext<void>;
+}) {
+	return (
+		<div>
+			<DialogTitle className={"text-destructive"}>
+				<CircleAlert className="size-6 inline" />{" "}
+				{tc("projects:manage:dialog:package hash mismatch")}
+			</DialogTitle>
+			<div>
+				<p className={"whitespace-normal"}>
+					{tc(
+						mismatch.locked
+							? "projects:manage:dialog:package hash mismatch locked description"
+							: "projects:manage:dialog:package hash mismatch description",
+						{ package: mismatch.package, version: mismatch.version },
+					)}
+				</p>
+				<ul className={"list-disc ml-4 mt-2 font-mono break-all"}>
+					<li>
+						{tc("projects:manage:dialog:package hash mismatch expected", {
+							hash: mismatch.expected,
+						})}
+					</li>
+					<li>
+						{tc("projects:manage:dialog:package hash mismatch actual", {
+							hash: mismatch.actual,
+						})}
+					</li>
+				</ul>
+			</div>
+			<DialogFooter>
+				<Button onClick={() => dialog.close()}>
+					{tc("general:button:close")}
+				</Button>
+			</DialogFooter>
+		</div>
+	);
+}
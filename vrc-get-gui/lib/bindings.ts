@@ -224,15 +224,24 @@ async projectResolve(projectPath: string) : Promise<TauriPendingProjectChanges>
 async projectRemovePackages(projectPath: string, names: string[]) : Promise<TauriPendingProjectChanges> {
     return await TAURI_INVOKE("project_remove_packages", { projectPath, names });
 },
-async projectApplyPendingChanges(projectPath: string, changesVersion: number) : Promise<null> {
-    return await TAURI_INVOKE("project_apply_pending_changes", { projectPath, changesVersion });
+async projectApplyPendingChanges(channel: string, projectPath: string, changesVersion: number) : Promise<AsyncCallResult<TauriInstallProgress, null>> {
+    return await TAURI_INVOKE("project_apply_pending_changes", { channel, projectPath, changesVersion });
 },
 async projectClearPendingChanges() : Promise<null> {
     return await TAURI_INVOKE("project_clear_pending_changes");
 },
+async projectHistory(projectPath: string) : Promise<TauriHistoryEntry[]> {
+    return await TAURI_INVOKE("project_history", { projectPath });
+},
+async projectRestoreHistory(projectPath: string, historyId: string) : Promise<TauriPendingProjectChanges> {
+    return await TAURI_INVOKE("project_restore_history", { projectPath, historyId });
+},
 async projectMigrateProjectTo2022(projectPath: string) : Promise<null> {
     return await TAURI_INVOKE("project_migrate_project_to_2022", { projectPath });
 },
+async projectCheckMigrateProjectTo2022(projectPath: string) : Promise<TauriMigrationIssue[]> {
+    return await TAURI_INVOKE("project_check_migrate_project_to_2022", { projectPath });
+},
 async projectCallUnityForMigration(channel: string, projectPath: string, unityPath: string) : Promise<AsyncCallResult<string, TauriCallUnityForMigrationResult>> {
     return await TAURI_INVOKE("project_call_unity_for_migration", { channel, projectPath, unityPath });
 },
@@ -317,7 +326,7 @@ export type CheckForUpdateResponse = { version: number; current_version: string;
 /**
  * Errors that is expected to be handled on the GUI side
  */
-export type HandleableRustError = { type: "MissingDependencies"; dependencies: ([string, string])[] }
+export type HandleableRustError = { type: "MissingDependencies"; dependencies: ([string, string])[] } | { type: "PackageHashMismatch"; package: string; version: string; expected: string; actual: string; locked: boolean }
 export type InstallUpgradeProgress = { type: "DownloadProgress"; received: number; total: number | null } | { type: "DownloadComplete" }
 export type LocalizableRustError = { id: string; args: { [key in string]: string } }
 export type LogEntry = { time: string; level: LogLevel; target: string; message: string; gui_toast: boolean }
@@ -329,22 +338,28 @@ export type TauriAddProjectWithPickerResult = "NoFolderSelected" | "InvalidSelec
 export type TauriAddRepositoryResult = "BadUrl" | "Success"
 export type TauriAddUserPackageWithPickerResult = "NoFolderSelected" | "InvalidSelection" | "AlreadyAdded" | "Successful"
 export type TauriAlcomTemplate = { display_name: string; base: string; unity_version: string | null; vpm_dependencies: { [key in string]: string }; unity_packages: string[] }
-export type TauriBasePackageInfo = { name: string; display_name: string | null; description: string | null; keywords: string[]; version: TauriVersion; unity: [number, number] | null; changelog_url: string | null; documentation_url: string | null; vpm_dependencies: string[]; legacy_packages: string[]; is_yanked: boolean }
+export type TauriBasePackageInfo = { name: string; display_name: string | null; description: string | null; keywords: string[]; version: TauriVersion; unity: [number, number] | null; changelog_url: string | null; documentation_url: string | null; vpm_dependencies: string[]; legacy_packages: string[]; is_yanked: boolean; yank_reason: string | null }
 export type TauriCallUnityForMigrationResult = { type: "ExistsWithNonZero"; status: string } | { type: "FinishedSuccessfully" }
 export type TauriConflictInfo = { packages: string[]; unity_conflict: boolean; unlocked_names: string[] }
 export type TauriCopyProjectProgress = { total: number; proceed: number; last_proceed: string }
 export type TauriCreateBackupProgress = { total: number; proceed: number; last_proceed: string }
 export type TauriCreateProjectResult = "AlreadyExists" | "TemplateNotFound" | "Successful"
+export type TauriDerivationStep = { type: "Requires"; dependant: [string, TauriVersion] | null; dependency: string; range: string } | { type: "RequiresOtherVersion"; dependant: [string, TauriVersion]; dependency: string; range: string; selected: TauriVersion } | { type: "LockedVersion"; package: string; version: TauriVersion } | { type: "NoMatchingVersion"; package: string } | { type: "AllVersionsConflict"; package: string; versions: TauriVersion[] } | { type: "TooComplex" } | { type: "Other"; message: string }
 export type TauriDownloadRepository = { type: "BadUrl" } | { type: "Duplicated"; reason: TauriDuplicatedReason; duplicated_name: string } | { type: "DownloadError"; message: string } | { type: "Success"; value: TauriRemoteRepositoryInfo }
 export type TauriDuplicatedReason = "URLDuplicated" | "IDDuplicated"
 export type TauriEnvironmentSettings = { default_project_path: string; project_backup_path: string; unity_hub: string; unity_paths: ([string, string, boolean])[]; show_prerelease_packages: boolean; backup_format: string; release_channel: string; use_alcom_for_vcc_protocol: boolean; default_unity_arguments: string[] | null; gui_animation: boolean; gui_compact: boolean; unity_hub_access_method: UnityHubAccessMethod; exclude_vpm_packages_from_backup: boolean }
+export type TauriHistoryChange = { name: string; before: TauriVersion | null; after: TauriVersion | null }
+export type TauriHistoryEntry = { id: string; created_at: number; changes: TauriHistoryChange[] }
 export type TauriImportDuplicated = { id: string; existing_path: string; existing_name: string; existing_update_date: string | null; importing_name: string; importing_update_date: string | null; data: number[] }
 export type TauriImportRepositoryPickResult = { type: "NoFilePicked" } | { type: "ParsedRepositories"; repositories: TauriRepositoryDescriptor[]; unparsable_lines: string[] }
 export type TauriImportTemplateResult = { imported: number; duplicates: TauriImportDuplicated[] }
-export type TauriPackage = ({ name: string; display_name: string | null; description: string | null; keywords: string[]; version: TauriVersion; unity: [number, number] | null; changelog_url: string | null; documentation_url: string | null; vpm_dependencies: string[]; legacy_packages: string[]; is_yanked: boolean }) & { source: TauriPackageSource }
+export type TauriInstallProgress = { type: "Downloading"; package: string; downloaded: number; total: number | null } | { type: "Extracting"; package: string; extracted: number; total: number } | { type: "Installed"; package: string }
+export type TauriMigrationIssue = { name: string; version: TauriVersion; locked: boolean; reason: TauriMigrationIssueReason; proposed_version: TauriVersion | null; upgraded_by_migration: boolean; blocks_migration: boolean }
+export type TauriMigrationIssueReason = { type: "BelowMinimum"; minimum: TauriVersion } | { type: "RequiresNewerUnity"; unity: [number, number] } | { type: "OnlyForUnity2019" } | { type: "ReplacedWith"; package: string } | { type: "Other"; message: string }
+export type TauriPackage = ({ name: string; display_name: string | null; description: string | null; keywords: string[]; version: TauriVersion; unity: [number, number] | null; changelog_url: string | null; documentation_url: string | null; vpm_dependencies: string[]; legacy_packages: string[]; is_yanked: boolean; yank_reason: string | null }) & { source: TauriPackageSource }
 export type TauriPackageChange = { InstallNew: TauriBasePackageInfo } | { Remove: TauriRemoveReason }
 export type TauriPackageSource = "LocalUser" | { Remote: { id: string; display_name: string } }
-export type TauriPendingProjectChanges = { changes_version: number; package_changes: ([string, TauriPackageChange])[]; remove_legacy_files: string[]; remove_legacy_folders: string[]; conflicts: ([string, TauriConflictInfo])[] }
+export type TauriPendingProjectChanges = { changes_version: number; package_changes: ([string, TauriPackageChange])[]; remove_legacy_files: string[]; remove_legacy_folders: string[]; conflicts: ([string, TauriConflictInfo])[]; resolution_conflict: TauriDerivationStep[] | null }
 export type TauriPickProjectBackupPathResult = { type: "NoFolderSelected" } | { type: "InvalidSelection" } | { type: "Successful" }
 export type TauriPickProjectDefaultPathResult = { type: "NoFolderSelected" } | { type: "InvalidSelection" } | { type: "Successful"; new_path: string }
 export type TauriPickUnityHubResult = { type: "NoFolderSelected" } | { type: "InvalidSelection" } | { type: "Successful" }
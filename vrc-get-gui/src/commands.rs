@@ -8,10 +8,13 @@ pub use start::startup;
 use tauri::generate_handler;
 use tauri::ipc::Invoke;
 pub use uri_custom_scheme::handle_vrc_get_scheme;
-use vrc_get_vpm::environment::VccDatabaseConnection;
+use vrc_get_vpm::environment::{
+    PackageHashMismatchError, PackageInstaller, Settings, VccDatabaseConnection,
+};
 use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo};
 use vrc_get_vpm::unity_project::{
-    AddPackageErr, MigrateUnity2022Error, MigrateVpmError, ReinstalPackagesError, ResolvePackageErr,
+    AddPackageErr, ApplyPendingChangesErr, MigrateUnityError, MigrateVpmError,
+    ReinstalPackagesError, ResolvePackageErr,
 };
 use vrc_get_vpm::version::{Version, VersionRange};
 use vrc_get_vpm::{PackageInfo, PackageManifest, UnityProject};
@@ -48,8 +51,8 @@ pub use environment::templates::import_templates;
 #[allow(unused_imports)]
 mod prelude {
     pub(super) use super::{
-        IntoPathBuf as _, RustError, TauriBasePackageInfo, TauriPackage, UnityProject,
-        load_project, update_project_last_modified,
+        IntoPathBuf as _, RustError, TauriBasePackageInfo, TauriPackage, TauriVersion,
+        UnityProject, create_installer, load_project, update_project_last_modified,
     };
     pub use crate::state::*;
 }
@@ -134,7 +137,10 @@ pub(crate) fn handlers() -> impl Fn(Invoke) -> bool + Send + Sync + 'static {
         project::project_remove_packages,
         project::project_apply_pending_changes,
         project::project_clear_pending_changes,
+        project::project_history,
+        project::project_restore_history,
         project::project_migrate_project_to_2022,
+        project::project_check_migrate_project_to_2022,
         project::project_call_unity_for_migration,
         project::project_migrate_project_to_vpm,
         project::project_open_unity,
@@ -241,7 +247,10 @@ pub(crate) fn export_ts() {
             project::project_remove_packages,
             project::project_apply_pending_changes,
             project::project_clear_pending_changes,
+            project::project_history,
+            project::project_restore_history,
             project::project_migrate_project_to_2022,
+            project::project_check_migrate_project_to_2022,
             project::project_call_unity_for_migration,
             project::project_migrate_project_to_vpm,
             project::project_open_unity,
@@ -316,6 +325,13 @@ enum HandleableRustError {
     MissingDependencies {
         dependencies: Vec<(Box<str>, Box<str>)>,
     },
+    PackageHashMismatch {
+        package: Box<str>,
+        version: Box<str>,
+        expected: Box<str>,
+        actual: Box<str>,
+        locked: bool,
+    },
 }
 
 impl RustError {
@@ -360,7 +376,6 @@ macro_rules! impl_from_error {
 }
 
 impl_from_error!(
-    io::Error,
     String,
     async_zip::error::ZipError,
     vrc_get_vpm::environment::AddRepositoryErr,
@@ -368,6 +383,39 @@ impl_from_error!(
     fs_extra::error::Error,
 );
 
+impl From<io::Error> for RustError {
+    fn from(value: io::Error) -> Self {
+        if let Some(mismatch) = PackageHashMismatchError::from_io_error(&value) {
+            return mismatch.into();
+        }
+        RustError::unrecoverable(value)
+    }
+}
+
+impl From<&PackageHashMismatchError> for RustError {
+    fn from(mismatch: &PackageHashMismatchError) -> Self {
+        RustError::handleable(
+            mismatch.to_string(),
+            HandleableRustError::PackageHashMismatch {
+                package: mismatch.package_name().into(),
+                version: mismatch.version().to_string().into(),
+                expected: mismatch.expected().into(),
+                actual: mismatch.actual().into(),
+                locked: mismatch.is_locked(),
+            },
+        )
+    }
+}
+
+impl From<ApplyPendingChangesErr> for RustError {
+    fn from(value: ApplyPendingChangesErr) -> Self {
+        match value {
+            ApplyPendingChangesErr::HashMismatch(mismatch) => (&mismatch).into(),
+            value => RustError::unrecoverable(value),
+        }
+    }
+}
+
 impl From<tauri_plugin_updater::Error> for RustError {
     fn from(value: tauri_plugin_updater::Error) -> Self {
         log::error!(gui_toast = false; "failed to load latest release: {value}");
@@ -379,15 +427,17 @@ impl From<MigrateVpmError> for RustError {
     fn from(value: MigrateVpmError) -> Self {
         match value {
             MigrateVpmError::AddPackageErr(add_err) => add_err.into(),
+            MigrateVpmError::HashMismatch(mismatch) => (&mismatch).into(),
             value => RustError::unrecoverable(value),
         }
     }
 }
 
-impl From<MigrateUnity2022Error> for RustError {
-    fn from(value: MigrateUnity2022Error) -> Self {
+impl From<MigrateUnityError> for RustError {
+    fn from(value: MigrateUnityError) -> Self {
         match value {
-            MigrateUnity2022Error::AddPackageErr(add_err) => add_err.into(),
+            MigrateUnityError::AddPackageErr(add_err) => add_err.into(),
+            MigrateUnityError::HashMismatch(mismatch) => (&mismatch).into(),
             value => RustError::unrecoverable(value),
         }
     }
@@ -463,6 +513,7 @@ struct TauriBasePackageInfo {
     vpm_dependencies: Vec<String>,
     legacy_packages: Vec<String>,
     is_yanked: bool,
+    yank_reason: Option<String>,
 }
 
 impl TauriBasePackageInfo {
@@ -489,6 +540,7 @@ impl TauriBasePackageInfo {
                 .map(|x| x.to_string())
                 .collect(),
             is_yanked: package.is_yanked(),
+            yank_reason: package.yank_reason().map(|v| v.to_string()),
         }
     }
 }
@@ -530,6 +582,19 @@ async fn load_project(project_path: String) -> Result<UnityProject, RustError> {
     Ok(UnityProject::load(DefaultProjectIo::new(PathBuf::from(project_path).into())).await?)
 }
 
+fn create_installer<'a>(
+    io: &'a DefaultEnvironmentIo,
+    http: &'a reqwest::Client,
+    settings: &Settings,
+) -> PackageInstaller<'a, reqwest::Client> {
+    let mut installer = PackageInstaller::new(io, Some(http));
+    installer.set_hash_mismatch_policy(settings.hash_mismatch_policy());
+    installer.set_max_concurrency(Some(settings.max_concurrent_downloads()));
+    installer.set_retry_policy(settings.download_retry_policy());
+    installer.set_use_package_store(settings.use_package_store());
+    installer
+}
+
 trait IntoPathBuf {
     fn into_path_buf(self) -> Result<PathBuf, RustError>;
 }
@@ -19,8 +19,9 @@ use std::time::Instant;
 use tauri::{AppHandle, Emitter, Manager, State, Window};
 use tauri_plugin_dialog::DialogExt;
 use vrc_get_vpm::ProjectType;
+use vrc_get_vpm::advisory::AdvisoryDatabase;
 use vrc_get_vpm::environment::{
-    InvalidRealProjectInformation, PackageInstaller, RealProjectInformation, Settings, UserProject,
+    InvalidRealProjectInformation, RealProjectInformation, Settings, UserProject,
     ValidRealProjectInformation, VccDatabaseConnection,
 };
 use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo};
@@ -815,8 +816,10 @@ pub async fn environment_create_project(
     };
 
     let packages;
+    let installer;
     {
         let mut settings = settings.load_mut(io.inner()).await?;
+        installer = create_installer(io.inner(), http.inner(), &settings);
         packages = packages_state
             .load_fully(&settings, io.inner(), http.inner())
             .await?;
@@ -831,9 +834,9 @@ pub async fn environment_create_project(
     }
 
     {
-        let installer = PackageInstaller::new(io.inner(), Some(http.inner()));
-
         // finally, resolve the project folder
+        let advisories = AdvisoryDatabase::load(io.inner(), packages.collection()).await?;
+        unity_project.set_advisories(advisories);
         let request = unity_project.resolve_request(packages.collection()).await?;
         unity_project
             .apply_pending_changes(&installer, request)
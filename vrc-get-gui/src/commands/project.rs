@@ -4,20 +4,28 @@ use crate::commands::prelude::*;
 use crate::utils::{collect_notable_project_files_tree, project_backup_path};
 use log::{error, info, warn};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, State, Window};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
-use vrc_get_vpm::environment::{PackageInstaller, VccDatabaseConnection};
+use vrc_get_vpm::advisory::AdvisoryDatabase;
+use vrc_get_vpm::environment::VccDatabaseConnection;
 use vrc_get_vpm::io::DefaultEnvironmentIo;
 use vrc_get_vpm::unity_project::pending_project_changes::{
     ConflictInfo, PackageChange, RemoveReason,
 };
-use vrc_get_vpm::unity_project::{AddPackageOperation, PendingProjectChanges};
+use vrc_get_vpm::unity_project::{
+    AddPackageOperation, DerivationStep, HistoryEntry, MigrationIssue, MigrationIssueReason,
+    PendingProjectChanges,
+};
 use vrc_get_vpm::version::{StrictEqVersion, Version};
+use vrc_get_vpm::{InstallProgress, PackageInfo, VRCHAT_RECOMMENDED_2022_UNITY};
 
 #[derive(Serialize, specta::Type)]
 pub struct TauriProjectDetails {
@@ -57,6 +65,7 @@ pub struct TauriPendingProjectChanges {
     remove_legacy_folders: Vec<String>,
 
     conflicts: Vec<(String, TauriConflictInfo)>,
+    resolution_conflict: Option<Vec<TauriDerivationStep>>,
 }
 
 impl TauriPendingProjectChanges {
@@ -83,6 +92,9 @@ impl TauriPendingProjectChanges {
                 .iter()
                 .map(|(name, info)| (name.to_string(), info.into()))
                 .collect(),
+            resolution_conflict: changes
+                .resolution_conflict()
+                .map(|conflict| conflict.steps().iter().map(Into::into).collect()),
         }
     }
 }
@@ -149,6 +161,83 @@ impl From<&ConflictInfo> for TauriConflictInfo {
     }
 }
 
+#[derive(Serialize, specta::Type)]
+#[serde(tag = "type")]
+enum TauriDerivationStep {
+    Requires {
+        dependant: Option<(String, TauriVersion)>,
+        dependency: String,
+        range: String,
+    },
+    RequiresOtherVersion {
+        dependant: (String, TauriVersion),
+        dependency: String,
+        range: String,
+        selected: TauriVersion,
+    },
+    LockedVersion {
+        package: String,
+        version: TauriVersion,
+    },
+    NoMatchingVersion {
+        package: String,
+    },
+    AllVersionsConflict {
+        package: String,
+        versions: Vec<TauriVersion>,
+    },
+    TooComplex {},
+    Other {
+        message: String,
+    },
+}
+
+impl From<&DerivationStep> for TauriDerivationStep {
+    fn from(value: &DerivationStep) -> Self {
+        match value {
+            DerivationStep::Requires {
+                dependant,
+                dependency,
+                range,
+            } => Self::Requires {
+                dependant: dependant
+                    .as_ref()
+                    .map(|(name, version)| (name.to_string(), version.into())),
+                dependency: dependency.to_string(),
+                range: range.to_string(),
+            },
+            DerivationStep::RequiresOtherVersion {
+                dependant: (name, version),
+                dependency,
+                range,
+                selected,
+            } => Self::RequiresOtherVersion {
+                dependant: (name.to_string(), version.into()),
+                dependency: dependency.to_string(),
+                range: range.to_string(),
+                selected: selected.into(),
+            },
+            DerivationStep::LockedVersion { package, version } => Self::LockedVersion {
+                package: package.to_string(),
+                version: version.into(),
+            },
+            DerivationStep::NoMatchingVersion { package } => Self::NoMatchingVersion {
+                package: package.to_string(),
+            },
+            DerivationStep::AllVersionsConflict { package, versions } => {
+                Self::AllVersionsConflict {
+                    package: package.to_string(),
+                    versions: versions.iter().map(Into::into).collect(),
+                }
+            }
+            DerivationStep::TooComplex => Self::TooComplex {},
+            step => Self::Other {
+                message: step.to_string(),
+            },
+        }
+    }
+}
+
 macro_rules! changes {
     ($packages_ref: ident, $changes: ident, |$collection: pat_param, $packages: pat_param| $body: expr) => {{
         $changes
@@ -210,7 +299,9 @@ pub async fn project_install_packages(
             return Err(RustError::unrecoverable("some packages not found"));
         };
 
-        let unity_project = load_project(project_path).await?;
+        let mut unity_project = load_project(project_path).await?;
+        unity_project.set_resolver_mode(settings.resolver_mode());
+        unity_project.set_advisories(AdvisoryDatabase::load(&io, collection).await?);
 
         let allow_prerelease = settings.show_prerelease_packages();
 
@@ -266,7 +357,9 @@ pub async fn project_resolve(
     let settings = settings.load(&io).await?;
     let packages = packages.load(&settings, &io, &http, app_handle).await?;
     changes!(packages, changes, |collection| {
-        let unity_project = load_project(project_path).await?;
+        let mut unity_project = load_project(project_path).await?;
+        unity_project.set_resolver_mode(settings.resolver_mode());
+        unity_project.set_advisories(AdvisoryDatabase::load(&io, collection).await?);
 
         unity_project.resolve_request(collection).await?
     })
@@ -288,31 +381,131 @@ pub async fn project_remove_packages(
     Ok(changes_state.set(changes, TauriPendingProjectChanges::new))
 }
 
+#[derive(Serialize, specta::Type, Clone)]
+#[serde(tag = "type")]
+pub enum TauriInstallProgress {
+    Downloading {
+        package: String,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    Extracting {
+        package: String,
+        extracted: usize,
+        total: usize,
+    },
+    Installed {
+        package: String,
+    },
+}
+
+/// The minimum interval between two progress events for the same package.
+/// Without this, a download emits an event for every chunk and floods the frontend.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+struct EmitInstallProgress {
+    ctx: AsyncCommandContext<TauriInstallProgress>,
+    last_emit: Mutex<HashMap<String, Instant>>,
+}
+
+impl EmitInstallProgress {
+    fn new(ctx: AsyncCommandContext<TauriInstallProgress>) -> Self {
+        Self {
+            ctx,
+            last_emit: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns whether a progress event for the package should be emitted now.
+    /// Finished steps are always emitted so the frontend sees the final state.
+    fn should_emit(&self, package: &str, finished: bool) -> bool {
+        let now = Instant::now();
+        let mut last_emit = self.last_emit.lock().unwrap();
+        match last_emit.get(package) {
+            Some(last) if !finished && now.duration_since(*last) < PROGRESS_EMIT_INTERVAL => false,
+            _ => {
+                last_emit.insert(package.to_string(), now);
+                true
+            }
+        }
+    }
+}
+
+impl InstallProgress for EmitInstallProgress {
+    fn downloading(&self, package: PackageInfo<'_>, downloaded: u64, total: Option<u64>) {
+        if !self.should_emit(package.name(), total == Some(downloaded)) {
+            return;
+        }
+        let package = package.name().to_string();
+        self.ctx
+            .emit(TauriInstallProgress::Downloading {
+                package,
+                downloaded,
+                total,
+            })
+            .ok();
+    }
+
+    fn extracting(&self, package: PackageInfo<'_>, extracted: usize, total: usize) {
+        if !self.should_emit(package.name(), extracted == total) {
+            return;
+        }
+        let package = package.name().to_string();
+        self.ctx
+            .emit(TauriInstallProgress::Extracting {
+                package,
+                extracted,
+                total,
+            })
+            .ok();
+    }
+
+    fn installed(&self, package: PackageInfo<'_>) {
+        let package = package.name().to_string();
+        self.ctx
+            .emit(TauriInstallProgress::Installed { package })
+            .ok();
+    }
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn project_apply_pending_changes(
-    changes: State<'_, ChangesState>,
-    io: State<'_, DefaultEnvironmentIo>,
-    http: State<'_, reqwest::Client>,
+    window: Window,
+    channel: String,
     project_path: String,
     changes_version: u32,
-) -> Result<(), RustError> {
-    let Some(mut changes) = changes.get_versioned(changes_version) else {
-        return Err(RustError::unrecoverable("changes version mismatch"));
-    };
+) -> Result<AsyncCallResult<TauriInstallProgress, ()>, RustError> {
+    async_command(channel, window, async {
+        With::<TauriInstallProgress>::continue_async(move |ctx| async move {
+            let changes = ctx.state::<ChangesState>();
+            let io = ctx.state::<DefaultEnvironmentIo>();
+            let http = ctx.state::<reqwest::Client>();
 
-    let changes = changes.take_changes();
+            let Some(mut changes) = changes.get_versioned(changes_version) else {
+                return Err(RustError::unrecoverable("changes version mismatch"));
+            };
 
-    let installer = PackageInstaller::new(io.inner(), Some(http.inner()));
+            let changes = changes.take_changes();
 
-    let mut unity_project = load_project(project_path).await?;
+            let installer = {
+                let settings = ctx.state::<SettingsState>();
+                let settings = settings.load(io.inner()).await?;
+                create_installer(io.inner(), http.inner(), &settings)
+            };
 
-    unity_project
-        .apply_pending_changes(&installer, changes)
-        .await?;
+            let mut unity_project = load_project(project_path).await?;
 
-    update_project_last_modified(&io, unity_project.project_dir()).await;
-    Ok(())
+            let progress = EmitInstallProgress::new(ctx.clone());
+            unity_project
+                .apply_pending_changes_with_progress(&installer, changes, &progress)
+                .await?;
+
+            update_project_last_modified(&io, unity_project.project_dir()).await;
+            Ok(())
+        })
+    })
+    .await
 }
 
 #[tauri::command]
@@ -324,6 +517,82 @@ pub async fn project_clear_pending_changes(
     Ok(())
 }
 
+#[derive(Serialize, specta::Type)]
+pub struct TauriHistoryEntry {
+    id: String,
+    created_at: i64,
+    changes: Vec<TauriHistoryChange>,
+}
+
+#[derive(Serialize, specta::Type)]
+pub struct TauriHistoryChange {
+    name: String,
+    before: Option<TauriVersion>,
+    after: Option<TauriVersion>,
+}
+
+impl From<&HistoryEntry> for TauriHistoryEntry {
+    fn from(value: &HistoryEntry) -> Self {
+        Self {
+            id: value.id().to_string(),
+            created_at: value
+                .created_at()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|x| x.as_millis() as i64)
+                .unwrap_or_default(),
+            changes: value
+                .changes()
+                .iter()
+                .map(|change| TauriHistoryChange {
+                    name: change.name().to_string(),
+                    before: change.before().map(Into::into),
+                    after: change.after().map(Into::into),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn project_history(project_path: String) -> Result<Vec<TauriHistoryEntry>, RustError> {
+    let unity_project = load_project(project_path).await?;
+
+    Ok(unity_project
+        .history()
+        .await?
+        .iter()
+        .map(Into::into)
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn project_restore_history(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsState>,
+    packages: State<'_, PackagesState>,
+    changes: State<'_, ChangesState>,
+    io: State<'_, DefaultEnvironmentIo>,
+    http: State<'_, reqwest::Client>,
+    project_path: String,
+    history_id: String,
+) -> Result<TauriPendingProjectChanges, RustError> {
+    let settings = settings.load(&io).await?;
+    let packages = packages.load(&settings, &io, &http, app_handle).await?;
+    changes!(packages, changes, |collection| {
+        let unity_project = load_project(project_path).await?;
+
+        let Some(entry) = unity_project.get_history(&history_id).await? else {
+            return Err(RustError::unrecoverable("history entry not found"));
+        };
+
+        unity_project
+            .restore_history_request(collection, &entry)
+            .await?
+    })
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn project_migrate_project_to_2022(
@@ -339,7 +608,7 @@ pub async fn project_migrate_project_to_2022(
         let packages = packages.load(&settings, &io, &http, app_handle).await?;
         let mut unity_project = load_project(project_path).await?;
 
-        let installer = PackageInstaller::new(io.inner(), Some(http.inner()));
+        let installer = create_installer(io.inner(), http.inner(), &settings);
 
         unity_project
             .migrate_unity_2022(packages.collection(), &installer)
@@ -351,6 +620,81 @@ pub async fn project_migrate_project_to_2022(
     }
 }
 
+#[derive(Serialize, specta::Type)]
+#[serde(tag = "type")]
+enum TauriMigrationIssueReason {
+    BelowMinimum { minimum: TauriVersion },
+    RequiresNewerUnity { unity: (u16, u8) },
+    OnlyForUnity2019 {},
+    ReplacedWith { package: String },
+    Other { message: String },
+}
+
+impl From<&MigrationIssueReason> for TauriMigrationIssueReason {
+    fn from(value: &MigrationIssueReason) -> Self {
+        match value {
+            MigrationIssueReason::BelowMinimum(minimum) => Self::BelowMinimum {
+                minimum: minimum.into(),
+            },
+            MigrationIssueReason::RequiresNewerUnity(unity) => {
+                Self::RequiresNewerUnity { unity: *unity }
+            }
+            MigrationIssueReason::OnlyForUnity2019 => Self::OnlyForUnity2019 {},
+            MigrationIssueReason::ReplacedWith(package) => Self::ReplacedWith {
+                package: package.to_string(),
+            },
+            reason => Self::Other {
+                message: reason.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, specta::Type)]
+pub struct TauriMigrationIssue {
+    name: String,
+    version: TauriVersion,
+    locked: bool,
+    reason: TauriMigrationIssueReason,
+    proposed_version: Option<TauriVersion>,
+    upgraded_by_migration: bool,
+    blocks_migration: bool,
+}
+
+impl From<&MigrationIssue> for TauriMigrationIssue {
+    fn from(value: &MigrationIssue) -> Self {
+        Self {
+            name: value.name().to_string(),
+            version: value.version().into(),
+            locked: value.is_locked(),
+            reason: value.reason().into(),
+            proposed_version: value.proposed_version().map(Into::into),
+            upgraded_by_migration: value.upgraded_by_migration(),
+            blocks_migration: value.blocks_migration(),
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn project_check_migrate_project_to_2022(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsState>,
+    packages: State<'_, PackagesState>,
+    io: State<'_, DefaultEnvironmentIo>,
+    http: State<'_, reqwest::Client>,
+    project_path: String,
+) -> Result<Vec<TauriMigrationIssue>, RustError> {
+    let settings = settings.load(io.inner()).await?;
+    let packages = packages.load(&settings, &io, &http, app_handle).await?;
+    let unity_project = load_project(project_path).await?;
+
+    let issues = unity_project
+        .check_unity_migration(VRCHAT_RECOMMENDED_2022_UNITY, packages.collection())?;
+
+    Ok(issues.iter().map(Into::into).collect())
+}
+
 #[derive(Serialize, specta::Type, Clone)]
 #[serde(tag = "type")]
 #[allow(dead_code)]
@@ -445,7 +789,7 @@ pub async fn project_migrate_project_to_vpm(
     let packages = packages.load(&settings, &io, &http, app_handle).await?;
 
     let mut unity_project = load_project(project_path).await?;
-    let installer = PackageInstaller::new(io.inner(), Some(http.inner()));
+    let installer = create_installer(io.inner(), http.inner(), &settings);
 
     unity_project
         .migrate_vpm(
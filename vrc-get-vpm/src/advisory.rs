@@ -0,0 +1,257 @@
+//! Security advisories for packages.
+//!
+//! The advisories are loaded from `vrc-get/advisories.json` in the vpm data folder
+//! and from the `vrc-get.advisories` list of the cached repositories, in the following format:
+//!
+//! ```json
+//! {
+//!   "advisories": [
+//!     {
+//!       "id": "EXAMPLE-2024-0001",
+//!       "package": "com.example.free-asset",
+//!       "versions": ">=1.0.0 <1.2.0",
+//!       "severity": "critical",
+//!       "description": "The package uploads the avatars in the project to a remote server",
+//!       "url": "https://example.com/advisories/EXAMPLE-2024-0001"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Installing the packages matching advisories is denied by
+//! [`add_package_request`](crate::UnityProject::add_package_request) and
+//! [`resolve_request`](crate::UnityProject::resolve_request) once the database is set with
+//! [`set_advisories`](crate::UnityProject::set_advisories).
+
+use crate::environment::PackageCollection;
+use crate::io::{DefaultEnvironmentIo, IoTrait};
+use crate::utils::{deserialize_json, deserialize_json_slice, parse_json_file, read_to_end};
+use crate::version::{PrereleaseAcceptance, Version, VersionRange};
+use crate::{PackageInfo, UnityProject, io};
+use serde::Deserialize;
+use std::fmt;
+use url::Url;
+
+const JSON_PATH: &str = "vrc-get/advisories.json";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AdvisorySeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for AdvisorySeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdvisorySeverity::Low => f.write_str("low"),
+            AdvisorySeverity::Medium => f.write_str("medium"),
+            AdvisorySeverity::High => f.write_str("high"),
+            AdvisorySeverity::Critical => f.write_str("critical"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+    id: Box<str>,
+    package: Box<str>,
+    versions: VersionRange,
+    severity: AdvisorySeverity,
+    description: Box<str>,
+    #[serde(default)]
+    url: Option<Url>,
+}
+
+impl Advisory {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// The affected versions of the package.
+    pub fn versions(&self) -> &VersionRange {
+        &self.versions
+    }
+
+    pub fn severity(&self) -> AdvisorySeverity {
+        self.severity
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    pub fn matches(&self, name: &str, version: &Version) -> bool {
+        // prerelease versions are affected as well
+        self.package.as_ref() == name
+            && self
+                .versions
+                .match_pre(version, PrereleaseAcceptance::Allow)
+    }
+}
+
+/// A package version matching an advisory.
+#[derive(Debug, Clone)]
+pub struct AdvisoryMatch {
+    package: Box<str>,
+    version: Version,
+    advisory: Advisory,
+}
+
+impl AdvisoryMatch {
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn advisory(&self) -> &Advisory {
+        &self.advisory
+    }
+}
+
+impl fmt::Display for AdvisoryMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let advisory = &self.advisory;
+        write!(
+            f,
+            "{}@{}: [{}] {}: {}",
+            self.package, self.version, advisory.severity, advisory.id, advisory.description
+        )?;
+        if let Some(url) = &advisory.url {
+            write!(f, " ({url})")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    #[serde(default)]
+    advisories: Vec<Advisory>,
+}
+
+/// The set of advisories checked when installing packages.
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryDatabase {
+    advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the advisory file in the format described in the [module documentation](self).
+    pub fn parse(json: &[u8]) -> io::Result<Self> {
+        let json = json.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json);
+        let file: AdvisoryFile = deserialize_json_slice(json)?;
+        Ok(Self {
+            advisories: file.advisories,
+        })
+    }
+
+    /// Loads the advisories from `vrc-get/advisories.json` and the repositories in the collection.
+    ///
+    /// Broken advisory lists in the repositories are skipped with warnings
+    /// since they are not under control of the user.
+    pub async fn load(
+        io: &DefaultEnvironmentIo,
+        collection: &PackageCollection,
+    ) -> io::Result<Self> {
+        let mut database = match io.open(JSON_PATH.as_ref()).await {
+            Ok(file) => {
+                let file: AdvisoryFile =
+                    parse_json_file(&read_to_end(file).await?, JSON_PATH.as_ref())?;
+                Self {
+                    advisories: file.advisories,
+                }
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Self::new(),
+            Err(e) => return Err(e),
+        };
+
+        for repo in collection.get_remote() {
+            let Some(advisories) = repo.repo().vrc_get_extension("advisories") else {
+                continue;
+            };
+            match deserialize_json::<Vec<Advisory>>(advisories.clone()) {
+                Ok(advisories) => database.advisories.extend(advisories),
+                Err(e) => log::warn!(
+                    "ignoring broken advisories of repository {}: {e}",
+                    repo.id()
+                        .or(repo.url().map(Url::as_str))
+                        .unwrap_or("(unknown)")
+                ),
+            }
+        }
+
+        Ok(database)
+    }
+
+    pub fn advisories(&self) -> &[Advisory] {
+        &self.advisories
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.advisories.is_empty()
+    }
+
+    /// Returns the advisories matching the package version, the most severe first.
+    pub fn find(&self, name: &str, version: &Version) -> Vec<&Advisory> {
+        let mut found = (self.advisories.iter())
+            .filter(|x| x.matches(name, version))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|x| std::cmp::Reverse(x.severity));
+        found
+    }
+
+    /// Checks the locked packages and the unlocked packages installed in the project.
+    pub fn audit(&self, project: &UnityProject) -> Vec<AdvisoryMatch> {
+        let mut matches = Vec::new();
+        for locked in project.locked_packages() {
+            self.check(locked.name(), locked.version(), &mut matches);
+        }
+        for (_, unlocked) in project.unlocked_packages() {
+            if let Some(unlocked) = unlocked {
+                self.check(unlocked.name(), unlocked.version(), &mut matches);
+            }
+        }
+        matches
+    }
+
+    fn check(&self, name: &str, version: &Version, matches: &mut Vec<AdvisoryMatch>) {
+        matches.extend(
+            self.find(name, version)
+                .into_iter()
+                .map(|advisory| AdvisoryMatch {
+                    package: name.into(),
+                    version: version.clone(),
+                    advisory: advisory.clone(),
+                }),
+        );
+    }
+
+    pub(crate) fn check_packages<'env>(
+        &self,
+        packages: impl Iterator<Item = PackageInfo<'env>>,
+    ) -> Vec<AdvisoryMatch> {
+        let mut matches = Vec::new();
+        for package in packages {
+            self.check(package.name(), package.version(), &mut matches);
+        }
+        matches
+    }
+}
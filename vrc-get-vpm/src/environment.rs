@@ -1,3 +1,4 @@
+mod bundle;
 mod repo_holder;
 mod repo_source;
 mod uesr_package_collection;
@@ -6,8 +7,10 @@ mod vrc_get_settings;
 
 #[cfg(feature = "vrc-get-litedb")]
 mod litedb;
+mod package_cache;
 mod package_collection;
 mod package_installer;
+mod package_store;
 #[cfg(feature = "experimental-project-management")]
 mod project_management;
 mod settings;
@@ -35,10 +38,18 @@ pub(crate) use repo_source::RepoSource;
 #[cfg(feature = "experimental-unity-management")]
 pub use unity_management::*;
 
+pub use bundle::{BundleExport, BundleImport, BundlePackage, export_bundle, import_bundle};
 #[cfg(feature = "vrc-get-litedb")]
 pub use litedb::VccDatabaseConnection;
+pub use package_cache::{
+    CachedPackage, CachedPackageHash, PackageCachePrunePolicy, package_cache_entries,
+    remove_cached_package,
+};
 pub use package_collection::PackageCollection;
+pub use package_installer::HashMismatchPolicy;
+pub use package_installer::PackageHashMismatchError;
 pub use package_installer::PackageInstaller;
+pub use package_store::{PackageStoreEntry, gc_package_store, package_store_entries};
 pub use settings::Settings;
 pub use uesr_package_collection::UserPackageCollection;
 
@@ -193,7 +204,9 @@ pub async fn clear_package_cache(io: &DefaultEnvironmentIo) -> io::Result<()> {
                     let name = cache_entry.file_name();
                     let name = name.as_encoded_bytes();
                     if name.starts_with(b"vrc-get-")
-                        && (name.ends_with(b".zip") || name.ends_with(b".zip.sha256"))
+                        && (name.ends_with(b".zip")
+                            || name.ends_with(b".zip.sha256")
+                            || name.ends_with(b".zip.partial"))
                         && cache_entry.file_type().await?.is_file()
                     {
                         return Ok(Some((pkg_id, cache_entry)));
@@ -0,0 +1,455 @@
+//! Offline bundles of packages.
+//!
+//! A bundle is a zip file with the zip files of the packages locked in projects and
+//! the repositories providing them, so projects can be resolved on machines without network.
+//!
+//! The bundle contains the following files:
+//! - `vrc-get-bundle.json`: the list of repositories and packages in the bundle
+//! - `repositories/<index>.json`: the repository, without headers since they may contain credentials
+//! - `packages/<name>/vrc-get-<name>-<version>.zip`: the zip file of the package
+
+use crate::environment::{
+    CURATED_URL_STR, LOCAL_CURATED_PATH, LOCAL_OFFICIAL_PATH, OFFICIAL_URL_STR, PackageCollection,
+    PackageInstaller, REPO_CACHE_FOLDER, Settings, write_new_repo,
+};
+use crate::io::{DefaultEnvironmentIo, IoTrait};
+use crate::repository::RemoteRepository;
+use crate::repository::local::LocalCachedRepository;
+use crate::utils::{
+    MapResultExt, Sha256AsyncWrite, parse_json_file, to_vec_pretty_os_eol, try_load_json,
+};
+use crate::version::Version;
+use crate::{HttpClient, PackageCollection as _, PackageInfo, UnityProject, io};
+use async_zip::base::read::seek::ZipFileReader;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use futures::prelude::*;
+use indexmap::IndexMap;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+const MANIFEST_PATH: &str = "vrc-get-bundle.json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleManifest {
+    format_version: u32,
+    repositories: Vec<BundleRepositoryEntry>,
+    packages: Vec<BundlePackageEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleRepositoryEntry {
+    url: Url,
+    path: Box<str>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundlePackageEntry {
+    name: Box<str>,
+    version: Version,
+    repository: Url,
+    sha256: Box<str>,
+    path: Box<str>,
+}
+
+/// A package in a bundle.
+#[derive(Debug, Clone)]
+pub struct BundlePackage {
+    name: Box<str>,
+    version: Version,
+}
+
+impl BundlePackage {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+}
+
+/// The result of [`export_bundle`].
+#[derive(Debug)]
+pub struct BundleExport {
+    packages: Vec<BundlePackage>,
+    not_found: Vec<BundlePackage>,
+    repositories: Vec<Url>,
+}
+
+impl BundleExport {
+    /// The packages written to the bundle.
+    pub fn packages(&self) -> &[BundlePackage] {
+        &self.packages
+    }
+
+    /// The locked packages not found in any remote repository, e.g. user packages.
+    ///
+    /// The projects cannot be resolved offline with the bundle only if this is not empty.
+    pub fn not_found(&self) -> &[BundlePackage] {
+        &self.not_found
+    }
+
+    /// The url of the repositories written to the bundle.
+    pub fn repositories(&self) -> &[Url] {
+        &self.repositories
+    }
+}
+
+/// The result of [`import_bundle`].
+#[derive(Debug)]
+pub struct BundleImport {
+    packages: Vec<BundlePackage>,
+    added_repositories: Vec<Url>,
+    updated_repositories: Vec<Url>,
+}
+
+impl BundleImport {
+    /// The packages added to the package cache.
+    pub fn packages(&self) -> &[BundlePackage] {
+        &self.packages
+    }
+
+    /// The repositories newly added to the settings.
+    pub fn added_repositories(&self) -> &[Url] {
+        &self.added_repositories
+    }
+
+    /// The repositories whose cache is replaced with the one in the bundle
+    /// since the cache didn't have some packages in the bundle.
+    pub fn updated_repositories(&self) -> &[Url] {
+        &self.updated_repositories
+    }
+}
+
+/// Writes the bundle with the packages locked in `projects` to the file at `output`.
+///
+/// Packages not in the package cache are downloaded with `installer`.
+/// `output` is relative to the environment folder unless absolute.
+/// The file is removed if exporting fails.
+pub async fn export_bundle<T: HttpClient>(
+    installer: &PackageInstaller<'_, T>,
+    collection: &PackageCollection,
+    projects: &[UnityProject],
+    output: &Path,
+) -> io::Result<BundleExport> {
+    let io = installer.io;
+    let file = io.create(output).await?;
+    let result = write_bundle(installer, collection, projects, file).await;
+    if result.is_err() {
+        io.remove_file(output).await.ok();
+    }
+    result
+}
+
+async fn write_bundle<T: HttpClient>(
+    installer: &PackageInstaller<'_, T>,
+    collection: &PackageCollection,
+    projects: &[UnityProject],
+    output: impl AsyncWrite + Unpin,
+) -> io::Result<BundleExport> {
+    let mut seen = HashSet::new();
+    let mut packages = Vec::<(PackageInfo, &Url, Option<&str>)>::new();
+    let mut not_found = Vec::new();
+
+    for project in projects {
+        for locked in project.locked_packages() {
+            if !seen.insert((locked.name(), locked.version())) {
+                continue;
+            }
+
+            // local repositories are not supported since they have no url to identify them
+            let candidates = (collection.find_packages(locked.name()))
+                .filter(|x| x.version() == locked.version())
+                .filter_map(|x| Some((x, x.repo()?.url()?)))
+                .collect::<Vec<_>>();
+            let recorded_url = locked.repository().and_then(|x| x.url());
+            let found = (candidates.iter())
+                .find(|(_, url)| Some(*url) == recorded_url)
+                .or(candidates.first());
+
+            match found {
+                Some(&(package, url)) => packages.push((package, url, locked.zip_sha_256())),
+                None => not_found.push(BundlePackage {
+                    name: locked.name().into(),
+                    version: locked.version().clone(),
+                }),
+            }
+        }
+    }
+
+    let mut repositories = IndexMap::<&Url, &RemoteRepository>::new();
+    for &(package, url, _) in &packages {
+        repositories
+            .entry(url)
+            .or_insert_with(|| package.repo().unwrap().repo());
+    }
+
+    let mut writer = ZipFileWriter::new(output);
+    let mut manifest = BundleManifest {
+        format_version: FORMAT_VERSION,
+        repositories: Vec::with_capacity(repositories.len()),
+        packages: Vec::with_capacity(packages.len()),
+    };
+
+    for (index, (&url, &repository)) in repositories.iter().enumerate() {
+        let path = format!("repositories/{index}.json");
+        let entry = ZipEntryBuilder::new(path.clone().into(), Compression::Deflate);
+        let json = to_vec_pretty_os_eol(repository)?;
+        writer.write_entry_whole(entry, &json).await.err_mapped()?;
+        manifest.repositories.push(BundleRepositoryEntry {
+            url: url.clone(),
+            path: path.into(),
+        });
+    }
+
+    for &(package, url, locked_sha256) in &packages {
+        let (mut zip_file, hash) = installer.download_package(package, locked_sha256).await?;
+
+        let path = format!(
+            "packages/{name}/vrc-get-{name}-{version}.zip",
+            name = package.name(),
+            version = package.version(),
+        );
+        // zip files are already compressed
+        let entry = ZipEntryBuilder::new(path.clone().into(), Compression::Stored);
+        let mut entry_writer = writer.write_entry_stream(entry).await.err_mapped()?;
+        io::copy(&mut zip_file, &mut entry_writer).await?;
+        entry_writer.close().await.err_mapped()?;
+
+        manifest.packages.push(BundlePackageEntry {
+            name: package.name().into(),
+            version: package.version().clone(),
+            repository: url.clone(),
+            sha256: hex::encode(hash).into(),
+            path: path.into(),
+        });
+    }
+
+    let entry = ZipEntryBuilder::new(MANIFEST_PATH.into(), Compression::Deflate);
+    let json = to_vec_pretty_os_eol(&manifest)?;
+    writer.write_entry_whole(entry, &json).await.err_mapped()?;
+    writer.close().await.err_mapped()?.flush().await?;
+
+    Ok(BundleExport {
+        packages: (packages.iter())
+            .map(|(package, _, _)| BundlePackage {
+                name: package.name().into(),
+                version: package.version().clone(),
+            })
+            .collect(),
+        not_found,
+        repositories: repositories.keys().map(|&x| x.clone()).collect(),
+    })
+}
+
+/// Adds the packages in the bundle to the package cache and the repositories to the settings.
+///
+/// The cache of repositories already in the settings is replaced with the one in the bundle
+/// only if it doesn't have some packages in the bundle.
+/// `bundle` is relative to the environment folder unless absolute.
+/// The settings are modified but not saved.
+pub async fn import_bundle(
+    settings: &mut Settings,
+    io: &DefaultEnvironmentIo,
+    bundle: &Path,
+) -> io::Result<BundleImport> {
+    let bundle = io::BufReader::new(io.open(bundle).await?);
+    let mut zip = ZipFileReader::new(bundle).await.err_mapped()?;
+
+    let manifest: BundleManifest = parse_json_file(
+        &read_entry(&mut zip, MANIFEST_PATH).await?,
+        MANIFEST_PATH.as_ref(),
+    )?;
+    if manifest.format_version != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unsupported bundle format version: {}",
+                manifest.format_version
+            ),
+        ));
+    }
+
+    // import packages first so repositories never refer packages missing in the cache
+    let mut packages = Vec::with_capacity(manifest.packages.len());
+    for package in &manifest.packages {
+        import_package(&mut zip, io, package).await?;
+        packages.push(BundlePackage {
+            name: package.name.clone(),
+            version: package.version.clone(),
+        });
+    }
+
+    let mut added_repositories = Vec::new();
+    let mut updated_repositories = Vec::new();
+    for repository in &manifest.repositories {
+        let url = &repository.url;
+        let json = read_entry(&mut zip, &repository.path).await?;
+        let mut remote: RemoteRepository = parse_json_file(&json, Path::new(&*repository.path))?;
+        remote.set_url_if_none(|| url.clone());
+
+        let Some(cache_path) = existing_cache_path(settings, url) else {
+            let cache = LocalCachedRepository::new(remote, IndexMap::new());
+            let file_name = write_new_repo(&cache, io).await?;
+            let repo_path = io.resolve(format!("{REPO_CACHE_FOLDER}/{file_name}").as_ref());
+            settings.add_remote_repo(url, None, IndexMap::new(), cache.repo(), &repo_path);
+            added_repositories.push(url.clone());
+            continue;
+        };
+
+        let existing = match try_load_json::<LocalCachedRepository>(io, &cache_path).await {
+            Ok(existing) => existing,
+            Err(e) => {
+                warn!("error loading repository cache for {url}: {e}");
+                None
+            }
+        };
+        let up_to_date = existing.as_ref().is_some_and(|existing| {
+            (manifest.packages.iter())
+                .filter(|x| x.repository == *url)
+                .all(|x| {
+                    existing
+                        .repo()
+                        .get_package_version(&x.name, &x.version)
+                        .is_some()
+                })
+        });
+        if up_to_date {
+            continue;
+        }
+
+        let headers = existing.map(|x| x.headers).unwrap_or_default();
+        let cache = LocalCachedRepository::new(remote, headers);
+        io.write_sync(&cache_path, &to_vec_pretty_os_eol(&cache)?)
+            .await?;
+        updated_repositories.push(url.clone());
+    }
+
+    Ok(BundleImport {
+        packages,
+        added_repositories,
+        updated_repositories,
+    })
+}
+
+async fn import_package(
+    zip: &mut ZipFileReader<impl AsyncBufRead + AsyncSeek + Unpin>,
+    io: &DefaultEnvironmentIo,
+    package: &BundlePackageEntry,
+) -> io::Result<()> {
+    // the name is used as a folder name
+    if package.name.is_empty()
+        || package.name.contains(['/', '\\'])
+        || package.name.starts_with('.')
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid package name in the bundle: {}", package.name),
+        ));
+    }
+
+    let folder = PathBuf::from(format!("{REPO_CACHE_FOLDER}/{}", package.name));
+    let zip_file_name = format!("vrc-get-{}-{}.zip", package.name, package.version);
+    let zip_path = folder.join(&zip_file_name);
+    let sha_path = zip_path.with_extension("zip.sha256");
+
+    io.create_dir_all(&folder).await?;
+
+    // the zip file is written to the temporary file so the existing cache is kept if it's broken
+    let temp_path = folder.join(format!("{zip_file_name}.{}.tmp", uuid::Uuid::new_v4()));
+    let hash = match write_package_zip(zip, io, package, &temp_path).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            io.remove_file(&temp_path).await.ok();
+            return Err(e);
+        }
+    };
+
+    if !hash.eq_ignore_ascii_case(&package.sha256) {
+        io.remove_file(&temp_path).await.ok();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "hash of {}@{} in the bundle doesn't match",
+                package.name, package.version
+            ),
+        ));
+    }
+
+    // remove the hash file first so the zip file is not used while replacing
+    match io.remove_file(&sha_path).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    if let Err(e) = io.rename(&temp_path, &zip_path).await {
+        io.remove_file(&temp_path).await.ok();
+        return Err(e);
+    }
+
+    io.write(&sha_path, format!("{hash} {zip_file_name}\n").as_bytes())
+        .await
+}
+
+/// Writes the zip file of the package in the bundle to `path` and returns the SHA-256 of it.
+async fn write_package_zip(
+    zip: &mut ZipFileReader<impl AsyncBufRead + AsyncSeek + Unpin>,
+    io: &DefaultEnvironmentIo,
+    package: &BundlePackageEntry,
+    path: &Path,
+) -> io::Result<String> {
+    let index = find_entry(zip, &package.path)?;
+    let mut reader = zip.reader_without_entry(index).await.err_mapped()?;
+    let mut hasher = Sha256AsyncWrite::new(io.create(path).await?);
+    io::copy(&mut reader, &mut hasher).await?;
+    let (mut file, hash) = hasher.finalize();
+    file.flush().await?;
+    Ok(hex::encode(hash))
+}
+
+/// Returns the path to the cache of the repository if the repository is in the settings.
+fn existing_cache_path(settings: &Settings, url: &Url) -> Option<PathBuf> {
+    if url.as_str() == OFFICIAL_URL_STR && !settings.ignore_official_repository() {
+        return Some(LOCAL_OFFICIAL_PATH.into());
+    }
+    if url.as_str() == CURATED_URL_STR && !settings.ignore_curated_repository() {
+        return Some(LOCAL_CURATED_PATH.into());
+    }
+    (settings.get_user_repos().iter())
+        .find(|x| x.url() == Some(url))
+        .map(|x| x.local_path().to_owned())
+}
+
+fn find_entry(
+    zip: &ZipFileReader<impl AsyncBufRead + AsyncSeek + Unpin>,
+    name: &str,
+) -> io::Result<usize> {
+    (zip.file().entries().iter())
+        .position(|x| x.filename().as_str().ok() == Some(name))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name} not found in the bundle"),
+            )
+        })
+}
+
+async fn read_entry(
+    zip: &mut ZipFileReader<impl AsyncBufRead + AsyncSeek + Unpin>,
+    name: &str,
+) -> io::Result<Vec<u8>> {
+    let index = find_entry(zip, name)?;
+    let mut buf = Vec::new();
+    (zip.reader_without_entry(index).await.err_mapped()?)
+        .read_to_end(&mut buf)
+        .await?;
+    Ok(buf)
+}
@@ -0,0 +1,287 @@
+//! Inspection and pruning of the package cache.
+//!
+//! The package cache is the zip files downloaded by vrc-get,
+//! stored as `Repos/<name>/vrc-get-<name>-<version>.zip` with the sha256 hash in `.zip.sha256` file.
+
+use crate::environment::REPO_CACHE_FOLDER;
+use crate::io::{DefaultEnvironmentIo, DefaultProjectIo, DirEntry, IoTrait};
+use crate::utils::Sha256AsyncWrite;
+use crate::version::Version;
+use crate::{UnityProject, io};
+use futures::prelude::*;
+use hex::FromHex;
+use log::warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// The state of the hash of a cached package.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CachedPackageHash {
+    /// The zip file matches the recorded hash.
+    Valid,
+    /// The zip file doesn't match the recorded hash. The file will be downloaded again.
+    Mismatch,
+    /// The hash file is missing or broken. The file will be downloaded again.
+    Missing,
+}
+
+/// A zip file in the package cache.
+#[derive(Debug, Clone)]
+pub struct CachedPackage {
+    name: Box<str>,
+    version: Version,
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: CachedPackageHash,
+    used: bool,
+}
+
+impl CachedPackage {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// The size of the zip file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The last modification time of the zip file, that is the time downloaded.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    pub fn hash(&self) -> CachedPackageHash {
+        self.hash
+    }
+
+    /// Whether any of the projects passed to [`package_cache_entries`] locks this version of the package.
+    pub fn used(&self) -> bool {
+        self.used
+    }
+
+    fn zip_path(&self) -> PathBuf {
+        PathBuf::from(format!(
+            "{REPO_CACHE_FOLDER}/{name}/vrc-get-{name}-{version}.zip",
+            name = self.name,
+            version = self.version,
+        ))
+    }
+}
+
+/// Returns the zip files in the package cache.
+///
+/// The hash of each zip file is verified, so this reads all the cached files.
+/// `projects` are used to check whether the packages are still used.
+pub async fn package_cache_entries(
+    io: &DefaultEnvironmentIo,
+    projects: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> io::Result<Vec<CachedPackage>> {
+    let locked = load_locked_packages(projects).await;
+
+    let mut entries = Vec::new();
+
+    let mut folders = match io.read_dir(REPO_CACHE_FOLDER.as_ref()).await {
+        Ok(folders) => folders,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e),
+    };
+
+    while let Some(folder) = folders.try_next().await? {
+        if !folder.file_type().await?.is_dir() {
+            continue;
+        }
+        let Ok(name) = folder.file_name().into_string() else {
+            continue;
+        };
+        let prefix = format!("vrc-get-{name}-");
+        let folder_path = Path::new(REPO_CACHE_FOLDER).join(&name);
+
+        let mut files = io.read_dir(&folder_path).await?;
+        while let Some(file) = files.try_next().await? {
+            let file_name = file.file_name();
+            let Some(version) = (file_name.to_str())
+                .and_then(|x| x.strip_prefix(&prefix))
+                .and_then(|x| x.strip_suffix(".zip"))
+            else {
+                continue;
+            };
+            let Ok(version) = Version::from_str(version) else {
+                continue;
+            };
+            let metadata = file.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let zip_path = folder_path.join(&file_name);
+            let hash = verify_hash(io, &zip_path).await?;
+            let used = (locked.iter()).any(|x| *x.name == *name && x.version == version);
+
+            entries.push(CachedPackage {
+                name: name.as_str().into(),
+                version,
+                size: metadata.size(),
+                modified: metadata.modified(),
+                hash,
+                used,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+async fn verify_hash(io: &DefaultEnvironmentIo, zip_path: &Path) -> io::Result<CachedPackageHash> {
+    let mut buf = [0u8; 256 / 4];
+    let recorded = match io.open(&zip_path.with_extension("zip.sha256")).await {
+        Ok(mut file) => match file.read_exact(&mut buf).await {
+            Ok(()) => <[u8; 256 / 8]>::from_hex(buf).ok(),
+            Err(_) => None,
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    let Some(recorded) = recorded else {
+        return Ok(CachedPackageHash::Missing);
+    };
+
+    let mut hasher = Sha256AsyncWrite::new(io::sink());
+    io::copy(&mut io.open(zip_path).await?, &mut hasher).await?;
+
+    if hasher.finalize().1[..] == recorded[..] {
+        Ok(CachedPackageHash::Valid)
+    } else {
+        Ok(CachedPackageHash::Mismatch)
+    }
+}
+
+/// Removes the zip file and the hash file of the cached package.
+pub async fn remove_cached_package(
+    io: &DefaultEnvironmentIo,
+    package: &CachedPackage,
+) -> io::Result<()> {
+    let zip_path = package.zip_path();
+    io.remove_file(&zip_path).await?;
+    match io.remove_file(&zip_path.with_extension("zip.sha256")).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The policy to select cached packages to remove.
+///
+/// A cached package is selected if it matches all the conditions set.
+/// If no condition is set, nothing is selected.
+#[derive(Debug, Clone, Default)]
+pub struct PackageCachePrunePolicy {
+    keep_latest: Option<usize>,
+    older_than: Option<Duration>,
+    unused_only: bool,
+}
+
+impl PackageCachePrunePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the latest `count` versions of each package.
+    pub fn set_keep_latest(&mut self, count: Option<usize>) {
+        self.keep_latest = count;
+    }
+
+    /// Selects packages downloaded more than `age` ago.
+    pub fn set_older_than(&mut self, age: Option<Duration>) {
+        self.older_than = age;
+    }
+
+    /// Selects packages not locked by any of the projects.
+    pub fn set_unused_only(&mut self, unused_only: bool) {
+        self.unused_only = unused_only;
+    }
+
+    fn has_condition(&self) -> bool {
+        self.keep_latest.is_some() || self.older_than.is_some() || self.unused_only
+    }
+
+    /// Returns the cached packages to remove.
+    pub fn select<'a>(
+        &self,
+        entries: &'a [CachedPackage],
+        now: SystemTime,
+    ) -> Vec<&'a CachedPackage> {
+        if !self.has_condition() {
+            return vec![];
+        }
+
+        // the rank of the version in the package, 0 for the latest
+        let mut by_name = HashMap::<&str, Vec<&Version>>::new();
+        for entry in entries {
+            by_name.entry(&entry.name).or_default().push(&entry.version);
+        }
+        for versions in by_name.values_mut() {
+            versions.sort_by(|a, b| b.cmp(a));
+        }
+        let rank = |entry: &CachedPackage| {
+            by_name[entry.name()]
+                .iter()
+                .position(|x| **x == entry.version)
+                .unwrap()
+        };
+
+        entries
+            .iter()
+            .filter(|entry| self.keep_latest.is_none_or(|count| rank(entry) >= count))
+            .filter(|entry| {
+                self.older_than.is_none_or(|age| {
+                    (entry.modified)
+                        .and_then(|modified| now.duration_since(modified).ok())
+                        .is_some_and(|elapsed| elapsed > age)
+                })
+            })
+            .filter(|entry| !self.unused_only || !entry.used)
+            .collect()
+    }
+}
+
+/// A package locked in a project.
+pub(super) struct LockedPackageRef {
+    pub name: Box<str>,
+    pub version: Version,
+    /// The lowercase hex sha256 hash of the zip file if recorded
+    pub sha256: Option<Box<str>>,
+}
+
+/// Loads the locked packages of the projects, ignoring projects failed to load.
+pub(super) async fn load_locked_packages(
+    projects: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Vec<LockedPackageRef> {
+    let mut locked_packages = Vec::new();
+
+    for project in projects {
+        let project = project.as_ref();
+        let project = match UnityProject::load(DefaultProjectIo::new(project.into())).await {
+            Ok(project) => project,
+            Err(e) => {
+                warn!("error loading project at {}: {e}", project.display());
+                continue;
+            }
+        };
+        for locked in project.locked_packages() {
+            locked_packages.push(LockedPackageRef {
+                name: locked.name().into(),
+                version: locked.version().clone(),
+                sha256: locked.zip_sha_256().map(|x| x.to_ascii_lowercase().into()),
+            });
+        }
+    }
+
+    locked_packages
+}
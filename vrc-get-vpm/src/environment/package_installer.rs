@@ -1,47 +1,242 @@
 use crate::environment::REPO_CACHE_FOLDER;
 use crate::io::{DefaultEnvironmentIo, DefaultProjectIo, IoTrait, TokioFile};
 use crate::repository::LocalCachedRepository;
-use crate::traits::AbortCheck;
-use crate::utils::Sha256AsyncWrite;
+use crate::traits::{AbortCheck, InstallProgress, RetryPolicy};
+use crate::utils::{Sha256AsyncWrite, read_to_end};
+use crate::version::Version;
 use crate::{HttpClient, PackageInfo, PackageManifest, io};
 use futures::prelude::*;
 use hex::FromHex;
 use indexmap::IndexMap;
-use log::{debug, error};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io::SeekFrom;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::pin::pin;
+use std::str::FromStr;
 use url::Url;
 
 pub struct PackageInstaller<'a, T: HttpClient> {
     pub(super) io: &'a DefaultEnvironmentIo,
     pub(super) http: Option<&'a T>,
+    hash_mismatch_policy: HashMismatchPolicy,
+    max_concurrency: Option<NonZeroUsize>,
+    retry_policy: Option<RetryPolicy>,
+    use_package_store: bool,
 }
 
 impl<'a, T: HttpClient> PackageInstaller<'a, T> {
     pub fn new(io: &'a DefaultEnvironmentIo, http: Option<&'a T>) -> Self {
-        Self { io, http }
+        Self {
+            io,
+            http,
+            hash_mismatch_policy: HashMismatchPolicy::default(),
+            max_concurrency: None,
+            retry_policy: None,
+            use_package_store: false,
+        }
+    }
+
+    pub fn set_hash_mismatch_policy(&mut self, policy: HashMismatchPolicy) {
+        self.hash_mismatch_policy = policy;
+    }
+
+    /// Sets the maximum number of packages downloaded and extracted at once.
+    ///
+    /// `None` means no limit.
+    pub fn set_max_concurrency(&mut self, max_concurrency: Option<NonZeroUsize>) {
+        self.max_concurrency = max_concurrency;
+    }
+
+    /// Sets the policy to retry downloading packages.
+    ///
+    /// `None` means the policy of the http client.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Sets whether to install packages from the shared package store.
+    ///
+    /// With the package store, packages are extracted once to the store and
+    /// files are cloned or hard-linked to projects instead of extracted to each project.
+    pub fn set_use_package_store(&mut self, use_package_store: bool) {
+        self.use_package_store = use_package_store;
+    }
+
+    /// Gets the zip file of the package from the package cache, downloading it if not cached.
+    ///
+    /// Returns the zip file and its sha256 hash. Local packages are not supported.
+    pub async fn download_package(
+        &self,
+        package: PackageInfo<'_>,
+        locked_sha256: Option<&str>,
+    ) -> io::Result<(TokioFile, [u8; 256 / 8])> {
+        use crate::PackageInfoInner;
+        match package.inner {
+            PackageInfoInner::Remote(manifest, repository) => {
+                let on_progress = |_, _| {};
+                get_package(self, repository, manifest, locked_sha256, &on_progress).await
+            }
+            PackageInfoInner::Local(_, path) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "{} is a local package at {}",
+                    package.name(),
+                    path.display()
+                ),
+            )),
+        }
+    }
+}
+
+/// How to handle the zip file whose hash doesn't match the hash in the repository.
+///
+/// Mismatch with the hash in the `locked` section of the project is always an error.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashMismatchPolicy {
+    /// Logs the mismatch and continues installing.
+    #[default]
+    Warn,
+    /// Fails installing the package.
+    Error,
+    /// Downloads the zip file again once, and fails if the hash still mismatches.
+    RedownloadThenError,
+}
+
+impl FromStr for HashMismatchPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            "redownload-then-error" => Ok(Self::RedownloadThenError),
+            _ => Err(format!(
+                "unknown hash mismatch policy: {s} (expected warn, error, or redownload-then-error)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for HashMismatchPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashMismatchPolicy::Warn => f.write_str("warn"),
+            HashMismatchPolicy::Error => f.write_str("error"),
+            HashMismatchPolicy::RedownloadThenError => f.write_str("redownload-then-error"),
+        }
+    }
+}
+
+/// The error that the hash of the downloaded zip file doesn't match the expected hash.
+///
+/// Applying changes to the project reports this error as [`ApplyPendingChangesErr::HashMismatch`].
+/// Since [`crate::PackageInstaller`] returns [`io::Error`], the installer returns this error
+/// wrapped in [`io::Error`] with [`io::ErrorKind::InvalidData`].
+/// Use [`PackageHashMismatchError::from_io_error`] to get this error from [`io::Error`].
+///
+/// [`ApplyPendingChangesErr::HashMismatch`]: crate::unity_project::ApplyPendingChangesErr::HashMismatch
+#[derive(Debug)]
+pub struct PackageHashMismatchError {
+    package_name: Box<str>,
+    version: Version,
+    expected: Box<str>,
+    actual: Box<str>,
+    locked: bool,
+}
+
+impl PackageHashMismatchError {
+    pub fn from_io_error(error: &io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn actual(&self) -> &str {
+        &self.actual
+    }
+
+    /// Returns true if the expected hash is from the `locked` section of the project.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl fmt::Display for PackageHashMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = if self.locked {
+            "the locked section"
+        } else {
+            "the repository"
+        };
+        write!(
+            f,
+            "hash of the zip file for {} v{} does not match the hash in {source}: expected {}, actual {}",
+            self.package_name, self.version, self.expected, self.actual
+        )
     }
 }
 
+impl std::error::Error for PackageHashMismatchError {}
+
 impl<T: HttpClient> crate::PackageInstaller for PackageInstaller<'_, T> {
     async fn install_package(
         &self,
         io: &DefaultProjectIo,
         package: PackageInfo<'_>,
+        dest_folder: &Path,
+        locked_sha256: Option<&str>,
+        progress: &dyn InstallProgress,
         abort: &AbortCheck,
-    ) -> io::Result<()> {
+    ) -> io::Result<Option<Box<str>>> {
         abort.check()?;
         use crate::PackageInfoInner;
         log::debug!("adding package {}", package.name());
-        let dest_folder = PathBuf::from(format!("Packages/{}", package.name()));
+        let package_info = package;
         match package.inner {
             PackageInfoInner::Remote(package, user_repo) => {
-                let zip_file = get_package(self.io, self.http, user_repo, package).await?;
+                let (zip_file, zip_hash) = get_package(
+                    self,
+                    user_repo,
+                    package,
+                    locked_sha256,
+                    &|downloaded, total| progress.downloading(package_info, downloaded, total),
+                )
+                .await?;
 
                 // downloading may take a long time, so check abort again
                 abort.check()?;
 
+                if self.use_package_store {
+                    let on_extract =
+                        |extracted, total| progress.extracting(package_info, extracted, total);
+                    super::package_store::install_from_store(
+                        self.io,
+                        io,
+                        package,
+                        zip_file,
+                        &zip_hash,
+                        dest_folder,
+                        on_extract,
+                    )
+                    .await?;
+                    progress.installed(package_info);
+                    return Ok(Some(hex::encode(zip_hash).into()));
+                }
+
                 let zip_file = io::BufReader::new(zip_file);
 
                 debug!(
@@ -50,14 +245,18 @@ impl<T: HttpClient> crate::PackageInstaller for PackageInstaller<'_, T> {
                     package.version()
                 );
                 // remove dest folder before extract if exists
-                if let Err(e) = crate::utils::extract_zip(zip_file, io, &dest_folder).await {
+                let on_extract =
+                    |extracted, total| progress.extracting(package_info, extracted, total);
+                if let Err(e) =
+                    crate::utils::extract_zip(zip_file, io, dest_folder, on_extract).await
+                {
                     // if an error occurs, try to remove the dest folder
                     log::debug!(
                         "Error occurred while extracting zip file for {}@{}: {e}",
                         package.name(),
                         package.version(),
                     );
-                    let _ = io.remove_dir_all(&dest_folder).await;
+                    let _ = io.remove_dir_all(dest_folder).await;
                     return Err(e);
                 }
                 debug!(
@@ -66,22 +265,31 @@ impl<T: HttpClient> crate::PackageInstaller for PackageInstaller<'_, T> {
                     package.version()
                 );
 
-                Ok(())
+                progress.installed(package_info);
+                Ok(Some(hex::encode(zip_hash).into()))
             }
             PackageInfoInner::Local(_, path) => {
-                crate::utils::copy_recursive(self.io, path.into(), io, dest_folder).await?;
-                Ok(())
+                crate::utils::copy_recursive(self.io, path.into(), io, dest_folder.into()).await?;
+                progress.installed(package_info);
+                Ok(None)
             }
         }
     }
+
+    fn max_concurrency(&self) -> Option<NonZeroUsize> {
+        self.max_concurrency
+    }
 }
 
 async fn get_package<T: HttpClient>(
-    io: &DefaultEnvironmentIo,
-    http: Option<&T>,
+    installer: &PackageInstaller<'_, T>,
     repository: &LocalCachedRepository,
     package: &PackageManifest,
-) -> io::Result<TokioFile> {
+    locked_sha256: Option<&str>,
+    on_progress: &(dyn Fn(u64, Option<u64>) + Sync),
+) -> io::Result<(TokioFile, [u8; 256 / 8])> {
+    let io = installer.io;
+    let policy = installer.hash_mismatch_policy;
     let zip_file_name = format!("vrc-get-{}-{}.zip", &package.name(), package.version());
     let zip_path = PathBuf::from(format!(
         "{REPO_CACHE_FOLDER}/{}/{}",
@@ -90,11 +298,12 @@ async fn get_package<T: HttpClient>(
     ));
     let sha_path = zip_path.with_extension("zip.sha256");
 
-    if let Some(cache_file) =
-        try_load_package_cache(io, &zip_path, &sha_path, package.zip_sha_256()).await
-    {
+    // the hash in the locked section has priority over the hash in the repository
+    let expected_sha256 = locked_sha256.or(package.zip_sha_256());
+
+    if let Some(cache) = try_load_package_cache(io, &zip_path, &sha_path, expected_sha256).await {
         debug!("using cache for {}@{}", package.name(), package.version());
-        Ok(cache_file)
+        Ok(cache)
     } else {
         io.create_dir_all(zip_path.parent().unwrap()).await?;
 
@@ -111,37 +320,74 @@ async fn get_package<T: HttpClient>(
             ),
         );
 
-        let (zip_file, zip_hash) = download_package_zip(
-            http,
-            io,
-            &new_headers,
-            &zip_path,
-            &sha_path,
-            &zip_file_name,
-            package.url().ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "URL field of the package.json in the repository empty",
-                )
-            })?,
-        )
-        .await?;
+        let url = package.url().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "URL field of the package.json in the repository empty",
+            )
+        })?;
+
+        let mut redownloaded = false;
+        loop {
+            let (zip_file, zip_hash) = download_package_zip(
+                installer.http,
+                installer.retry_policy,
+                io,
+                &new_headers,
+                &zip_path,
+                url,
+                on_progress,
+            )
+            .await?;
+
+            let Some(mismatch) = check_hash(package, locked_sha256, zip_hash) else {
+                return Ok((zip_file, zip_hash));
+            };
 
-        if let Some(repo_hash) = package
-            .zip_sha_256()
-            .and_then(|x| <[u8; 256 / 8] as FromHex>::from_hex(x).ok())
-            && repo_hash != zip_hash
-        {
-            error!(
-                "Package hash mismatched! This will be hard error in the future!: {} v{}",
-                package.name(),
-                package.version()
-            );
-            //return None;
+            // mismatch with the locked section is always an error
+            if !mismatch.is_locked() && policy == HashMismatchPolicy::Warn {
+                warn!("{mismatch}");
+                return Ok((zip_file, zip_hash));
+            }
+
+            if policy == HashMismatchPolicy::RedownloadThenError && !redownloaded {
+                warn!("{mismatch}. downloading again");
+                redownloaded = true;
+                continue;
+            }
+
+            error!(gui_toast = false; "{mismatch}");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, mismatch));
         }
+    }
+}
+
+/// Checks the hash of the downloaded zip file with the locked hash and the hash in the repository.
+///
+/// The malformed expected hash is reported as a mismatch since the zip file cannot be verified.
+fn check_hash(
+    package: &PackageManifest,
+    locked_sha256: Option<&str>,
+    zip_hash: [u8; 256 / 8],
+) -> Option<PackageHashMismatchError> {
+    let (expected, locked) = if let Some(locked_sha256) = locked_sha256 {
+        (locked_sha256, true)
+    } else {
+        (package.zip_sha_256()?, false)
+    };
 
-        Ok(zip_file)
+    let expected_hash = <[u8; 256 / 8] as FromHex>::from_hex(expected);
+    if expected_hash.is_ok_and(|hash| hash == zip_hash) {
+        return None;
     }
+
+    Some(PackageHashMismatchError {
+        package_name: package.name().into(),
+        version: package.version().clone(),
+        expected: expected.into(),
+        actual: hex::encode(zip_hash).into(),
+        locked,
+    })
 }
 
 /// Try to load from the zip file
@@ -152,13 +398,13 @@ async fn get_package<T: HttpClient>(
 /// * `sha_path`: the path to sha256 file
 /// * `sha256`: sha256 hash if specified
 ///
-/// returns: Option<File> readable zip file or None
+/// returns: Option<(File, hash)> readable zip file and its hash or None
 async fn try_load_package_cache(
     io: &DefaultEnvironmentIo,
     zip_path: &Path,
     sha_path: &Path,
     sha256: Option<&str>,
-) -> Option<TokioFile> {
+) -> Option<(TokioFile, [u8; 256 / 8])> {
     let mut cache_file = io.open(zip_path).await.ok()?;
 
     let mut buf = [0u8; 256 / 4];
@@ -171,9 +417,10 @@ async fn try_load_package_cache(
 
     let hex: [u8; 256 / 8] = FromHex::from_hex(buf).ok()?;
 
-    // if stored sha doesn't match sha in repo: current cache is invalid
-    if let Some(repo_hash) = sha256.and_then(|x| <[u8; 256 / 8] as FromHex>::from_hex(x).ok())
-        && repo_hash != hex
+    // if stored sha doesn't match sha in repo: current cache is invalid.
+    // the cache cannot be verified with malformed sha, so download it again to report the mismatch
+    if let Some(sha256) = sha256
+        && !<[u8; 256 / 8] as FromHex>::from_hex(sha256).is_ok_and(|x| x == hex)
     {
         return None;
     }
@@ -189,55 +436,490 @@ async fn try_load_package_cache(
 
     cache_file.seek(SeekFrom::Start(0)).await.ok()?;
 
-    Some(cache_file)
+    Some((cache_file, hex))
 }
 
 /// downloads the zip file from the url to the specified path
 ///
+/// The zip file is downloaded to `<zip_path>.partial` first, and renamed to `zip_path` after completion.
+/// The partial file is kept if downloading fails so the next download can resume from it.
+/// Transient errors are retried with the retry policy.
+///
 /// # Arguments
 ///
 /// * `http`: http client. returns error if none
-/// * `zip_path`: the path to zip file
-/// * `sha_path`: the path to sha256 file
-/// * `zip_file_name`: the name of zip file. will be used in the sha file
+/// * `retry_policy`: the retry policy. defaults to the policy of the http client
+/// * `zip_path`: the path to zip file. the sha256 file is written next to the zip file
 /// * `url`: url to zip file
+/// * `on_progress`: called with the downloaded bytes and the total bytes if known
 ///
 /// returns: Result<File, Error> the readable zip file.
 async fn download_package_zip(
     http: Option<&impl HttpClient>,
+    retry_policy: Option<RetryPolicy>,
     io: &DefaultEnvironmentIo,
     headers: &IndexMap<&str, &str>,
     zip_path: &Path,
-    sha_path: &Path,
-    zip_file_name: &str,
     url: &Url,
+    on_progress: &(dyn Fn(u64, Option<u64>) + Sync),
 ) -> io::Result<(TokioFile, [u8; 256 / 8])> {
     let Some(http) = http else {
         return Err(io::Error::new(io::ErrorKind::NotFound, "Offline mode"));
     };
 
-    // file not found: err
-    let cache_file = io.create(zip_path).await?;
+    let retry_policy = retry_policy.unwrap_or_else(|| http.retry_policy());
+    let partial_path = zip_path.with_extension("zip.partial");
 
     debug!("Download started for {url}");
-    let mut response = pin!(http.get(url, headers).await?);
-
-    let mut writer = Sha256AsyncWrite::new(cache_file);
-    io::copy(&mut response, &mut writer).await?;
+    let mut attempt = 1;
+    loop {
+        match download_to_partial(http, io, headers, &partial_path, url, on_progress).await {
+            Ok(()) => break,
+            Err(DownloadError::Http(e))
+                if attempt < retry_policy.max_attempts() && http.is_transient_error(&e) =>
+            {
+                let backoff = retry_policy.backoff(attempt);
+                warn!(
+                    "error downloading {url}: {e}. retrying in {:.1} seconds ({attempt}/{})",
+                    backoff.as_secs_f32(),
+                    retry_policy.max_attempts(),
+                );
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            Err(DownloadError::Http(e)) => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("failed to download {url} after {attempt} attempt(s): {e}"),
+                ));
+            }
+            Err(DownloadError::Io(e)) => return Err(e),
+        }
+    }
     debug!("finished downloading {url}");
 
-    let (mut cache_file, hash) = writer.finalize();
-    let hash: [u8; 256 / 8] = hash.into();
+    io.rename(&partial_path, zip_path).await?;
+    remove_if_exists(io, &validator_path(&partial_path)).await?;
+
+    let mut cache_file = io.open(zip_path).await?;
+    let mut hasher = Sha256AsyncWrite::new(io::sink());
+    io::copy(&mut cache_file, &mut hasher).await?;
+    let hash: [u8; 256 / 8] = hasher.finalize().1.into();
 
-    cache_file.flush().await?;
     cache_file.seek(SeekFrom::Start(0)).await?;
 
-    // write sha file
+    // write sha file. the name of zip file is written in the sha file
+    let zip_file_name = zip_path.file_name().unwrap().to_string_lossy();
     io.write(
-        sha_path,
+        &zip_path.with_extension("zip.sha256"),
         format!("{} {zip_file_name}\n", hex::encode(&hash[..])).as_bytes(),
     )
     .await?;
 
     Ok((cache_file, hash))
 }
+
+/// The error while downloading to the partial file
+enum DownloadError {
+    /// The error from the http client, which may be retried
+    Http(io::Error),
+    /// The error from the file system
+    Io(io::Error),
+}
+
+/// downloads the zip file to the partial file, resuming from the existing partial file if possible
+///
+/// The validator (`ETag` or `Last-Modified`) of the resource is saved to `<partial_path>.validator`,
+/// and the partial file is only resumed if the server confirms the resource is unchanged with `If-Range`.
+async fn download_to_partial(
+    http: &impl HttpClient,
+    io: &DefaultEnvironmentIo,
+    headers: &IndexMap<&str, &str>,
+    partial_path: &Path,
+    url: &Url,
+    on_progress: &(dyn Fn(u64, Option<u64>) + Sync),
+) -> Result<(), DownloadError> {
+    let validator_path = validator_path(partial_path);
+    let mut partial_file = io.append(partial_path).await.map_err(DownloadError::Io)?;
+    let mut offset = (partial_file.seek(SeekFrom::End(0)).await).map_err(DownloadError::Io)?;
+
+    let validator = if offset != 0 {
+        read_validator(io, &validator_path)
+            .await
+            .map_err(DownloadError::Io)?
+    } else {
+        None
+    };
+
+    if offset != 0 && validator.is_none() {
+        // we cannot tell if the partial file is of the same resource
+        debug!("discarding partial download of {url} since the validator is unknown");
+        offset = 0;
+    }
+
+    if offset != 0 {
+        debug!("resuming download of {url} from {offset} bytes");
+    }
+
+    let (mut response, mut start, mut total, mut new_validator) = (http
+        .get_range(url, headers, offset, validator.as_deref())
+        .await)
+        .map_err(DownloadError::Http)?;
+
+    if start != offset && start != 0 {
+        // the server responded with an unexpected range, so download from the beginning
+        debug!("unexpected range for {url}: requested {offset} but got {start}. restarting");
+        (response, start, total, new_validator) =
+            (http.get_range(url, headers, 0, None).await).map_err(DownloadError::Http)?;
+        if start != 0 {
+            return Err(DownloadError::Http(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected range for {url}"),
+            )));
+        }
+    }
+
+    let mut response = pin!(response);
+
+    if start != offset || offset == 0 {
+        // the partial file is not resumed, so download from the beginning
+        partial_file = io.create(partial_path).await.map_err(DownloadError::Io)?;
+        match &new_validator {
+            Some(validator) => io.write(&validator_path, validator.as_bytes()).await,
+            None => remove_if_exists(io, &validator_path).await,
+        }
+        .map_err(DownloadError::Io)?;
+    }
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut downloaded = start;
+    on_progress(downloaded, total);
+    let result = loop {
+        let read = match response.read(&mut buffer).await {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(e) => break Err(DownloadError::Http(e)),
+        };
+        if let Err(e) = partial_file.write_all(&buffer[..read]).await {
+            break Err(DownloadError::Io(e));
+        }
+        downloaded += read as u64;
+        on_progress(downloaded, total);
+    };
+
+    // flush even if downloading failed to keep the downloaded part for resuming
+    partial_file.flush().await.map_err(DownloadError::Io)?;
+    result?;
+
+    if total.is_some_and(|total| downloaded < total) {
+        return Err(DownloadError::Http(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed before downloading completes",
+        )));
+    }
+
+    Ok(())
+}
+
+fn validator_path(partial_path: &Path) -> PathBuf {
+    partial_path.with_extension("partial.validator")
+}
+
+async fn read_validator(io: &DefaultEnvironmentIo, path: &Path) -> io::Result<Option<Box<str>>> {
+    let file = match io.open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let validator = read_to_end(file).await?;
+    Ok(String::from_utf8(validator)
+        .ok()
+        .filter(|x| !x.is_empty())
+        .map(Into::into))
+}
+
+async fn remove_if_exists(io: &DefaultEnvironmentIo, path: &Path) -> io::Result<()> {
+    match io.remove_file(path).await {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(zip_sha256: Option<&str>) -> PackageManifest {
+        serde_json::from_value(serde_json::json!({
+            "name": "com.anatawa12.package",
+            "version": "1.0.0",
+            "zipSHA256": zip_sha256,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn check_zip_hash() {
+        let hash = [0xab; 256 / 8];
+        let hex = "ab".repeat(256 / 8);
+        let other = "cd".repeat(256 / 8);
+
+        assert!(check_hash(&manifest(None), None, hash).is_none());
+        assert!(check_hash(&manifest(Some(&hex)), None, hash).is_none());
+        assert!(check_hash(&manifest(Some(&other)), Some(&hex), hash).is_none());
+
+        let mismatch = check_hash(&manifest(Some(&other)), None, hash).unwrap();
+        assert!(!mismatch.is_locked());
+        assert_eq!(mismatch.expected(), other);
+        assert_eq!(mismatch.actual(), hex);
+
+        let mismatch = check_hash(&manifest(Some(&hex)), Some(&other), hash).unwrap();
+        assert!(mismatch.is_locked());
+    }
+
+    #[test]
+    fn malformed_hash_is_mismatch() {
+        let hash = [0xab; 256 / 8];
+
+        let mismatch = check_hash(&manifest(Some("not a hash")), None, hash).unwrap();
+        assert!(!mismatch.is_locked());
+        assert_eq!(mismatch.expected(), "not a hash");
+
+        // too short
+        let mismatch = check_hash(&manifest(None), Some("abab"), hash).unwrap();
+        assert!(mismatch.is_locked());
+    }
+
+    /// The http client which serves `body` and supports range requests with `If-Range`
+    struct RangeHttp {
+        body: Vec<u8>,
+        etag: &'static str,
+        /// responds with a range different from the requested one
+        wrong_start: bool,
+        requests: std::sync::Mutex<Vec<(u64, Option<String>)>>,
+    }
+
+    impl RangeHttp {
+        fn new(body: &[u8], etag: &'static str) -> Self {
+            Self {
+                body: body.to_vec(),
+                etag,
+                wrong_start: false,
+                requests: Default::default(),
+            }
+        }
+
+        fn requests(&self) -> Vec<(u64, Option<String>)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpClient for RangeHttp {
+        async fn get(
+            &self,
+            _: &Url,
+            _: &IndexMap<&str, &str>,
+        ) -> io::Result<(impl AsyncRead, Option<u64>)> {
+            Ok((
+                futures::io::Cursor::new(self.body.clone()),
+                Some(self.body.len() as u64),
+            ))
+        }
+
+        async fn get_with_etag(
+            &self,
+            _: &Url,
+            _: &IndexMap<Box<str>, Box<str>>,
+            _: Option<&str>,
+        ) -> io::Result<Option<(impl AsyncRead, Option<Box<str>>)>> {
+            Ok(Some((futures::io::Cursor::new(self.body.clone()), None)))
+        }
+
+        async fn get_range(
+            &self,
+            _: &Url,
+            _: &IndexMap<&str, &str>,
+            offset: u64,
+            validator: Option<&str>,
+        ) -> io::Result<(impl AsyncRead, u64, Option<u64>, Option<Box<str>>)> {
+            (self.requests.lock().unwrap()).push((offset, validator.map(Into::into)));
+            let start = match offset {
+                0 => 0,
+                _ if validator != Some(self.etag) => 0,
+                _ if self.wrong_start => offset / 2,
+                _ => offset,
+            };
+            Ok((
+                futures::io::Cursor::new(self.body[start as usize..].to_vec()),
+                start,
+                Some(self.body.len() as u64),
+                Some(self.etag.into()),
+            ))
+        }
+    }
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    /// The io on a temporary folder removed on drop
+    struct TempIo {
+        io: DefaultEnvironmentIo,
+        root: PathBuf,
+    }
+
+    impl std::ops::Deref for TempIo {
+        type Target = DefaultEnvironmentIo;
+
+        fn deref(&self) -> &Self::Target {
+            &self.io
+        }
+    }
+
+    impl Drop for TempIo {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.root).ok();
+        }
+    }
+
+    fn new_io(name: &str) -> TempIo {
+        let root = std::env::temp_dir().join(format!(
+            "vrc-get-vpm-package-installer-{name}-{}",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::create_dir_all(&root).unwrap();
+        TempIo {
+            io: DefaultEnvironmentIo::new(root.clone().into_boxed_path()),
+            root,
+        }
+    }
+
+    fn download(http: &RangeHttp, io: &DefaultEnvironmentIo) {
+        let url = Url::parse("https://example.com/package.zip").unwrap();
+        let result = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(download_to_partial(
+                http,
+                io,
+                &IndexMap::new(),
+                "package.zip.partial".as_ref(),
+                &url,
+                &|_, _| {},
+            ));
+        assert!(result.is_ok());
+    }
+
+    fn partial_file(io: &DefaultEnvironmentIo) -> Vec<u8> {
+        std::fs::read(io.resolve("package.zip.partial".as_ref())).unwrap()
+    }
+
+    fn validator_file(io: &DefaultEnvironmentIo) -> Option<String> {
+        std::fs::read_to_string(io.resolve("package.zip.partial.validator".as_ref())).ok()
+    }
+
+    fn write_partial(io: &DefaultEnvironmentIo, partial: &[u8], validator: Option<&str>) {
+        std::fs::write(io.resolve("package.zip.partial".as_ref()), partial).unwrap();
+        if let Some(validator) = validator {
+            std::fs::write(
+                io.resolve("package.zip.partial.validator".as_ref()),
+                validator,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn download_from_beginning() {
+        let io = new_io("download_from_beginning");
+        let http = RangeHttp::new(BODY, "\"v1\"");
+
+        download(&http, &io);
+
+        assert_eq!(http.requests(), vec![(0, None)]);
+        assert_eq!(partial_file(&io), BODY);
+        assert_eq!(validator_file(&io).as_deref(), Some("\"v1\""));
+    }
+
+    #[test]
+    fn resume_download() {
+        let io = new_io("resume_download");
+        let http = RangeHttp::new(BODY, "\"v1\"");
+        write_partial(&io, &BODY[..10], Some("\"v1\""));
+
+        download(&http, &io);
+
+        assert_eq!(http.requests(), vec![(10, Some("\"v1\"".into()))]);
+        assert_eq!(partial_file(&io), BODY);
+    }
+
+    #[test]
+    fn restart_without_validator() {
+        let io = new_io("restart_without_validator");
+        let http = RangeHttp::new(BODY, "\"v1\"");
+        write_partial(&io, b"garbage", None);
+
+        download(&http, &io);
+
+        assert_eq!(http.requests(), vec![(0, None)]);
+        assert_eq!(partial_file(&io), BODY);
+        assert_eq!(validator_file(&io).as_deref(), Some("\"v1\""));
+    }
+
+    #[test]
+    fn restart_if_resource_changed() {
+        let io = new_io("restart_if_resource_changed");
+        let http = RangeHttp::new(BODY, "\"v2\"");
+        write_partial(&io, b"old content", Some("\"v1\""));
+
+        download(&http, &io);
+
+        assert_eq!(http.requests(), vec![(11, Some("\"v1\"".into()))]);
+        assert_eq!(partial_file(&io), BODY);
+        assert_eq!(validator_file(&io).as_deref(), Some("\"v2\""));
+    }
+
+    #[test]
+    fn restart_on_unexpected_range() {
+        let io = new_io("restart_on_unexpected_range");
+        let mut http = RangeHttp::new(BODY, "\"v1\"");
+        http.wrong_start = true;
+        write_partial(&io, &BODY[..10], Some("\"v1\""));
+
+        download(&http, &io);
+
+        assert_eq!(
+            http.requests(),
+            vec![(10, Some("\"v1\"".into())), (0, None)]
+        );
+        assert_eq!(partial_file(&io), BODY);
+    }
+
+    #[test]
+    fn validator_removed_after_download() {
+        let io = new_io("validator_removed_after_download");
+        let http = RangeHttp::new(BODY, "\"v1\"");
+        write_partial(&io, &BODY[..10], Some("\"v1\""));
+        let url = Url::parse("https://example.com/package.zip").unwrap();
+
+        use sha2::Digest as _;
+
+        let (_, hash) = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(download_package_zip(
+                Some(&http),
+                Some(RetryPolicy::no_retry()),
+                &io,
+                &IndexMap::new(),
+                "package.zip".as_ref(),
+                &url,
+                &|_, _| {},
+            ))
+            .unwrap();
+
+        let zip = std::fs::read(io.resolve("package.zip".as_ref())).unwrap();
+        assert_eq!(zip, BODY);
+        assert_eq!(hash, <[u8; 256 / 8]>::from(sha2::Sha256::digest(BODY)));
+        assert!(!io.resolve("package.zip.partial".as_ref()).exists());
+        assert_eq!(validator_file(&io), None);
+    }
+}
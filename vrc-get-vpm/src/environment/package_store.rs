@@ -0,0 +1,402 @@
+//! The global store of extracted packages.
+//!
+//! Each entry of the store is the extracted zip file of a package version, keyed by the name,
+//! the version, and the sha256 hash of the zip file.
+//! Packages are installed to projects by cloning the files in the entry with copy-on-write
+//! if the file system supports it, or by hard-linking the files otherwise,
+//! so projects using the same package version share the disk space.
+//!
+//! Since hard-linked files share the content with the store and other projects,
+//! files in the store are made read-only, and the size and the modification time of the files
+//! are recorded in the index of the entry to detect modified entries before reusing them.
+//!
+//! Files are copied instead if both fail, e.g. the project is on another drive.
+
+use super::package_cache::load_locked_packages;
+use crate::io::{DefaultEnvironmentIo, DefaultProjectIo, DirEntry, IoTrait, TokioFile};
+use crate::version::Version;
+use crate::{PackageManifest, io};
+use futures::prelude::*;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::UNIX_EPOCH;
+
+const PACKAGE_STORE_FOLDER: &str = "vrc-get/package-store";
+/// The prefix of the folders being extracted.
+const TEMP_PREFIX: &str = ".tmp-";
+/// The name of the file in each entry which records the files in the entry.
+/// This file is not installed to projects.
+const INDEX_FILE: &str = ".vrc-get-store-index.json";
+
+/// An entry of the package store.
+#[derive(Debug, Clone)]
+pub struct PackageStoreEntry {
+    name: Box<str>,
+    version: Version,
+    sha256: Box<str>,
+}
+
+impl PackageStoreEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// The hex-encoded sha256 hash of the zip file the entry extracted from.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    fn path(&self) -> PathBuf {
+        entry_path(&self.name, &self.version, &self.sha256)
+    }
+}
+
+fn entry_path(name: &str, version: &Version, sha256: &str) -> PathBuf {
+    PathBuf::from(format!("{PACKAGE_STORE_FOLDER}/{name}/{version}-{sha256}"))
+}
+
+/// Installs the package from the store to `dest_folder`, extracting the zip file to the store if not stored yet.
+pub(super) async fn install_from_store(
+    env_io: &DefaultEnvironmentIo,
+    project_io: &DefaultProjectIo,
+    package: &PackageManifest,
+    zip_file: TokioFile,
+    zip_hash: &[u8; 256 / 8],
+    dest_folder: &Path,
+    on_extract: impl Fn(usize, usize),
+) -> io::Result<()> {
+    let entry = entry_path(package.name(), package.version(), &hex::encode(zip_hash));
+
+    if env_io.is_dir(&entry).await && !verify_entry(env_io, &entry).await? {
+        warn!(
+            "{}@{} in the package store has been modified. extracting again",
+            package.name(),
+            package.version()
+        );
+        env_io.remove_dir_all(&entry).await?;
+    }
+
+    if !env_io.is_dir(&entry).await {
+        debug!(
+            "extracting {}@{} to the package store",
+            package.name(),
+            package.version()
+        );
+        // extract to the temporary folder and rename to make the entry appear atomically
+        let temp = entry.with_file_name(format!("{TEMP_PREFIX}{}", uuid::Uuid::new_v4()));
+        let zip_file = io::BufReader::new(zip_file);
+        let extracted = async {
+            crate::utils::extract_zip(zip_file, env_io, &temp, on_extract).await?;
+            seal_entry(env_io, &temp).await
+        };
+        if let Err(e) = extracted.await {
+            env_io.remove_dir_all(&temp).await.ok();
+            return Err(e);
+        }
+        if let Err(e) = env_io.rename(&temp, &entry).await {
+            env_io.remove_dir_all(&temp).await.ok();
+            // another process may have extracted the same package
+            if !env_io.is_dir(&entry).await {
+                return Err(e);
+            }
+        }
+    }
+
+    if let Err(e) = link_recursive(env_io, &entry, project_io, dest_folder).await {
+        project_io.remove_dir_all(dest_folder).await.ok();
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// The size and the modification time of a file in an entry.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+struct IndexedFile {
+    size: u64,
+    /// nanoseconds since the unix epoch. `None` if the platform doesn't support it
+    modified: Option<u128>,
+}
+
+/// Lists the files in the entry with their size and modification time, excluding the index file.
+async fn index_entry(
+    io: &DefaultEnvironmentIo,
+    entry: &Path,
+) -> io::Result<BTreeMap<String, IndexedFile>> {
+    let mut files = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_front(String::new());
+
+    while let Some(relative) = queue.pop_back() {
+        let mut iter = io.read_dir(&entry.join(&relative)).await?;
+        while let Some(file) = iter.try_next().await? {
+            let name = file.file_name().to_string_lossy().into_owned();
+            let relative = match relative.as_str() {
+                "" => name,
+                dir => format!("{dir}/{name}"),
+            };
+            if relative == INDEX_FILE {
+                continue;
+            }
+            let metadata = file.metadata().await?;
+            if metadata.is_dir() {
+                queue.push_front(relative);
+                continue;
+            }
+            let modified = (metadata.modified())
+                .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+                .map(|x| x.as_nanos());
+            let size = metadata.size();
+            files.insert(relative, IndexedFile { size, modified });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Makes the files in the extracted entry read-only and writes the index of the entry.
+async fn seal_entry(io: &DefaultEnvironmentIo, entry: &Path) -> io::Result<()> {
+    let files = index_entry(io, entry).await?;
+    for relative in files.keys() {
+        let path = io.resolve(&entry.join(relative));
+        let mut permissions = tokio::fs::metadata(&path).await?.permissions();
+        permissions.set_readonly(true);
+        tokio::fs::set_permissions(&path, permissions).await?;
+    }
+    let index = serde_json::to_vec(&files).map_err(io::Error::other)?;
+    io.write(&entry.join(INDEX_FILE), &index).await
+}
+
+/// Returns whether the files in the entry are the same as the index of the entry.
+///
+/// Entries without the index, e.g. made by older versions, are also treated as modified.
+async fn verify_entry(io: &DefaultEnvironmentIo, entry: &Path) -> io::Result<bool> {
+    let index = match io.open(&entry.join(INDEX_FILE)).await {
+        Ok(file) => crate::utils::read_to_end(file).await?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let Ok(index) = serde_json::from_slice::<BTreeMap<String, IndexedFile>>(&index) else {
+        return Ok(false);
+    };
+    Ok(index == index_entry(io, entry).await?)
+}
+
+/// Installs the files in `src_dir` to `dst_dir`.
+///
+/// Files are cloned with copy-on-write if supported, hard-linked otherwise,
+/// and copied if both fail.
+async fn link_recursive(
+    src_io: &DefaultEnvironmentIo,
+    src_dir: &Path,
+    dst_io: &DefaultProjectIo,
+    dst_dir: &Path,
+) -> io::Result<()> {
+    let root = dst_dir;
+    let mut queue = VecDeque::new();
+    queue.push_front((src_dir.to_owned(), dst_dir.to_owned()));
+
+    let mut reflink_fallback = false;
+    let mut copy_fallback = false;
+
+    while let Some((src_dir, dst_dir)) = queue.pop_back() {
+        let mut iter = src_io.read_dir(&src_dir).await?;
+        dst_io.create_dir_all(&dst_dir).await?;
+        while let Some(entry) = iter.try_next().await? {
+            let src = src_dir.join(entry.file_name());
+            let dst = dst_dir.join(entry.file_name());
+
+            if entry.file_type().await?.is_dir() {
+                queue.push_front((src, dst));
+                continue;
+            }
+
+            if dst_dir == root && entry.file_name() == INDEX_FILE {
+                continue;
+            }
+
+            let src_abs = src_io.resolve(&src);
+            let dst_abs = dst_io.location().join(&dst);
+
+            if !reflink_fallback {
+                match reflink(&src_abs, &dst_abs) {
+                    Ok(()) => continue,
+                    Err(e) => {
+                        debug!("cloning {} failed, hard-linking: {e}", src_abs.display());
+                        reflink_fallback = true;
+                    }
+                }
+            }
+
+            if !copy_fallback {
+                match tokio::fs::hard_link(&src_abs, &dst_abs).await {
+                    Ok(()) => continue,
+                    Err(e) => {
+                        debug!("hard-linking {} failed, copying: {e}", src_abs.display());
+                        copy_fallback = true;
+                    }
+                }
+            }
+
+            let mut src_file = src_io.open(&src).await?;
+            let mut dst_file = dst_io.create_new(&dst).await?;
+            io::copy(&mut src_file, &mut dst_file).await?;
+            dst_file.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Clones the file with copy-on-write.
+///
+/// `dst` must not exist. The cloned file doesn't share the content with `src` after modification.
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+fn reflink(src: &Path, dst: &Path) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // _IOW(0x94, 9, int) in linux/fs.h
+    const FICLONE: libc::c_ulong = 0x40049409;
+
+    let src_file = std::fs::File::open(src)?;
+    let dst_file = (std::fs::OpenOptions::new())
+        .write(true)
+        .create_new(true)
+        .open(dst)?;
+    // SAFETY: both file descriptors are valid while the files are open
+    let result = unsafe { libc::ioctl(dst_file.as_raw_fd(), FICLONE as _, src_file.as_raw_fd()) };
+    if result == -1 {
+        let error = std::io::Error::last_os_error();
+        drop(dst_file);
+        std::fs::remove_file(dst).ok();
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Clones the file with copy-on-write.
+///
+/// `dst` must not exist. The cloned file doesn't share the content with `src` after modification.
+#[cfg(target_os = "macos")]
+#[allow(unsafe_code)]
+fn reflink(src: &Path, dst: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let src = CString::new(src.as_os_str().as_bytes())?;
+    let dst = CString::new(dst.as_os_str().as_bytes())?;
+    // SAFETY: both paths are valid nul-terminated strings
+    let result = unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Clones the file with copy-on-write.
+///
+/// Not supported on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_: &Path, _: &Path) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Returns the entries in the package store.
+pub async fn package_store_entries(
+    io: &DefaultEnvironmentIo,
+) -> io::Result<Vec<PackageStoreEntry>> {
+    let mut entries = Vec::new();
+
+    let mut packages = match io.read_dir(PACKAGE_STORE_FOLDER.as_ref()).await {
+        Ok(packages) => packages,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e),
+    };
+
+    while let Some(package) = packages.try_next().await? {
+        let Ok(name) = package.file_name().into_string() else {
+            continue;
+        };
+        let package_folder = Path::new(PACKAGE_STORE_FOLDER).join(&name);
+        let mut versions = io.read_dir(&package_folder).await?;
+        while let Some(version) = versions.try_next().await? {
+            let Some(file_name) = version.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            // the version may contain '-' for prerelease, so split at the last '-'
+            let Some((version, sha256)) = file_name.rsplit_once('-') else {
+                continue;
+            };
+            let Ok(version) = Version::from_str(version) else {
+                continue;
+            };
+            entries.push(PackageStoreEntry {
+                name: name.as_str().into(),
+                version,
+                sha256: sha256.into(),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Removes the entries in the package store not used by any of `projects`.
+///
+/// An entry is used if the package is locked to the version in the project,
+/// and the hash of the locked package is the same as the entry if recorded.
+/// Since projects have their own clones or hard links of the files, removing an entry does not break projects
+/// not listed in `projects`, but they no longer share the disk space.
+///
+/// Returns the removed entries.
+pub async fn gc_package_store(
+    io: &DefaultEnvironmentIo,
+    projects: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> io::Result<Vec<PackageStoreEntry>> {
+    let used = load_locked_packages(projects).await;
+
+    let mut removed = Vec::new();
+
+    for entry in package_store_entries(io).await? {
+        let is_used = used.iter().any(|locked| {
+            locked.name == entry.name
+                && locked.version == entry.version
+                && (locked.sha256.as_ref()).is_none_or(|x| *x == entry.sha256)
+        });
+        if is_used {
+            continue;
+        }
+
+        debug!(
+            "removing {}@{} from package store",
+            entry.name, entry.version
+        );
+        io.remove_dir_all(&entry.path()).await?;
+        removed.push(entry);
+    }
+
+    // remove folders left by interrupted extraction and empty package folders
+    if let Ok(mut packages) = io.read_dir(PACKAGE_STORE_FOLDER.as_ref()).await {
+        while let Some(package) = packages.try_next().await? {
+            let package_folder = Path::new(PACKAGE_STORE_FOLDER).join(package.file_name());
+            let mut versions = io.read_dir(&package_folder).await?;
+            while let Some(version) = versions.try_next().await? {
+                if (version.file_name().to_str()).is_some_and(|x| x.starts_with(TEMP_PREFIX)) {
+                    io.remove_dir_all(&package_folder.join(version.file_name()))
+                        .await?;
+                }
+            }
+            io.remove_dir(&package_folder).await.ok();
+        }
+    }
+
+    Ok(removed)
+}
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use std::fmt::Write;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::{Path, PathBuf};
 
 use indexmap::IndexMap;
@@ -7,12 +8,13 @@ use url::Url;
 
 use crate::environment::vpm_settings::VpmSettings;
 use crate::environment::vrc_get_settings::VrcGetSettings;
-use crate::environment::{AddUserPackageResult, PackageCollection};
+use crate::environment::{AddUserPackageResult, HashMismatchPolicy, PackageCollection};
 use crate::io::DefaultEnvironmentIo;
 use crate::package_manifest::LooseManifest;
 use crate::repository::RemoteRepository;
+use crate::unity_project::ResolverMode;
 use crate::utils::{normalize_path, try_load_json};
-use crate::{UserRepoSetting, io};
+use crate::{RetryPolicy, UserRepoSetting, io};
 
 #[derive(Debug, Clone)]
 pub struct Settings {
@@ -72,14 +74,14 @@ impl Settings {
     pub fn set_unity_hub_path(&mut self, value: &str) {
         self.vpm.set_unity_hub(value);
     }
-}
 
-#[cfg(feature = "experimental-project-management")]
-impl Settings {
     pub fn user_projects(&self) -> Option<&[Box<str>]> {
         self.vpm.user_projects()
     }
+}
 
+#[cfg(feature = "experimental-project-management")]
+impl Settings {
     pub fn retain_user_projects(&mut self, f: impl FnMut(&str) -> bool) -> Option<Vec<Box<str>>> {
         self.vpm.retain_user_projects(f)
     }
@@ -128,6 +130,42 @@ impl Settings {
     pub fn ignore_official_repository(&self) -> bool {
         self.vrc_get.ignore_official_repository()
     }
+
+    pub fn hash_mismatch_policy(&self) -> HashMismatchPolicy {
+        self.vrc_get.hash_mismatch_policy()
+    }
+
+    pub fn resolver_mode(&self) -> ResolverMode {
+        self.vrc_get.resolver_mode()
+    }
+
+    /// The maximum number of packages downloaded at once.
+    pub fn max_concurrent_downloads(&self) -> NonZeroUsize {
+        self.vrc_get.max_concurrent_downloads()
+    }
+
+    /// The maximum number of attempts to download a package, including the first attempt.
+    ///
+    /// `None` means the default retry policy of the http client.
+    pub fn download_attempts(&self) -> Option<NonZeroU32> {
+        self.vrc_get.download_attempts()
+    }
+
+    /// The policy to retry downloading packages with [`download_attempts`](Self::download_attempts).
+    pub fn download_retry_policy(&self) -> Option<RetryPolicy> {
+        (self.download_attempts())
+            .map(|attempts| RetryPolicy::default().with_max_attempts(attempts))
+    }
+
+    /// Whether to install packages from the shared package store with copy-on-write clones or hard links.
+    pub fn use_package_store(&self) -> bool {
+        self.vrc_get.use_package_store()
+    }
+
+    /// The folders searched for Unity editors not installed with Unity Hub.
+    pub fn unity_search_paths(&self) -> &[PathBuf] {
+        self.vrc_get.unity_search_paths()
+    }
 }
 
 /// User Package Managements
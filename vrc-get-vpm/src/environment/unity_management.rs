@@ -32,6 +32,26 @@ impl VccDatabaseConnection {
         Ok(())
     }
 
+    /// Adds the Unity installation unless the path is already registered.
+    ///
+    /// Returns `false` if the path is already registered.
+    pub fn add_unity_installation_if_absent(
+        &mut self,
+        path: &str,
+        version: UnityVersion,
+    ) -> io::Result<bool> {
+        check_absolute_path(path)?;
+        let normalized = normalize_path(path.as_ref());
+        let registered = (self.db.get_all(COLLECTION))
+            .filter_map(|x| x[PATH].as_str())
+            .any(|x| normalize_path(x.as_ref()) == normalized);
+        if registered {
+            return Ok(false);
+        }
+        self.add_unity_installation(path, version)?;
+        Ok(true)
+    }
+
     fn add_unity_installation_internal(
         db: &mut LiteDBFile,
         path: &str,
@@ -82,6 +102,11 @@ impl VccDatabaseConnection {
         revision_match.or(minor_match).or(major_match)
     }
 
+    /// Updates the list of Unity with the list from Unity Hub and the file system.
+    ///
+    /// Unity not found in the file system is removed, and Unity from Unity Hub is added.
+    /// Unity added by other ways, like [`Self::add_unity_installation_if_absent`] for search paths,
+    /// is kept as long as it exists.
     pub async fn update_unity_from_unity_hub_and_fs(
         &mut self,
         path_and_version_from_hub: &[(UnityVersion, PathBuf)],
@@ -197,6 +197,10 @@ impl VpmSettings {
         self.parsed.path_to_unity_hub = path.into();
     }
 
+    pub(crate) fn user_projects(&self) -> Option<&[Box<str>]> {
+        self.parsed.user_projects.as_deref()
+    }
+
     pub async fn save(&self, io: &DefaultEnvironmentIo) -> io::Result<()> {
         save_json(io, JSON_PATH.as_ref(), &self.parsed).await
     }
@@ -204,10 +208,6 @@ impl VpmSettings {
 
 #[cfg(feature = "experimental-project-management")]
 impl VpmSettings {
-    pub(crate) fn user_projects(&self) -> Option<&[Box<str>]> {
-        self.parsed.user_projects.as_deref()
-    }
-
     pub(crate) fn retain_user_projects(
         &mut self,
         mut f: impl FnMut(&str) -> bool,
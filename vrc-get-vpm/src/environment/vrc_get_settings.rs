@@ -1,7 +1,14 @@
+use crate::environment::HashMismatchPolicy;
 use crate::io;
 use crate::io::{DefaultEnvironmentIo, IoTrait};
+use crate::unity_project::ResolverMode;
 use crate::utils::{parse_json_file, read_to_end};
 use serde::{Deserialize, Serialize};
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::PathBuf;
+
+/// The number of packages downloaded at once if not specified in the settings.
+const DEFAULT_MAX_CONCURRENT_DOWNLOADS: NonZeroUsize = NonZeroUsize::new(4).unwrap();
 
 /// since this file is vrc-get specific, additional keys can be removed
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -11,6 +18,18 @@ struct AsJson {
     ignore_official_repository: bool,
     #[serde(default)]
     ignore_curated_repository: bool,
+    #[serde(default)]
+    hash_mismatch_policy: HashMismatchPolicy,
+    #[serde(default)]
+    resolver_mode: ResolverMode,
+    #[serde(default)]
+    max_concurrent_downloads: Option<NonZeroUsize>,
+    #[serde(default)]
+    download_attempts: Option<NonZeroU32>,
+    #[serde(default)]
+    use_package_store: bool,
+    #[serde(default)]
+    unity_search_paths: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,7 +43,7 @@ impl VrcGetSettings {
     pub async fn load(io: &DefaultEnvironmentIo) -> io::Result<Self> {
         //let parsed = load_json_or_default(io, JSON_PATH.as_ref()).await?;
 
-        let parsed = match io.open(JSON_PATH.as_ref()).await {
+        let mut parsed: AsJson = match io.open(JSON_PATH.as_ref()).await {
             Ok(file) => match read_to_end(file).await? {
                 vec if vec.is_empty() => Default::default(),
                 vec => {
@@ -36,6 +55,18 @@ impl VrcGetSettings {
             Err(e) => return Err(e),
         };
 
+        // relative paths would be resolved against the current directory, which differs per launch
+        parsed.unity_search_paths.retain(|path| {
+            let absolute = path.is_absolute();
+            if !absolute {
+                log::warn!(
+                    "relative path {} in unitySearchPaths is ignored",
+                    path.display()
+                );
+            }
+            absolute
+        });
+
         Ok(Self { parsed })
     }
 
@@ -46,4 +77,30 @@ impl VrcGetSettings {
     pub fn ignore_curated_repository(&self) -> bool {
         self.parsed.ignore_curated_repository
     }
+
+    pub fn hash_mismatch_policy(&self) -> HashMismatchPolicy {
+        self.parsed.hash_mismatch_policy
+    }
+
+    pub fn resolver_mode(&self) -> ResolverMode {
+        self.parsed.resolver_mode
+    }
+
+    pub fn max_concurrent_downloads(&self) -> NonZeroUsize {
+        self.parsed
+            .max_concurrent_downloads
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS)
+    }
+
+    pub fn download_attempts(&self) -> Option<NonZeroU32> {
+        self.parsed.download_attempts
+    }
+
+    pub fn use_package_store(&self) -> bool {
+        self.parsed.use_package_store
+    }
+
+    pub fn unity_search_paths(&self) -> &[PathBuf] {
+        &self.parsed.unity_search_paths
+    }
 }
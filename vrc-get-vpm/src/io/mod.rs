@@ -1,6 +1,7 @@
 use std::ffi::OsString;
 use std::future::Future;
 use std::path::Path;
+use std::time::SystemTime;
 
 pub(crate) use futures::Stream;
 pub(crate) use futures::io::{
@@ -58,6 +59,8 @@ pub trait IoTrait: Sync {
     fn create_new(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
     fn create(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
     fn open(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
+    /// Opens the file to append, creating the file if it doesn't exist.
+    fn append(&self, path: &Path) -> impl Future<Output = Result<Self::FileStream>> + Send;
 }
 
 pub trait FileStream: AsyncRead + AsyncWrite + AsyncSeek + Unpin + Send {}
@@ -104,18 +107,24 @@ impl From<std::fs::FileType> for FileType {
 #[derive(Debug, Clone)]
 pub struct Metadata {
     file_type: FileType,
+    size: u64,
+    modified: Option<SystemTime>,
 }
 
 impl Metadata {
     pub fn file() -> Self {
         Self {
             file_type: FileType::file(),
+            size: 0,
+            modified: None,
         }
     }
 
     pub fn dir() -> Self {
         Self {
             file_type: FileType::dir(),
+            size: 0,
+            modified: None,
         }
     }
 
@@ -130,12 +139,24 @@ impl Metadata {
     pub fn is_dir(&self) -> bool {
         self.file_type.is_dir
     }
+
+    /// The size of the file in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The last modification time if supported by the platform
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
 }
 
 impl From<std::fs::Metadata> for Metadata {
     fn from(value: std::fs::Metadata) -> Self {
         Self {
             file_type: value.file_type().into(),
+            size: value.len(),
+            modified: value.modified().ok(),
         }
     }
 }
@@ -290,6 +290,16 @@ impl<T: TokioIoTraitImpl + Sync> IoTrait for T {
     async fn open(&self, path: &Path) -> io::Result<Self::FileStream> {
         Ok(fs::File::open(self.resolve(path)?).await?.compat())
     }
+
+    async fn append(&self, path: &Path) -> io::Result<Self::FileStream> {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(self.resolve(path)?)
+            .map_ok(|file| file.compat())
+            .await
+    }
 }
 
 impl FileStream for tokio_util::compat::Compat<fs::File> {}
@@ -12,6 +12,7 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 
 use version::{ReleaseType, UnityVersion, Version, VersionRange};
 
+pub mod advisory;
 pub mod environment;
 pub mod io;
 mod package_manifest;
@@ -27,18 +28,21 @@ pub mod repositories_file;
 
 #[cfg(feature = "unity")]
 pub mod unity;
-#[cfg(feature = "unity-hub")]
+#[cfg(any(feature = "unity-hub", test))]
 pub mod unity_hub;
 
 use crate::repository::local::LocalCachedRepository;
 
 pub use package_manifest::PackageManifest;
 pub use package_manifest::PartialUnityVersion;
+pub use package_manifest::{ManifestIssue, ManifestIssueSeverity, PackageManifestLint};
 pub use structs::setting::UserRepoSetting;
 pub use traits::AbortCheck;
 pub use traits::HttpClient;
+pub use traits::InstallProgress;
 pub use traits::PackageCollection;
 pub use traits::PackageInstaller;
+pub use traits::RetryPolicy;
 pub use unity_project::UnityProject;
 pub use version_selector::VersionSelector;
 
@@ -143,6 +147,10 @@ impl<'a> PackageInfo<'a> {
         self.package_json().is_yanked()
     }
 
+    pub fn yank_reason(self) -> Option<&'a str> {
+        self.package_json().yank_reason()
+    }
+
     pub fn display_name(self) -> Option<&'a str> {
         self.package_json().display_name()
     }
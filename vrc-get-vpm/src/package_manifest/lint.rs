@@ -0,0 +1,356 @@
+//! Checking `package.json` of packages for problems.
+
+use crate::unity_project::Guid;
+use crate::utils::read_zip_entry;
+use crate::{PackageCollection, PackageManifest, io};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use url::Url;
+
+/// The severity of a [`ManifestIssue`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ManifestIssueSeverity {
+    /// The package cannot be installed or behaves wrongly when installed.
+    Error,
+    /// The package can be installed but something looks wrong.
+    Warning,
+}
+
+impl fmt::Display for ManifestIssueSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestIssueSeverity::Error => f.write_str("error"),
+            ManifestIssueSeverity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A problem found in `package.json` or the index of a repository.
+#[derive(Debug, Clone)]
+pub struct ManifestIssue {
+    severity: ManifestIssueSeverity,
+    pointer: String,
+    message: String,
+}
+
+impl ManifestIssue {
+    pub(crate) fn new(severity: ManifestIssueSeverity, pointer: String, message: String) -> Self {
+        Self {
+            severity,
+            pointer,
+            message,
+        }
+    }
+
+    pub fn severity(&self) -> ManifestIssueSeverity {
+        self.severity
+    }
+
+    /// The JSON pointer to the value with the problem. Empty for the whole file.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ManifestIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(f, "{} at {}: {}", self.severity, self.pointer, self.message)
+        }
+    }
+}
+
+/// The result of checking `package.json`.
+///
+/// [`Self::new`] checks the file itself, and other checks can be added with `check_*` methods.
+pub struct PackageManifestLint {
+    manifest: Option<PackageManifest>,
+    issues: Vec<ManifestIssue>,
+}
+
+impl PackageManifestLint {
+    /// Checks the contents of `package.json`.
+    ///
+    /// Every value failing to deserialize as [`PackageManifest`] is reported, not only the first one.
+    pub fn new(json: &[u8]) -> Self {
+        let mut lint = Self {
+            manifest: None,
+            issues: Vec::new(),
+        };
+
+        let json = json.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json);
+        let mut root = match serde_json::from_slice::<Value>(json) {
+            Ok(Value::Object(root)) => root,
+            Ok(_) => {
+                lint.error("", "package.json must be an object".into());
+                return lint;
+            }
+            Err(e) => {
+                lint.error("", format!("syntax error: {e}"));
+                return lint;
+            }
+        };
+
+        // The required fields are replaced with placeholders to find problems in other fields.
+        let mut has_placeholder = false;
+        for (key, placeholder) in REQUIRED_FIELDS {
+            if !root.contains_key(*key) {
+                lint.error(&format!("/{key}"), "required field is missing".into());
+                root.insert(key.to_string(), (*placeholder).into());
+                has_placeholder = true;
+            }
+        }
+
+        let manifest = loop {
+            let value = Value::Object(root.clone());
+            let error = match serde_path_to_error::deserialize::<_, PackageManifest>(&value) {
+                Ok(manifest) => break manifest,
+                Err(error) => error,
+            };
+
+            let Some(segments) = path_segments(error.path()) else {
+                lint.error("", error.inner().to_string());
+                return lint;
+            };
+            let pointer = to_pointer(&segments);
+            lint.error(&pointer, error.inner().to_string());
+
+            if let [key] = segments.as_slice()
+                && let Some((_, placeholder)) = REQUIRED_FIELDS.iter().find(|(k, _)| k == key)
+            {
+                root.insert(key.clone(), (*placeholder).into());
+                has_placeholder = true;
+            } else if !remove_value(&mut root, &segments) {
+                return lint;
+            }
+        };
+
+        if !has_placeholder {
+            lint.check_manifest(&manifest);
+            lint.manifest = Some(manifest);
+        }
+
+        lint
+    }
+
+    /// Checks `package.json` of the package folder, `package.json` file, or zip file at `path`.
+    ///
+    /// For zip files, the version is also checked with the name of the zip file.
+    pub async fn load(path: &Path) -> io::Result<Self> {
+        if tokio::fs::metadata(path).await?.is_dir() {
+            return Ok(Self::new(
+                &tokio::fs::read(path.join("package.json")).await?,
+            ));
+        }
+
+        if path.extension().is_none_or(|x| x != "zip") {
+            return Ok(Self::new(&tokio::fs::read(path).await?));
+        }
+
+        let file = io::BufReader::new(tokio::fs::File::open(path).await?.compat());
+        let Some(package_json) = read_zip_entry(file, "package.json").await? else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} doesn't have package.json at the root", path.display()),
+            ));
+        };
+        let mut lint = Self::new(&package_json);
+        if let Some(file_name) = path.file_name().and_then(|x| x.to_str()) {
+            lint.check_zip_file_name(file_name);
+        }
+        Ok(lint)
+    }
+
+    /// The manifest if the required fields are valid.
+    ///
+    /// Invalid optional fields are dropped from the manifest.
+    pub fn manifest(&self) -> Option<&PackageManifest> {
+        self.manifest.as_ref()
+    }
+
+    pub fn issues(&self) -> &[ManifestIssue] {
+        &self.issues
+    }
+
+    pub fn has_errors(&self) -> bool {
+        (self.issues.iter()).any(|x| x.severity == ManifestIssueSeverity::Error)
+    }
+
+    pub(crate) fn into_parts(self) -> (Option<PackageManifest>, Vec<ManifestIssue>) {
+        (self.manifest, self.issues)
+    }
+
+    /// Checks the version of the package matches the name of the zip file.
+    pub fn check_zip_file_name(&mut self, file_name: &str) {
+        let Some(manifest) = &self.manifest else {
+            return;
+        };
+        let stem = file_name.strip_suffix(".zip").unwrap_or(file_name);
+        let version = manifest.version().to_string();
+        if !stem.contains(&version) {
+            let message = format!("version {version} doesn't match the zip file name {file_name}");
+            self.warning("/version", message);
+        }
+    }
+
+    /// Checks the dependencies with the packages in the collection.
+    pub fn check_dependencies(&mut self, collection: &impl PackageCollection) {
+        let Some(manifest) = self.manifest.take() else {
+            return;
+        };
+
+        for (name, range) in manifest.vpm_dependencies() {
+            let mut matched = collection
+                .find_packages(name)
+                .filter(|x| range.matches(x.version()))
+                .peekable();
+            if matched.peek().is_some() && matched.all(|x| x.is_yanked()) {
+                self.warning(
+                    &to_pointer(&["vpmDependencies".into(), name.to_string()]),
+                    format!("every version of {name} matching {range} is yanked"),
+                );
+            }
+        }
+
+        self.manifest = Some(manifest);
+    }
+
+    fn check_manifest(&mut self, manifest: &PackageManifest) {
+        if !is_valid_package_name(manifest.name()) {
+            self.error(
+                "/name",
+                format!(
+                    "package name '{}' must be lowercase letters, digits, '-', '_', or '.'",
+                    manifest.name()
+                ),
+            );
+        }
+
+        for name in manifest.vpm_dependencies().keys() {
+            let pointer = to_pointer(&["vpmDependencies".into(), name.to_string()]);
+            if **name == *manifest.name() {
+                self.error(&pointer, "the package depends on itself".into());
+            } else if !is_valid_package_name(name) {
+                self.error(&pointer, format!("'{name}' is not a valid package name"));
+            }
+        }
+
+        self.check_guids("legacyFolders", manifest.legacy_folders());
+        self.check_guids("legacyFiles", manifest.legacy_files());
+
+        match manifest.url() {
+            None => self.warning("/url", "url of the zip file is missing".into()),
+            Some(url) => self.check_http_url("/url", url),
+        }
+        if let Some(url) = manifest.changelog_url() {
+            self.check_http_url("/changelogUrl", url);
+        }
+        if let Some(url) = manifest.documentation_url() {
+            self.check_http_url("/documentationUrl", url);
+        }
+    }
+
+    fn check_guids(&mut self, field: &str, assets: &HashMap<Box<str>, Option<Box<str>>>) {
+        let mut assets = assets.iter().collect::<Vec<_>>();
+        assets.sort();
+        for (path, guid) in assets {
+            if let Some(guid) = guid
+                && Guid::parse(guid).is_none()
+            {
+                self.error(
+                    &to_pointer(&[field.into(), path.to_string()]),
+                    format!("GUID '{guid}' is not valid. GUID must be 32 hex digits"),
+                );
+            }
+        }
+    }
+
+    fn check_http_url(&mut self, pointer: &str, url: &Url) {
+        if url.scheme() != "http" && url.scheme() != "https" {
+            self.error(pointer, format!("'{url}' is not a http or https url"));
+        }
+    }
+
+    fn error(&mut self, pointer: &str, message: String) {
+        self.push(ManifestIssueSeverity::Error, pointer, message);
+    }
+
+    fn warning(&mut self, pointer: &str, message: String) {
+        self.push(ManifestIssueSeverity::Warning, pointer, message);
+    }
+
+    fn push(&mut self, severity: ManifestIssueSeverity, pointer: &str, message: String) {
+        (self.issues).push(ManifestIssue::new(severity, pointer.into(), message));
+    }
+}
+
+/// The required fields and valid placeholder values for them.
+const REQUIRED_FIELDS: &[(&str, &str)] = &[("name", "placeholder"), ("version", "0.0.0")];
+
+pub(crate) fn is_valid_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && (name.bytes()).all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_' || b == b'.'
+        })
+}
+
+/// Converts the path to keys of the json. Returns `None` for the root or unknown paths.
+pub(crate) fn path_segments(path: &serde_path_to_error::Path) -> Option<Vec<String>> {
+    use serde_path_to_error::Segment;
+    let mut segments = Vec::new();
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => segments.push(index.to_string()),
+            Segment::Map { key } => segments.push(key.clone()),
+            Segment::Enum { .. } | Segment::Unknown => return None,
+        }
+    }
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments)
+    }
+}
+
+/// Builds the JSON pointer (RFC 6901) from the keys.
+pub(crate) fn to_pointer(segments: &[String]) -> String {
+    let mut pointer = String::new();
+    for segment in segments {
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    }
+    pointer
+}
+
+/// Removes the value at the path. Returns false if not found.
+fn remove_value(root: &mut Map<String, Value>, segments: &[String]) -> bool {
+    let (last, parents) = segments.split_last().unwrap();
+    let mut value = Value::Object(std::mem::take(root));
+
+    let removed = match value.pointer_mut(&to_pointer(parents)) {
+        Some(Value::Object(map)) => map.remove(last).is_some(),
+        Some(Value::Array(array)) => match last.parse::<usize>() {
+            Ok(index) if index < array.len() => {
+                array.remove(index);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+
+    let Value::Object(map) = value else {
+        unreachable!()
+    };
+    *root = map;
+    removed
+}
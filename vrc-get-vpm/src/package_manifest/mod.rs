@@ -1,3 +1,4 @@
+pub(crate) mod lint;
 mod partial_unity_version;
 mod yank_state;
 
@@ -9,6 +10,7 @@ use std::collections::HashMap;
 use url::Url;
 
 use crate::package_manifest::yank_state::YankState;
+pub use lint::{ManifestIssue, ManifestIssueSeverity, PackageManifestLint};
 pub use partial_unity_version::PartialUnityVersion;
 
 macro_rules! initialize_from_package_json_like {
@@ -171,6 +173,10 @@ impl PackageManifest {
     pub fn is_yanked(&self) -> bool {
         self.vrc_get.yanked.is_yanked()
     }
+    /// The reason of yanking if the package is yanked with a reason.
+    pub fn yank_reason(&self) -> Option<&str> {
+        self.vrc_get.yanked.reason()
+    }
     // TODO: deprecate aliases on next minor release
     pub fn aliases(&self) -> &[Box<str>] {
         self.vrc_get.aliases.as_slice()
@@ -0,0 +1,238 @@
+//! Building the repository index from a folder of packages.
+
+use crate::package_manifest::lint::is_valid_package_name;
+use crate::repository::RemoteRepository;
+use crate::repository::pack::pack_package;
+use crate::utils::{
+    Sha256AsyncWrite, deserialize_json, parse_json_file, read_zip_entry, to_vec_pretty_os_eol,
+};
+use crate::version::Version;
+use crate::{PackageManifest, io};
+use log::warn;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use url::Url;
+
+type JsonMap = Map<String, Value>;
+
+/// Builds the index of a repository from package folders and zip files.
+///
+/// The zip files are placed in the zip folder and expected to be hosted at `<base url>/<file name>`.
+pub struct RepositoryBuilder {
+    base_url: Url,
+    zip_folder: PathBuf,
+    repository: JsonMap,
+    added: Vec<(Box<str>, Version)>,
+}
+
+impl RepositoryBuilder {
+    pub fn new(base_url: Url, zip_folder: PathBuf) -> Self {
+        let mut base_url = base_url;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Self {
+            base_url,
+            zip_folder,
+            repository: JsonMap::new(),
+            added: Vec::new(),
+        }
+    }
+
+    /// Loads the existing repository index to keep the versions not in the folder.
+    pub fn load_existing(&mut self, json: &[u8], path: &Path) -> io::Result<()> {
+        let repository: JsonMap = parse_json_file(json, path)?;
+        // validate the index
+        RemoteRepository::parse(repository.clone())?;
+        self.repository = repository;
+        Ok(())
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        (self.repository).insert("name".into(), name.into());
+    }
+
+    pub fn set_id(&mut self, id: &str) {
+        (self.repository).insert("id".into(), id.into());
+    }
+
+    pub fn set_url(&mut self, url: &Url) {
+        (self.repository).insert("url".into(), url.as_str().into());
+    }
+
+    pub fn has_url(&self) -> bool {
+        self.repository.contains_key("url")
+    }
+
+    /// The packages added by [`Self::add_folder`] and [`Self::add_zip`].
+    pub fn added_packages(&self) -> &[(Box<str>, Version)] {
+        &self.added
+    }
+
+    /// Adds the packages in `folder`.
+    ///
+    /// Each child folder with `package.json` is packed to a zip file,
+    /// and each zip file with `package.json` at the root is added as is.
+    /// If a version is found in both, the folder has priority.
+    pub async fn add_all_in(&mut self, folder: &Path) -> io::Result<()> {
+        let mut folders = Vec::new();
+        let mut zips = Vec::new();
+
+        let mut iter = tokio::fs::read_dir(folder).await?;
+        while let Some(entry) = iter.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                if tokio::fs::metadata(path.join("package.json")).await.is_ok() {
+                    folders.push(path);
+                }
+            } else if path.extension().is_some_and(|x| x == "zip") {
+                zips.push(path);
+            }
+        }
+        folders.sort();
+        zips.sort();
+
+        for folder in folders {
+            self.add_folder(&folder).await?;
+        }
+        for zip in zips {
+            self.add_zip(&zip).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Packs the package folder to the zip folder and adds it.
+    pub async fn add_folder(&mut self, folder: &Path) -> io::Result<()> {
+        let package_json = tokio::fs::read(folder.join("package.json")).await?;
+        let manifest: PackageManifest =
+            parse_json_file(&package_json, &folder.join("package.json"))?;
+
+        if self.is_added(&manifest) {
+            return Ok(());
+        }
+
+        let file_name = zip_file_name(&manifest, folder)?;
+        tokio::fs::create_dir_all(&self.zip_folder).await?;
+        let packed = pack_package(folder, &self.zip_folder.join(&file_name)).await?;
+        let url = self.base_url.join(&file_name).unwrap();
+
+        self.add_entry(
+            packed.repository_entry(Some(&url)),
+            packed.manifest(),
+            &packed.sha256(),
+        );
+        Ok(())
+    }
+
+    /// Adds the zip file, copying it to the zip folder if it's not in the zip folder.
+    ///
+    /// Zip files without `package.json` at the root are ignored with a warning.
+    pub async fn add_zip(&mut self, zip: &Path) -> io::Result<()> {
+        let file = io::BufReader::new(tokio::fs::File::open(zip).await?.compat());
+        let Some(package_json) = read_zip_entry(file, "package.json").await? else {
+            warn!("{} doesn't have package.json at the root", zip.display());
+            return Ok(());
+        };
+        let raw: JsonMap = parse_json_file(&package_json, &zip.join("package.json"))?;
+        let manifest: PackageManifest = deserialize_json(Value::Object(raw.clone()))?;
+
+        if self.is_added(&manifest) {
+            return Ok(());
+        }
+
+        let file_name = zip_file_name(&manifest, zip)?;
+        let dest = self.zip_folder.join(&file_name);
+        if !is_same_file(zip, &dest).await {
+            tokio::fs::create_dir_all(&self.zip_folder).await?;
+            tokio::fs::copy(zip, &dest).await?;
+        }
+
+        let mut hasher = Sha256AsyncWrite::new(io::sink());
+        io::copy(
+            &mut tokio::fs::File::open(&dest).await?.compat(),
+            &mut hasher,
+        )
+        .await?;
+        let sha256 = hex::encode(hasher.finalize().1);
+
+        let mut entry = raw;
+        let url = self.base_url.join(&file_name).unwrap();
+        entry.insert("url".into(), url.as_str().into());
+        entry.insert("zipSHA256".into(), sha256.as_str().into());
+
+        self.add_entry(entry, &manifest, &sha256);
+        Ok(())
+    }
+
+    fn is_added(&self, manifest: &PackageManifest) -> bool {
+        (self.added.iter())
+            .any(|(name, version)| **name == *manifest.name() && version == manifest.version())
+    }
+
+    fn add_entry(&mut self, entry: JsonMap, manifest: &PackageManifest, sha256: &str) {
+        let packages = object_entry(&mut self.repository, "packages");
+        let package = object_entry(packages, manifest.name());
+        let versions = object_entry(package, "versions");
+
+        let version = manifest.version().to_string();
+        if let Some(existing) = versions.get(&version)
+            && existing.get("zipSHA256").and_then(Value::as_str) != Some(sha256)
+        {
+            warn!(
+                "replacing {} version {} with different zip file",
+                manifest.name(),
+                version
+            );
+        }
+        versions.insert(version, Value::Object(entry));
+
+        self.added
+            .push((manifest.name().into(), manifest.version().clone()));
+    }
+
+    /// Returns the repository index.
+    pub fn to_json(&self) -> io::Result<Vec<u8>> {
+        to_vec_pretty_os_eol(&self.repository)
+    }
+}
+
+/// Returns the name of the zip file in the zip folder.
+///
+/// The package name is checked not to escape the zip folder, like `pack` and `lint-package` do.
+fn zip_file_name(manifest: &PackageManifest, source: &Path) -> io::Result<String> {
+    if !is_valid_package_name(manifest.name()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "invalid package name '{}' in {}",
+                manifest.name(),
+                source.display()
+            ),
+        ));
+    }
+    Ok(format!("{}-{}.zip", manifest.name(), manifest.version()))
+}
+
+/// Gets the object at the key, inserting an empty object if not exists or not an object.
+fn object_entry<'a>(map: &'a mut JsonMap, key: &str) -> &'a mut JsonMap {
+    let value = map
+        .entry(key)
+        .or_insert_with(|| Value::Object(JsonMap::new()));
+    if !value.is_object() {
+        *value = Value::Object(JsonMap::new());
+    }
+    value.as_object_mut().unwrap()
+}
+
+async fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (
+        tokio::fs::canonicalize(a).await,
+        tokio::fs::canonicalize(b).await,
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
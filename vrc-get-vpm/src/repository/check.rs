@@ -0,0 +1,332 @@
+//! Checking the index of a repository for problems.
+
+use crate::package_manifest::lint::{path_segments, to_pointer};
+use crate::traits::HttpClient;
+use crate::utils::Sha256AsyncWrite;
+use crate::version::Version;
+use crate::{ManifestIssue, ManifestIssueSeverity, PackageCollection, PackageManifest};
+use crate::{PackageManifestLint, io};
+use indexmap::IndexMap;
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::pin;
+
+/// The result of checking the index of a repository.
+///
+/// Unlike [`RemoteRepository`](super::RemoteRepository), which skips broken package versions,
+/// every problem in the index is reported with the JSON pointer to the value.
+pub struct RepositoryCheck {
+    issues: Vec<ManifestIssue>,
+    versions: Vec<CheckedVersion>,
+}
+
+struct CheckedVersion {
+    pointer: String,
+    manifest: PackageManifest,
+}
+
+impl RepositoryCheck {
+    /// Checks the contents of the index of the repository.
+    pub fn new(json: &[u8]) -> Self {
+        let mut check = Self {
+            issues: Vec::new(),
+            versions: Vec::new(),
+        };
+
+        let json = json.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(json);
+        let mut deserializer = serde_json::Deserializer::from_slice(json);
+        let repository =
+            match serde_path_to_error::deserialize::<_, RawRepository>(&mut deserializer) {
+                Ok(repository) => repository,
+                Err(error) => {
+                    let pointer = path_segments(error.path())
+                        .map(|x| to_pointer(&x))
+                        .unwrap_or_default();
+                    check.error(pointer, error.inner().to_string());
+                    return check;
+                }
+            };
+
+        let mut package_names = HashSet::new();
+        for (name, package) in repository.packages.0 {
+            let package_pointer = to_pointer(&["packages".into(), name.clone()]);
+            if !package_names.insert(name.clone()) {
+                check.error(package_pointer.clone(), format!("duplicate package {name}"));
+            }
+
+            let mut versions = HashMap::<Version, String>::new();
+            for (key, value) in package.versions.0 {
+                let pointer = format!(
+                    "{package_pointer}{}",
+                    to_pointer(&["versions".into(), key.clone()])
+                );
+                check.check_version(&name, &key, value, pointer, &mut versions);
+            }
+        }
+
+        check
+    }
+
+    fn check_version(
+        &mut self,
+        name: &str,
+        key: &str,
+        value: Value,
+        pointer: String,
+        versions: &mut HashMap<Version, String>,
+    ) {
+        let key_version = key.parse::<Version>().ok();
+        match &key_version {
+            Some(version) => {
+                if let Some(existing) = versions.get(version) {
+                    let message =
+                        format!("duplicate version {version}, also defined as '{existing}'");
+                    self.error(pointer.clone(), message);
+                } else {
+                    versions.insert(version.clone(), key.into());
+                }
+            }
+            None => self.error(pointer.clone(), format!("'{key}' is not a valid version")),
+        }
+
+        let lint = PackageManifestLint::new(&serde_json::to_vec(&value).unwrap());
+        let (manifest, issues) = lint.into_parts();
+        let url_missing = manifest.as_ref().is_some_and(|x| x.url().is_none());
+        for issue in issues {
+            // VCC cannot install packages without url from repositories
+            let severity = if url_missing && issue.pointer() == "/url" {
+                ManifestIssueSeverity::Error
+            } else {
+                issue.severity()
+            };
+            let message = issue.message().to_owned();
+            (self.issues).push(ManifestIssue::new(
+                severity,
+                format!("{pointer}{}", issue.pointer()),
+                message,
+            ));
+        }
+
+        let Some(manifest) = manifest else {
+            return;
+        };
+
+        if manifest.name() != name {
+            let message = format!(
+                "name '{}' doesn't match the package key '{name}'",
+                manifest.name()
+            );
+            self.error(format!("{pointer}/name"), message);
+        }
+        if key_version.is_some_and(|x| x != *manifest.version()) {
+            let message = format!(
+                "version {} doesn't match the version key '{key}'",
+                manifest.version()
+            );
+            self.error(format!("{pointer}/version"), message);
+        }
+        if manifest.zip_sha_256().is_none() {
+            let message = "zipSHA256 is missing. the zip file cannot be verified".into();
+            self.warning(format!("{pointer}/zipSHA256"), message);
+        }
+
+        self.versions.push(CheckedVersion { pointer, manifest });
+    }
+
+    pub fn issues(&self) -> &[ManifestIssue] {
+        &self.issues
+    }
+
+    pub fn has_errors(&self) -> bool {
+        (self.issues.iter()).any(|x| x.severity() == ManifestIssueSeverity::Error)
+    }
+
+    /// The package versions successfully loaded from the index.
+    pub fn versions(&self) -> impl Iterator<Item = &PackageManifest> {
+        self.versions.iter().map(|x| &x.manifest)
+    }
+
+    /// Checks the dependencies exist in this repository or the packages in the collection.
+    pub fn check_dependencies(&mut self, collection: &impl PackageCollection) {
+        let in_repository = (self.versions.iter())
+            .map(|x| x.manifest.name())
+            .collect::<HashSet<_>>();
+
+        let mut issues = Vec::new();
+        for version in &self.versions {
+            for name in version.manifest.vpm_dependencies().keys() {
+                if !in_repository.contains(name.as_ref())
+                    && collection.find_packages(name).next().is_none()
+                {
+                    issues.push(ManifestIssue::new(
+                        ManifestIssueSeverity::Error,
+                        format!(
+                            "{}{}",
+                            version.pointer,
+                            to_pointer(&["vpmDependencies".into(), name.to_string()])
+                        ),
+                        format!("{name} is not found in this repository or any known repository"),
+                    ));
+                }
+            }
+        }
+        self.issues.extend(issues);
+    }
+
+    /// Downloads the zip file of every package version and checks the hash.
+    ///
+    /// `headers` are the headers of the repository, sent with the headers of the package.
+    /// `on_checked` is called for each package version with the result.
+    pub async fn check_zip_hashes(
+        &mut self,
+        client: &impl HttpClient,
+        headers: &IndexMap<Box<str>, Box<str>>,
+        on_checked: impl Fn(&PackageManifest, &io::Result<String>),
+    ) {
+        let mut issues = Vec::new();
+        for version in &self.versions {
+            let manifest = &version.manifest;
+            let Some(url) = manifest.url() else {
+                continue;
+            };
+
+            let headers = IndexMap::from_iter(
+                (headers.iter().map(|(k, v)| (k.as_ref(), v.as_ref())))
+                    .chain((manifest.headers().iter()).map(|(k, v)| (k.as_ref(), v.as_ref()))),
+            );
+            let result = async {
+                let (stream, _) = client.get(url, &headers).await?;
+                let mut hasher = Sha256AsyncWrite::new(io::sink());
+                io::copy(&mut pin!(stream), &mut hasher).await?;
+                Ok(hex::encode(hasher.finalize().1))
+            }
+            .await;
+            on_checked(manifest, &result);
+
+            let pointer = &version.pointer;
+            match result {
+                Ok(sha256) => {
+                    if let Some(expected) = manifest.zip_sha_256()
+                        && !expected.eq_ignore_ascii_case(&sha256)
+                    {
+                        issues.push(ManifestIssue::new(
+                            ManifestIssueSeverity::Error,
+                            format!("{pointer}/zipSHA256"),
+                            format!("zipSHA256 is {expected} but the zip file is {sha256}"),
+                        ));
+                    }
+                }
+                Err(e) => issues.push(ManifestIssue::new(
+                    ManifestIssueSeverity::Error,
+                    format!("{pointer}/url"),
+                    format!("failed to download {url}: {e}"),
+                )),
+            }
+        }
+        self.issues.extend(issues);
+    }
+
+    fn error(&mut self, pointer: String, message: String) {
+        (self.issues).push(ManifestIssue::new(
+            ManifestIssueSeverity::Error,
+            pointer,
+            message,
+        ));
+    }
+
+    fn warning(&mut self, pointer: String, message: String) {
+        (self.issues).push(ManifestIssue::new(
+            ManifestIssueSeverity::Warning,
+            pointer,
+            message,
+        ));
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRepository {
+    #[serde(default)]
+    packages: Entries<RawPackage>,
+}
+
+struct RawPackage {
+    versions: Entries<Value>,
+}
+
+// derived Deserialize accepts arrays for structs but RemoteRepository doesn't
+impl<'de> Deserialize<'de> for RawPackage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct VisitorImpl;
+
+        impl<'de> Visitor<'de> for VisitorImpl {
+            type Value = RawPackage;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of package versions")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut versions = Entries::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "versions" {
+                        versions = map.next_value()?;
+                    } else {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+                Ok(RawPackage { versions })
+            }
+        }
+
+        deserializer.deserialize_map(VisitorImpl)
+    }
+}
+
+/// The entries of a json object, keeping the duplicated keys.
+struct Entries<T>(Vec<(String, T)>);
+
+impl<T> Default for Entries<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Entries<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct VisitorImpl<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for VisitorImpl<T> {
+            type Value = Entries<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut entries = Vec::new();
+                while let Some((key, value)) = map.next_entry()? {
+                    entries.push((key, value));
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(VisitorImpl(PhantomData))
+    }
+}
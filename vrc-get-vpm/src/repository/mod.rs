@@ -1,6 +1,12 @@
+mod build;
+mod check;
 pub(crate) mod local;
+mod pack;
 mod remote;
 
+pub use build::RepositoryBuilder;
+pub use check::RepositoryCheck;
 pub use local::LocalCachedRepository;
+pub use pack::{PackedPackage, pack_package};
 pub use remote::RemotePackages;
 pub use remote::RemoteRepository;
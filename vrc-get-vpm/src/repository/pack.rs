@@ -0,0 +1,183 @@
+//! Building the zip file of a package from the package folder.
+
+use crate::io::{DefaultProjectIo, DirEntry, IoTrait};
+use crate::utils::{
+    MapResultExt, Sha256AsyncWrite, deserialize_json, parse_json_file, read_to_end,
+};
+use crate::{ManifestIssueSeverity, PackageManifest, PackageManifestLint, io};
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipDateTimeBuilder, ZipEntryBuilder};
+use futures::prelude::*;
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+use url::Url;
+
+type JsonMap = Map<String, Value>;
+
+/// The names of files and folders not included in the zip file.
+const EXCLUDED_NAMES: &[&str] = &[
+    ".git",
+    ".svn",
+    ".DS_Store",
+    "__MACOSX",
+    "Thumbs.db",
+    "desktop.ini",
+];
+
+/// The zip file of a package built by [`pack_package`].
+#[derive(Debug)]
+pub struct PackedPackage {
+    manifest: PackageManifest,
+    raw_manifest: JsonMap,
+    sha256: [u8; 256 / 8],
+}
+
+impl PackedPackage {
+    pub fn manifest(&self) -> &PackageManifest {
+        &self.manifest
+    }
+
+    /// The lowercase hex sha256 hash of the zip file.
+    pub fn sha256(&self) -> String {
+        hex::encode(self.sha256)
+    }
+
+    /// The entry of this version for the `versions` of the repository.
+    ///
+    /// This is the `package.json` with `url` and `zipSHA256`.
+    /// `url` is omitted if `None` since the location of the zip file is not known.
+    pub fn repository_entry(&self, url: Option<&Url>) -> JsonMap {
+        let mut entry = self.raw_manifest.clone();
+        if let Some(url) = url {
+            entry.insert("url".into(), url.as_str().into());
+        }
+        entry.insert("zipSHA256".into(), self.sha256().into());
+        entry
+    }
+}
+
+/// Writes the zip file of the package in `folder` to `output`.
+///
+/// The zip file is deterministic: the files are sorted by path and have fixed timestamps,
+/// so packing the same folder always results the same hash.
+///
+/// The `package.json` is checked with [`PackageManifestLint`] before packing,
+/// and junk files like `.DS_Store` are excluded.
+/// `output` and zip files directly in `folder`, e.g. previously packed zip files, are also excluded.
+pub async fn pack_package(folder: &Path, output: &Path) -> io::Result<PackedPackage> {
+    let io = DefaultProjectIo::new(folder.into());
+
+    let package_json_path = folder.join("package.json");
+    let package_json = read_to_end(io.open("package.json".as_ref()).await?).await?;
+
+    let lint = PackageManifestLint::new(&package_json);
+    if lint.has_errors() {
+        let errors = (lint.issues().iter())
+            .filter(|x| x.severity() == ManifestIssueSeverity::Error)
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "invalid {}: {}",
+                package_json_path.display(),
+                errors.join(", ")
+            ),
+        ));
+    }
+
+    let raw_manifest: JsonMap = parse_json_file(&package_json, &package_json_path)?;
+    let manifest: PackageManifest = deserialize_json(Value::Object(raw_manifest.clone()))?;
+
+    let files = list_files(&io, folder, output).await?;
+
+    let file = tokio::fs::File::create(output).await?.compat_write();
+    let mut writer = ZipFileWriter::new(Sha256AsyncWrite::new(file));
+    let date = ZipDateTimeBuilder::new().year(1980).month(1).day(1).build();
+
+    let result = async {
+        for path in files {
+            let mut name = String::new();
+            for component in path.components() {
+                if !name.is_empty() {
+                    name.push('/');
+                }
+                name.push_str(component.as_os_str().to_str().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("non utf8 path in the package: {}", path.display()),
+                    )
+                })?);
+            }
+
+            let entry = ZipEntryBuilder::new(name.into(), Compression::Deflate)
+                .last_modification_date(date)
+                .unix_permissions(0o644);
+            let mut entry_writer = writer.write_entry_stream(entry).await.err_mapped()?;
+            io::copy(&mut io.open(&path).await?, &mut entry_writer).await?;
+            entry_writer.close().await.err_mapped()?;
+        }
+
+        let mut hasher = writer.close().await.err_mapped()?;
+        hasher.flush().await?;
+        Ok(hasher.finalize().1.into())
+    }
+    .await;
+
+    match result {
+        Ok(sha256) => Ok(PackedPackage {
+            manifest,
+            raw_manifest,
+            sha256,
+        }),
+        Err(e) => {
+            tokio::fs::remove_file(output).await.ok();
+            Err(e)
+        }
+    }
+}
+
+/// Returns true if the file or folder should not be included in the zip file.
+fn is_excluded(name: &str) -> bool {
+    // "._" files are AppleDouble files created by macOS on non-HFS file systems
+    EXCLUDED_NAMES.contains(&name) || name.starts_with("._")
+}
+
+/// Lists the files in the package folder, sorted by path.
+///
+/// `output` and zip files at the root of the package are excluded
+/// so packing to the package folder doesn't include the zip file itself.
+async fn list_files(
+    io: &DefaultProjectIo,
+    folder: &Path,
+    output: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut queue = VecDeque::from([PathBuf::new()]);
+
+    while let Some(dir) = queue.pop_front() {
+        let mut iter = io.read_dir(&dir).await?;
+        while let Some(entry) = iter.try_next().await? {
+            let file_name = entry.file_name();
+            if (file_name.to_str()).is_some_and(is_excluded) {
+                continue;
+            }
+            let path = dir.join(&file_name);
+            if entry.file_type().await?.is_dir() {
+                queue.push_back(path);
+                continue;
+            }
+            let is_root_zip = dir.as_os_str().is_empty()
+                && (file_name.to_str()).is_some_and(|x| x.to_ascii_lowercase().ends_with(".zip"));
+            if is_root_zip || folder.join(&path) == output {
+                continue;
+            }
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -137,6 +137,11 @@ impl RemoteRepository {
     pub fn get_package_version(&self, name: &str, version: &Version) -> Option<&PackageManifest> {
         self.parsed.packages.get(name)?.versions.get(version)
     }
+
+    /// Returns the value of the vrc-get specific extension `"vrc-get": { key: value }` in the index.
+    pub(crate) fn vrc_get_extension(&self, key: &str) -> Option<&Value> {
+        self.actual.get("vrc-get")?.get(key)
+    }
 }
 
 impl Serialize for RemoteRepository {
@@ -7,7 +7,11 @@ use core::option::Option;
 use futures::prelude::*;
 use indexmap::IndexMap;
 use std::convert::Infallible;
+use std::error::Error;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use url::Url;
 
 pub trait PackageCollection {
@@ -37,15 +41,61 @@ pub trait PackageCollection {
 ///
 /// Caching packages is responsibility of this trait.
 pub trait PackageInstaller {
-    /// Installs the specified package.
+    /// Installs the specified package to `dest_folder`.
+    ///
+    /// `dest_folder` is relative to the project and does not exist when this function is called.
+    /// The folder is moved to `Packages` after all packages are installed successfully.
+    ///
+    /// `locked_sha256` is the hash recorded in the `locked` section of the project.
+    /// If specified, the installed zip file must match the hash.
+    ///
+    /// The progress of downloading and extracting should be reported to `progress`.
+    ///
+    /// Returns the SHA-256 of the installed zip file in hex, which is recorded in the `locked` section,
+    /// or `None` if the package is not installed from a zip file.
     fn install_package(
         &self,
         io: &DefaultProjectIo,
         package: PackageInfo<'_>,
+        dest_folder: &Path,
+        locked_sha256: Option<&str>,
+        progress: &dyn InstallProgress,
         abort: &AbortCheck,
-    ) -> impl Future<Output = io::Result<()>>;
+    ) -> impl Future<Output = io::Result<Option<Box<str>>>>;
+
+    /// The maximum number of packages installed at once.
+    ///
+    /// `None` means no limit.
+    fn max_concurrency(&self) -> Option<NonZeroUsize> {
+        None
+    }
 }
 
+/// The receiver of the progress of installing packages.
+///
+/// Since packages are installed concurrently, the methods may be called for multiple packages alternately.
+pub trait InstallProgress: Sync {
+    /// Called when some bytes of the zip file of the package are downloaded.
+    ///
+    /// `total` is `None` if the server doesn't tell the size of the zip file.
+    fn downloading(&self, package: PackageInfo<'_>, downloaded: u64, total: Option<u64>) {
+        let _ = (package, downloaded, total);
+    }
+
+    /// Called when a file in the zip file of the package is extracted.
+    fn extracting(&self, package: PackageInfo<'_>, extracted: usize, total: usize) {
+        let _ = (package, extracted, total);
+    }
+
+    /// Called when the package is installed to the staging directory.
+    fn installed(&self, package: PackageInfo<'_>) {
+        let _ = package;
+    }
+}
+
+/// Ignores all progress.
+impl InstallProgress for () {}
+
 pub struct AbortCheck {
     abort: AtomicBool,
 }
@@ -73,12 +123,14 @@ impl AbortCheck {
 pub trait HttpClient: Sync {
     /// Get resource from the URL with specified headers
     ///
+    /// Returns the stream and the length of the resource if the server tells it.
+    ///
     /// Note: If remote server returns error status code, this function should return error.
     fn get(
         &self,
         url: &Url,
         headers: &IndexMap<&str, &str>,
-    ) -> impl Future<Output = io::Result<impl AsyncRead + Send>> + Send;
+    ) -> impl Future<Output = io::Result<(impl AsyncRead + Send, Option<u64>)>> + Send;
 
     /// Get resource from the URL with specified headers and etag
     ///
@@ -93,10 +145,111 @@ pub trait HttpClient: Sync {
         headers: &IndexMap<Box<str>, Box<str>>,
         current_etag: Option<&str>,
     ) -> impl Future<Output = io::Result<Option<(impl AsyncRead + Send, Option<Box<str>>)>>> + Send;
+
+    /// Get resource from the URL with specified headers, starting from `offset` bytes.
+    ///
+    /// `validator` is the `ETag` or `Last-Modified` of the resource the first `offset` bytes came from.
+    /// It's sent with `If-Range` so the server sends the whole resource if the resource has been changed.
+    ///
+    /// Returns the stream, the offset the stream starts from, the length of the whole resource if known,
+    /// and the validator of the resource if the server tells it.
+    /// If the server doesn't support range requests, the stream starts from the beginning and the offset is `0`.
+    ///
+    /// Note: If remote server returns error status code, this function should return error.
+    #[allow(clippy::type_complexity)]
+    fn get_range(
+        &self,
+        url: &Url,
+        headers: &IndexMap<&str, &str>,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> impl Future<
+        Output = io::Result<(impl AsyncRead + Send, u64, Option<u64>, Option<Box<str>>)>,
+    > + Send {
+        let _ = (offset, validator);
+        async move {
+            let (stream, length) = self.get(url, headers).await?;
+            Ok((stream, 0, length, None))
+        }
+    }
+
+    /// The policy to retry downloading packages.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Returns true if the error is temporary and the request may succeed if retried.
+    fn is_transient_error(&self, error: &io::Error) -> bool {
+        is_transient_error_kind(error.kind())
+    }
+}
+
+/// How many times and how long to wait before retrying failed requests.
+///
+/// The wait time is doubled for each retry up to `max_backoff`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: NonZeroU32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub const fn new(
+        max_attempts: NonZeroU32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// The policy which never retries.
+    pub const fn no_retry() -> Self {
+        Self::new(NonZeroU32::MIN, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Returns the policy with the maximum number of attempts changed.
+    pub const fn with_max_attempts(self, max_attempts: NonZeroU32) -> Self {
+        Self {
+            max_attempts,
+            ..self
+        }
+    }
+
+    /// The maximum number of attempts including the first attempt.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.get()
+    }
+
+    /// The time to wait before the `retry`-th retry, which starts from 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        (self.initial_backoff.saturating_mul(factor)).min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(
+            NonZeroU32::new(4).unwrap(),
+            Duration::from_secs(1),
+            Duration::from_secs(30),
+        )
+    }
 }
 
 impl HttpClient for reqwest::Client {
-    async fn get(&self, url: &Url, headers: &IndexMap<&str, &str>) -> io::Result<impl AsyncRead> {
+    async fn get(
+        &self,
+        url: &Url,
+        headers: &IndexMap<&str, &str>,
+    ) -> io::Result<(impl AsyncRead, Option<u64>)> {
         // file not found: err
 
         let mut request = self.get(url.to_owned());
@@ -105,14 +258,20 @@ impl HttpClient for reqwest::Client {
             request = request.header(name, header);
         }
 
-        Ok(request
+        let response = request
             .send()
             .await
             .and_then(reqwest::Response::error_for_status)
-            .err_mapped()?
+            .err_mapped()?;
+
+        let content_length = response.content_length();
+
+        let response_stream = response
             .bytes_stream()
             .map(|x| x.err_mapped())
-            .into_async_read())
+            .into_async_read();
+
+        Ok((response_stream, content_length))
     }
 
     async fn get_with_etag(
@@ -150,11 +309,107 @@ impl HttpClient for reqwest::Client {
 
         Ok(Some((response_stream, etag)))
     }
+
+    async fn get_range(
+        &self,
+        url: &Url,
+        headers: &IndexMap<&str, &str>,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> io::Result<(impl AsyncRead, u64, Option<u64>, Option<Box<str>>)> {
+        let request = |offset: u64| {
+            let mut request = self.get(url.to_owned());
+            for (&name, &header) in headers {
+                request = request.header(name, header);
+            }
+            if offset != 0 {
+                request = request.header("Range", format!("bytes={offset}-"));
+                if let Some(validator) = validator {
+                    request = request.header("If-Range", validator);
+                }
+            }
+            request.send()
+        };
+
+        let mut response = request(offset).await.err_mapped()?;
+        if offset != 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // the resource is shorter than the offset, so get the whole resource
+            response = request(0).await.err_mapped()?;
+        }
+        let response = response.error_for_status().err_mapped()?;
+
+        let (start, length) = if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            let content_range = response
+                .headers()
+                .get("Content-Range")
+                .and_then(|x| x.to_str().ok())
+                .and_then(parse_content_range);
+            match content_range {
+                Some((start, length)) if start == offset => (
+                    offset,
+                    length.or(response.content_length().map(|x| x + offset)),
+                ),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected Content-Range for {url}"),
+                    ));
+                }
+            }
+        } else {
+            (0, response.content_length())
+        };
+
+        // weak etags cannot be used with If-Range
+        let validator = (response.headers().get("Etag"))
+            .and_then(|x| x.to_str().ok())
+            .filter(|x| !x.starts_with("W/"))
+            .or_else(|| (response.headers().get("Last-Modified")).and_then(|x| x.to_str().ok()))
+            .map(Into::into);
+
+        let response_stream = response
+            .bytes_stream()
+            .map(|x| x.err_mapped())
+            .into_async_read();
+
+        Ok((response_stream, start, length, validator))
+    }
+
+    fn is_transient_error(&self, error: &io::Error) -> bool {
+        // find the reqwest error in the error chain
+        let mut source = error.get_ref().map(|x| x as &(dyn Error + 'static));
+        while let Some(error) = source {
+            if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+                return match error.status() {
+                    Some(status) => {
+                        status.is_server_error()
+                            || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                            || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    }
+                    // errors while reading the response body are reported as decode errors
+                    None => {
+                        error.is_timeout()
+                            || error.is_connect()
+                            || error.is_request()
+                            || error.is_body()
+                            || error.is_decode()
+                    }
+                };
+            }
+            source = error.source();
+        }
+
+        is_transient_error_kind(error.kind())
+    }
 }
 
 impl HttpClient for Infallible {
-    async fn get(&self, _: &Url, _: &IndexMap<&str, &str>) -> io::Result<impl AsyncRead> {
-        Ok(io::empty())
+    async fn get(
+        &self,
+        _: &Url,
+        _: &IndexMap<&str, &str>,
+    ) -> io::Result<(impl AsyncRead, Option<u64>)> {
+        Ok((io::empty(), Some(0)))
     }
 
     async fn get_with_etag(
@@ -166,3 +421,77 @@ impl HttpClient for Infallible {
         Ok(Some((io::empty(), None)))
     }
 }
+
+/// Parses the value of `Content-Range` header like `bytes <start>-<end>/<length>`.
+///
+/// Returns the start of the range and the length of the whole resource if known.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, length) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start = start.parse::<u64>().ok()?;
+    let end = end.parse::<u64>().ok()?;
+    if end < start {
+        return None;
+    }
+    let length = match length {
+        "*" => None,
+        length => Some(length.parse::<u64>().ok()?),
+    };
+    Some((start, length))
+}
+
+fn is_transient_error_kind(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_backoff() {
+        let policy = RetryPolicy::new(
+            NonZeroU32::new(10).unwrap(),
+            Duration::from_secs(1),
+            Duration::from_secs(30),
+        );
+        assert_eq!(policy.max_attempts(), 10);
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(5), Duration::from_secs(16));
+        assert_eq!(policy.backoff(6), Duration::from_secs(30));
+        // must not overflow
+        assert_eq!(policy.backoff(100), Duration::from_secs(30));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(30));
+
+        assert_eq!(RetryPolicy::no_retry().max_attempts(), 1);
+        assert_eq!(RetryPolicy::no_retry().backoff(1), Duration::ZERO);
+
+        let policy = RetryPolicy::default().with_max_attempts(NonZeroU32::new(2).unwrap());
+        assert_eq!(policy.max_attempts(), 2);
+        assert_eq!(policy.backoff(1), RetryPolicy::default().backoff(1));
+    }
+
+    #[test]
+    fn content_range() {
+        assert_eq!(parse_content_range("bytes 0-99/100"), Some((0, Some(100))));
+        assert_eq!(
+            parse_content_range("bytes 100-199/1000"),
+            Some((100, Some(1000)))
+        );
+        assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, None)));
+
+        assert_eq!(parse_content_range("bytes */1000"), None);
+        assert_eq!(parse_content_range("bytes 200-100/1000"), None);
+        assert_eq!(parse_content_range("bytes 100-199"), None);
+        assert_eq!(parse_content_range("items 100-199/1000"), None);
+        assert_eq!(parse_content_range("bytes a-199/1000"), None);
+    }
+}
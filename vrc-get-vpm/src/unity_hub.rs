@@ -10,6 +10,8 @@ use std::process::Output;
 use std::str::from_utf8;
 use tokio::process::Command;
 
+pub use find_unity_from_unity_hub_logic::ChipArchitecture;
+pub use find_unity_from_unity_hub_logic::find_unity_editors_in_search_paths;
 pub use find_unity_from_unity_hub_logic::load_unity_by_loading_unity_hub_files;
 pub use os::load_unity_version;
 
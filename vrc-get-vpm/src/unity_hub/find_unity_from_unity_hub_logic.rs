@@ -11,12 +11,21 @@ use std::result;
 
 type Result<T> = result::Result<T, std::io::Error>;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ChipArchitecture {
     X86_64,
     ARM64,
 }
 
+impl std::fmt::Display for ChipArchitecture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChipArchitecture::X86_64 => f.write_str("x86_64"),
+            ChipArchitecture::ARM64 => f.write_str("arm64"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UnityEditorInHub {
     pub version: UnityVersion,
@@ -47,10 +56,7 @@ pub async fn load_unity_by_loading_unity_hub_files() -> Result<Vec<UnityEditorIn
     )
     .await?;
 
-    Ok(a.into_iter()
-        .chain(b.into_iter())
-        .chain(c.into_iter())
-        .collect())
+    Ok(a.into_iter().chain(b).chain(c).collect())
 }
 
 async fn get_custom_install_location(local_settings: &LocalSettings) -> Option<PathBuf> {
@@ -73,23 +79,69 @@ async fn get_custom_install_location(local_settings: &LocalSettings) -> Option<P
 async fn find_unity_editors_in_folder(folder_path: &Path) -> Result<Vec<UnityEditorInHub>> {
     let editor_folders = find_unity_editor_folder_in_folder(folder_path).await?;
 
-    Ok(
-        join_all(editor_folders.into_iter().map(|folder_path| async move {
-            let editor_exe_path = os::editor_path_from_folder(&folder_path);
-            let version = os::load_unity_version(&editor_exe_path).await.ok()?;
-            let architecture = os::load_editor_architecture(&editor_exe_path).await.ok()?;
-
-            Some(UnityEditorInHub {
-                version,
-                path: editor_exe_path,
-                architecture: Some(architecture),
-            })
-        }))
-        .await
-        .into_iter()
-        .flatten()
-        .collect(),
-    )
+    Ok(load_editors(editor_folders).await)
+}
+
+async fn load_editors(editor_folders: Vec<PathBuf>) -> Vec<UnityEditorInHub> {
+    join_all(editor_folders.into_iter().map(|folder_path| async move {
+        let editor_exe_path = os::editor_path_from_folder(&folder_path);
+        let version = os::load_unity_version(&editor_exe_path).await.ok()?;
+        let architecture = os::load_editor_architecture(&editor_exe_path).await.ok()?;
+
+        Some(UnityEditorInHub {
+            version,
+            path: editor_exe_path,
+            architecture: Some(architecture),
+        })
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// The depth of folders searched for Unity editors from the search paths.
+const MAX_SEARCH_DEPTH: usize = 3;
+
+/// Finds Unity editors placed in the folders without Unity Hub, e.g. unpacked from archives.
+///
+/// The folders are searched up to a few levels deep, and the version and architecture
+/// are read from the files of the editor without launching it.
+/// Folders not found are ignored.
+pub async fn find_unity_editors_in_search_paths(search_paths: &[PathBuf]) -> Vec<UnityEditorInHub> {
+    let mut editor_folders = Vec::new();
+    let mut searching = (search_paths.iter())
+        .map(|x| (x.clone(), 0))
+        .collect::<Vec<_>>();
+
+    while let Some((folder, depth)) = searching.pop() {
+        if tokio::fs::try_exists(os::editor_path_from_folder(&folder))
+            .await
+            .unwrap_or_default()
+        {
+            editor_folders.push(folder);
+            continue;
+        }
+
+        if depth >= MAX_SEARCH_DEPTH {
+            continue;
+        }
+
+        let Ok(mut entries) = tokio::fs::read_dir(&folder).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            // symlinks are not followed to avoid loops
+            if entry.file_type().await.is_ok_and(|x| x.is_dir()) {
+                searching.push((entry.path(), depth + 1));
+            }
+        }
+    }
+
+    editor_folders.sort();
+    editor_folders.dedup();
+
+    load_editors(editor_folders).await
 }
 
 async fn find_unity_editor_folder_in_folder(folder_path: &Path) -> Result<Vec<PathBuf>> {
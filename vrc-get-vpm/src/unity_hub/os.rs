@@ -96,6 +96,7 @@ mod darwin {
 #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
 mod linux {
     use super::*;
+    use tokio::io::AsyncReadExt;
 
     pub fn app_path() -> PathBuf {
         std::env::var_os("HOME")
@@ -126,6 +127,11 @@ mod linux {
     }
 
     pub async fn load_unity_version(unity: &Path) -> Result<UnityVersion> {
+        // editors unpacked from the archive can be anywhere, so the folder name may not be the version
+        if let Some(version) = load_unity_version_from_modules(unity).await {
+            return Ok(version);
+        }
+
         let version_name = unity
             .parent()
             .and_then(|x| x.parent())
@@ -144,9 +150,119 @@ mod linux {
             .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid version"))
     }
 
-    pub async fn load_editor_architecture(_unity: &Path) -> Result<ChipArchitecture> {
-        // Linux ARM is not supported by Unity
-        Ok(ChipArchitecture::X86_64)
+    /// Reads the version from `ivy.xml` of the modules bundled with the editor.
+    async fn load_unity_version_from_modules(unity: &Path) -> Option<UnityVersion> {
+        let data = unity.parent()?.join("Data");
+
+        for modules in ["PlaybackEngines", "UnityExtensions/Unity"] {
+            let Ok(mut entries) = tokio::fs::read_dir(data.join(modules)).await else {
+                continue;
+            };
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let Ok(ivy) = tokio::fs::read_to_string(entry.path().join("ivy.xml")).await else {
+                    continue;
+                };
+                if let Some(version) = parse_ivy_unity_version(&ivy) {
+                    return Some(version);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Parses `e:unityVersion` attribute of the `info` element in `ivy.xml`
+    fn parse_ivy_unity_version(ivy: &str) -> Option<UnityVersion> {
+        let (_, version) = ivy.split_once("unityVersion=\"")?;
+        let (version, _) = version.split_once('"')?;
+        UnityVersion::parse(version)
+    }
+
+    const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+    /// The size of the ELF header up to `e_machine`
+    const ELF_HEADER_SIZE: usize = 20;
+
+    pub async fn load_editor_architecture(unity: &Path) -> Result<ChipArchitecture> {
+        let mut header = Vec::with_capacity(ELF_HEADER_SIZE);
+        tokio::fs::File::open(unity)
+            .await?
+            .take(ELF_HEADER_SIZE as u64)
+            .read_to_end(&mut header)
+            .await?;
+
+        // Linux ARM is not supported by Unity, so assume x86_64 if the header is unknown
+        Ok(parse_elf_architecture(&header).unwrap_or(ChipArchitecture::X86_64))
+    }
+
+    /// Parses `e_machine` of the ELF header
+    fn parse_elf_architecture(header: &[u8]) -> Option<ChipArchitecture> {
+        if header.len() < ELF_HEADER_SIZE || &header[0..4] != ELF_MAGIC {
+            return None;
+        }
+
+        let machine = header[18..][..2].try_into().unwrap();
+        let machine = match header[5] {
+            // ELFDATA2MSB
+            2 => u16::from_be_bytes(machine),
+            _ => u16::from_le_bytes(machine),
+        };
+
+        match machine {
+            0x3e => Some(ChipArchitecture::X86_64),
+            0xb7 => Some(ChipArchitecture::ARM64),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn ivy_unity_version() {
+            assert_eq!(
+                parse_ivy_unity_version(
+                    r#"<ivy-module version="2.0"><info e:unityVersion="2022.3.22f1" /></ivy-module>"#
+                ),
+                Some(UnityVersion::new_f1(2022, 3, 22))
+            );
+            assert_eq!(
+                parse_ivy_unity_version(r#"<ivy-module version="2.0"><info /></ivy-module>"#),
+                None
+            );
+            assert_eq!(parse_ivy_unity_version(r#"unityVersion="invalid""#), None);
+        }
+
+        #[test]
+        fn elf_architecture() {
+            fn header(data: u8, machine: [u8; 2]) -> Vec<u8> {
+                let mut header = vec![0u8; ELF_HEADER_SIZE];
+                header[0..4].copy_from_slice(ELF_MAGIC);
+                header[4] = 2; // ELFCLASS64
+                header[5] = data;
+                header[18..20].copy_from_slice(&machine);
+                header
+            }
+
+            assert_eq!(
+                parse_elf_architecture(&header(1, [0x3e, 0])),
+                Some(ChipArchitecture::X86_64)
+            );
+            assert_eq!(
+                parse_elf_architecture(&header(1, [0xb7, 0])),
+                Some(ChipArchitecture::ARM64)
+            );
+            assert_eq!(
+                parse_elf_architecture(&header(2, [0, 0xb7])),
+                Some(ChipArchitecture::ARM64)
+            );
+            // unknown machine
+            assert_eq!(parse_elf_architecture(&header(1, [0x28, 0])), None);
+            // short file
+            assert_eq!(parse_elf_architecture(&header(1, [0x3e, 0])[..16]), None);
+            // not an ELF file
+            assert_eq!(parse_elf_architecture(b"#!/bin/sh\nexec unity\n"), None);
+        }
     }
 }
 
@@ -1,6 +1,9 @@
 mod add_package;
+mod backtracking_resolution;
+mod dependency_paths;
 mod find_legacy_assets;
-mod migrate_unity_2022;
+mod history;
+mod migrate_unity;
 mod migrate_vpm;
 mod package_resolution;
 pub mod pending_project_changes;
@@ -8,9 +11,12 @@ mod project_type;
 mod reinstall;
 mod remove_package;
 mod resolve;
+mod scan_legacy_assets;
 mod upm_manifest;
 mod vpm_manifest;
+mod yanked_packages;
 
+use crate::advisory::AdvisoryDatabase;
 use crate::unity_project::upm_manifest::UpmManifest;
 use crate::unity_project::vpm_manifest::VpmManifest;
 use crate::utils::{PathBufExt, try_load_json};
@@ -30,12 +36,32 @@ use crate::io::{DefaultProjectIo, DirEntry, IoTrait, TokioDirEntry};
 use crate::package_manifest::LooseManifest;
 pub use add_package::AddPackageErr;
 pub use add_package::AddPackageOperation;
-pub use migrate_unity_2022::MigrateUnity2022Error;
+pub use backtracking_resolution::DerivationStep;
+pub use backtracking_resolution::ResolutionConflict;
+pub use backtracking_resolution::ResolverMode;
+pub use dependency_paths::DependencyEdge;
+pub use dependency_paths::DependencyPath;
+pub(crate) use find_legacy_assets::Guid;
+pub use history::HistoryChange;
+pub use history::HistoryEntry;
+pub use migrate_unity::BlockingPackage;
+pub use migrate_unity::MigrateUnityError;
+#[deprecated(note = "use MigrateUnityError instead")]
+pub type MigrateUnity2022Error = MigrateUnityError;
+pub use migrate_unity::MigrationIssue;
+pub use migrate_unity::MigrationIssueReason;
+pub use migrate_unity::UnityMigrationTarget;
+pub use migrate_vpm::LegacyImportedPackage;
 pub use migrate_vpm::MigrateVpmError;
+pub use pending_project_changes::ApplyPendingChangesErr;
 pub use pending_project_changes::PendingProjectChanges;
 pub use reinstall::ReinstalPackagesError;
 pub use remove_package::RemovePackageErr;
+pub use resolve::LockedMismatch;
 pub use resolve::ResolvePackageErr;
+pub use scan_legacy_assets::LegacyAsset;
+pub use vpm_manifest::LockedRepository;
+pub use yanked_packages::YankedLockedPackage;
 
 #[derive(Debug)]
 pub struct UnityProject {
@@ -52,6 +78,10 @@ pub struct UnityProject {
     unlocked_packages: Vec<(Box<str>, Option<PackageManifest>)>,
     /// packages installed in the directory and licked in vpm-manifest.json
     installed_packages: HashMap<Box<str>, PackageManifest>,
+    /// the algorithm to resolve dependencies
+    resolver_mode: ResolverMode,
+    /// the advisories denying packages to be installed
+    advisories: AdvisoryDatabase,
 }
 
 // basic lifecycle
@@ -126,6 +156,8 @@ impl UnityProject {
             unity_revision,
             unlocked_packages,
             installed_packages,
+            resolver_mode: ResolverMode::default(),
+            advisories: AdvisoryDatabase::new(),
         })
     }
 }
@@ -237,6 +269,10 @@ impl UnityProject {
         self.manifest.dependencies().map(|(name, _)| name)
     }
 
+    pub fn get_dependency(&self, name: &str) -> Option<&DependencyRange> {
+        self.manifest.get_dependency(name)
+    }
+
     pub fn get_locked(&self, name: &str) -> Option<LockedDependencyInfo<'_>> {
         self.manifest.get_locked(name)
     }
@@ -279,6 +315,26 @@ impl UnityProject {
         )
     }
 
+    pub fn resolver_mode(&self) -> ResolverMode {
+        self.resolver_mode
+    }
+
+    /// Sets the algorithm used to resolve dependencies in the following requests.
+    pub fn set_resolver_mode(&mut self, mode: ResolverMode) {
+        self.resolver_mode = mode;
+    }
+
+    pub fn advisories(&self) -> &AdvisoryDatabase {
+        &self.advisories
+    }
+
+    /// Sets the advisories checked in the following requests.
+    ///
+    /// Requests installing packages matching the advisories fail.
+    pub fn set_advisories(&mut self, advisories: AdvisoryDatabase) {
+        self.advisories = advisories;
+    }
+
     pub fn unity_version(&self) -> UnityVersion {
         self.unity_version
     }
@@ -291,6 +347,11 @@ impl UnityProject {
         self.upm_manifest.get_dependency(name).is_some()
     }
 
+    /// Returns the dependencies in `Packages/manifest.json` with their version or other notation like git url.
+    pub fn upm_dependencies(&self) -> impl Iterator<Item = (&str, impl std::fmt::Display + '_)> {
+        self.upm_manifest.dependencies()
+    }
+
     /// Adds dependency without actually adding package.
     /// This only modifies manifest
     pub fn add_dependency_raw(&mut self, name: &str, version: DependencyRange) {
@@ -309,6 +370,8 @@ pub struct LockedDependencyInfo<'a> {
     name: &'a str,
     version: &'a Version,
     dependencies: &'a IndexMap<Box<str>, VersionRange>,
+    zip_sha_256: Option<&'a str>,
+    repository: Option<&'a LockedRepository>,
 }
 
 impl<'a> LockedDependencyInfo<'a> {
@@ -324,6 +387,20 @@ impl<'a> LockedDependencyInfo<'a> {
             name,
             version,
             dependencies: dependencies.unwrap_or(&*EMPTY_DEPENDENCIES),
+            zip_sha_256: None,
+            repository: None,
+        }
+    }
+
+    fn with_source(
+        self,
+        zip_sha_256: Option<&'a str>,
+        repository: Option<&'a LockedRepository>,
+    ) -> Self {
+        Self {
+            zip_sha_256,
+            repository,
+            ..self
         }
     }
 
@@ -338,4 +415,14 @@ impl<'a> LockedDependencyInfo<'a> {
     pub fn dependencies(&self) -> &'a IndexMap<Box<str>, VersionRange> {
         self.dependencies
     }
+
+    /// The SHA-256 hash of the zip file recorded when the package is locked.
+    pub fn zip_sha_256(&self) -> Option<&'a str> {
+        self.zip_sha_256
+    }
+
+    /// The repository recorded when the package is locked.
+    pub fn repository(&self) -> Option<&'a LockedRepository> {
+        self.repository
+    }
 }
@@ -1,3 +1,4 @@
+use crate::advisory::AdvisoryMatch;
 use crate::unity_project::package_resolution::MissingDependencies;
 use crate::unity_project::pending_project_changes::RemoveReason;
 use crate::unity_project::vpm_manifest::VpmManifest;
@@ -22,6 +23,10 @@ pub enum AddPackageErr {
     UpgradingWithDowngrade {
         package_name: Box<str>,
     },
+    /// Some packages to be installed match the advisories of the project.
+    DeniedByAdvisories {
+        matches: Vec<AdvisoryMatch>,
+    },
 }
 
 impl fmt::Display for AddPackageErr {
@@ -51,6 +56,13 @@ impl fmt::Display for AddPackageErr {
                 f,
                 "Package {package_name} is locked, so it cannot be downgraded"
             ),
+            AddPackageErr::DeniedByAdvisories { matches } => {
+                writeln!(f, "Following packages are denied by security advisories:")?;
+                for advisory_match in matches {
+                    writeln!(f, "- {advisory_match}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -299,6 +311,7 @@ impl UnityProject {
             env,
             adding_packages,
             allow_prerelease,
+            self.resolver_mode,
             &mut missing_dependencies,
         );
         if !missing_dependencies.is_empty() {
@@ -329,6 +342,11 @@ impl UnityProject {
             changes.conflict_multiple(package, conflicts_with);
         }
 
+        if let Some(conflict) = result.resolution_conflict {
+            debug!("dependencies cannot be resolved:\n{conflict}");
+            changes.resolution_conflict(conflict);
+        }
+
         for name in result
             .found_legacy_packages
             .into_iter()
@@ -338,6 +356,11 @@ impl UnityProject {
             changes.remove(name, RemoveReason::Legacy);
         }
 
+        let matches = (self.advisories).check_packages(changes.get_all_install_packages());
+        if !matches.is_empty() {
+            return Err(AddPackageErr::DeniedByAdvisories { matches });
+        }
+
         debug!("Building changes (finding legacy assets, checking conflicts)");
 
         let changes = changes.build_resolve(self).await;
@@ -0,0 +1,657 @@
+//! Backtracking dependency resolver.
+//!
+//! Unlike the greedy resolver in `package_resolution`, this resolver searches older versions of
+//! packages when the newest versions conflict with each other.
+//! Locked packages are not downgraded to resolve conflicts as long as the locked version satisfies
+//! the requirements.
+//! When no combination of versions satisfies all requirements, it explains why with a list of
+//! [`DerivationStep`]s.
+
+use crate::traits::PackageCollection;
+use crate::unity_project::LockedDependencyInfo;
+use crate::unity_project::package_resolution::PackageResolutionResult;
+use crate::version::{PrereleaseAcceptance, UnityVersion, Version, VersionRange};
+use crate::{PackageInfo, PackageManifest, unity_compatible};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// The limit of the number of decisions to prevent the resolver from running forever.
+const MAX_DECISIONS: usize = 10000;
+
+/// The algorithm used to resolve dependencies.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolverMode {
+    /// Uses the latest version of each package and reports conflicts as is.
+    #[default]
+    Greedy,
+    /// Searches older versions of packages when the latest versions conflict.
+    Backtracking,
+}
+
+impl FromStr for ResolverMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(Self::Greedy),
+            "backtracking" => Ok(Self::Backtracking),
+            _ => Err(format!(
+                "unknown resolver mode: {s} (expected greedy or backtracking)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ResolverMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolverMode::Greedy => f.write_str("greedy"),
+            ResolverMode::Backtracking => f.write_str("backtracking"),
+        }
+    }
+}
+
+/// The explanation why the dependencies cannot be resolved.
+///
+/// The steps are ordered so that each step is derived from the steps before it,
+/// and the last step is the conclusion.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ResolutionConflict {
+    steps: Vec<DerivationStep>,
+}
+
+impl ResolutionConflict {
+    pub fn steps(&self) -> &[DerivationStep] {
+        &self.steps
+    }
+}
+
+impl fmt::Display for ResolutionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i != 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{step}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DerivationStep {
+    /// `dependant` requires `dependency` in `range`.
+    /// `dependant` is `None` if the requirement is from the project itself.
+    Requires {
+        dependant: Option<(Box<str>, Version)>,
+        dependency: Box<str>,
+        range: VersionRange,
+    },
+    /// `dependant` requires `dependency` in `range`, but `selected` version of `dependency` is chosen.
+    RequiresOtherVersion {
+        dependant: (Box<str>, Version),
+        dependency: Box<str>,
+        range: VersionRange,
+        selected: Version,
+    },
+    /// `package` is locked at `version` which satisfies the requirements, so older versions are not selected.
+    LockedVersion { package: Box<str>, version: Version },
+    /// No version of `package` satisfies all the requirements.
+    NoMatchingVersion { package: Box<str> },
+    /// All the `versions` of `package` lead to conflicts.
+    AllVersionsConflict {
+        package: Box<str>,
+        versions: Vec<Version>,
+    },
+    /// The resolver gave up because there are too many combinations to search.
+    TooComplex,
+}
+
+impl fmt::Display for DerivationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationStep::Requires {
+                dependant: Some((name, version)),
+                dependency,
+                range,
+            } => write!(f, "{name} {version} requires {dependency} {range}"),
+            DerivationStep::Requires {
+                dependant: None,
+                dependency,
+                range,
+            } => write!(f, "the project requires {dependency} {range}"),
+            DerivationStep::RequiresOtherVersion {
+                dependant: (name, version),
+                dependency,
+                range,
+                selected,
+            } => write!(
+                f,
+                "{name} {version} requires {dependency} {range}, but {dependency} {selected} is selected"
+            ),
+            DerivationStep::LockedVersion { package, version } => {
+                write!(f, "{package} is locked at {version} and is not downgraded")
+            }
+            DerivationStep::NoMatchingVersion { package } => {
+                write!(f, "no version of {package} satisfies all the requirements")
+            }
+            DerivationStep::AllVersionsConflict { package, versions } => {
+                write!(f, "every version of {package} (")?;
+                for (i, version) in versions.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{version}")?;
+                }
+                write!(f, ") leads to a conflict")
+            }
+            DerivationStep::TooComplex => {
+                f.write_str("gave up resolving: there are too many combinations of versions")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Candidate<'env, 'a> {
+    version: &'a Version,
+    dependencies: &'a IndexMap<Box<str>, VersionRange>,
+    legacy_packages: &'a [Box<str>],
+    // None if the package is kept as is without installing
+    package: Option<PackageInfo<'env>>,
+}
+
+impl<'env: 'a, 'a> Candidate<'env, 'a> {
+    fn from_package(package: PackageInfo<'env>) -> Self {
+        Self {
+            version: package.version(),
+            dependencies: package.vpm_dependencies(),
+            legacy_packages: package.legacy_packages(),
+            package: Some(package),
+        }
+    }
+
+    fn from_manifest(manifest: &'a PackageManifest) -> Self {
+        Self {
+            version: manifest.version(),
+            dependencies: manifest.vpm_dependencies(),
+            legacy_packages: manifest.legacy_packages(),
+            package: None,
+        }
+    }
+
+    fn from_locked(locked: &LockedDependencyInfo<'a>) -> Self {
+        Self {
+            version: locked.version(),
+            dependencies: locked.dependencies(),
+            legacy_packages: &[],
+            package: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Requirement<'a> {
+    // None for the project itself
+    source: Option<(&'a str, &'a Version)>,
+    range: &'a VersionRange,
+}
+
+impl Requirement<'_> {
+    fn to_step(self, dependency: &str) -> DerivationStep {
+        DerivationStep::Requires {
+            dependant: self
+                .source
+                .map(|(name, version)| (name.into(), version.clone())),
+            dependency: dependency.into(),
+            range: self.range.clone(),
+        }
+    }
+}
+
+struct Failure<'a> {
+    steps: Vec<DerivationStep>,
+    // the packages whose selected version contributed to this failure
+    involved: HashSet<&'a str>,
+    // true if we should stop searching
+    abort: bool,
+}
+
+impl Failure<'_> {
+    fn add_step(&mut self, step: DerivationStep) {
+        if !self.steps.contains(&step) {
+            self.steps.push(step);
+        }
+    }
+}
+
+pub(crate) struct BacktrackingResolver<'env, 'a, C: PackageCollection> {
+    env: &'env C,
+    unity_version: Option<UnityVersion>,
+    allow_prerelease: bool,
+    locked: HashMap<&'a str, LockedDependencyInfo<'a>>,
+    requested: HashSet<&'a str>,
+    ignored: HashSet<&'a str>,
+    assignments: IndexMap<&'a str, Candidate<'env, 'a>>,
+    requirements: HashMap<&'a str, Vec<Requirement<'a>>>,
+    decisions: usize,
+}
+
+impl<'env: 'a, 'a, C: PackageCollection> BacktrackingResolver<'env, 'a, C> {
+    pub(crate) fn new(
+        env: &'env C,
+        unity_version: Option<UnityVersion>,
+        allow_prerelease: bool,
+    ) -> Self {
+        Self {
+            env,
+            unity_version,
+            allow_prerelease,
+            locked: HashMap::new(),
+            requested: HashSet::new(),
+            ignored: HashSet::new(),
+            assignments: IndexMap::new(),
+            requirements: HashMap::new(),
+            decisions: 0,
+        }
+    }
+
+    pub(crate) fn add_root_dependency(&mut self, name: &'a str, range: &'a VersionRange) {
+        self.requirements
+            .entry(name)
+            .or_default()
+            .push(Requirement {
+                source: None,
+                range,
+            });
+    }
+
+    pub(crate) fn add_locked_dependency(&mut self, locked: LockedDependencyInfo<'a>) {
+        self.locked.insert(locked.name(), locked);
+    }
+
+    pub(crate) fn add_unlocked_name(&mut self, name: &'a str) {
+        self.ignored.insert(name);
+    }
+
+    pub(crate) fn add_unlocked_manifest(&mut self, manifest: &'a PackageManifest) {
+        self.assign(manifest.name(), Candidate::from_manifest(manifest));
+    }
+
+    pub(crate) fn add_requested_package(&mut self, package: PackageInfo<'env>) {
+        self.requested.insert(package.name());
+        self.assign(package.name(), Candidate::from_package(package));
+    }
+
+    pub(crate) fn resolve(mut self) -> Result<PackageResolutionResult<'env>, ResolutionConflict> {
+        // the packages we must install may conflict with each other
+        let fixed = self.assignments.clone();
+        for (&name, candidate) in &fixed {
+            if let Some(failure) = self.check_dependencies(name, candidate) {
+                return Err(ResolutionConflict {
+                    steps: failure.steps,
+                });
+            }
+        }
+
+        if let Err(failure) = self.solve() {
+            return Err(ResolutionConflict {
+                steps: failure.steps,
+            });
+        }
+
+        let legacy_packages = self.legacy_packages();
+
+        let new_packages = self
+            .assignments
+            .iter()
+            .filter(|(name, _)| !legacy_packages.contains(*name))
+            .filter_map(|(&name, candidate)| {
+                let package = candidate.package?;
+                let keep_locked = self
+                    .locked
+                    .get(name)
+                    .map(|locked| locked.version() == package.version())
+                    .unwrap_or(false);
+                (!keep_locked || self.requested.contains(name)).then_some(package)
+            })
+            .collect();
+
+        Ok(PackageResolutionResult {
+            new_packages,
+            conflicts: HashMap::new(),
+            found_legacy_packages: legacy_packages.into_iter().map(Into::into).collect(),
+            resolution_conflict: None,
+        })
+    }
+
+    fn solve(&mut self) -> Result<(), Failure<'a>> {
+        self.decisions += 1;
+        if self.decisions > MAX_DECISIONS {
+            return Err(Failure {
+                steps: vec![DerivationStep::TooComplex],
+                involved: HashSet::new(),
+                abort: true,
+            });
+        }
+
+        let Some(name) = self.next_package() else {
+            return Ok(());
+        };
+
+        let requirements = self.active_requirements(name);
+        let candidates = self.candidates(name, &requirements);
+
+        // the packages whose selected version made this package required
+        let required_by = requirements
+            .iter()
+            .filter_map(|x| x.source)
+            .map(|(source, _)| source)
+            .collect::<HashSet<_>>();
+
+        if candidates.is_empty() {
+            log::debug!("backtracking: no candidates for {name}");
+            let mut steps = requirements
+                .iter()
+                .map(|x| x.to_step(name))
+                .collect::<Vec<_>>();
+            steps.push(DerivationStep::NoMatchingVersion {
+                package: name.into(),
+            });
+            return Err(Failure {
+                steps,
+                involved: required_by,
+                abort: false,
+            });
+        }
+
+        let mut combined = Failure {
+            steps: vec![],
+            involved: required_by,
+            abort: false,
+        };
+        let mut tried = vec![];
+
+        for candidate in candidates {
+            log::debug!("backtracking: trying {name} {}", candidate.version);
+            tried.push(candidate.version.clone());
+
+            let failure = if let Some(failure) = self.check_dependencies(name, &candidate) {
+                failure
+            } else {
+                self.assign(name, candidate);
+                match self.solve() {
+                    Ok(()) => return Ok(()),
+                    Err(failure) => {
+                        self.unassign(name);
+                        failure
+                    }
+                }
+            };
+
+            if failure.abort || !failure.involved.contains(name) {
+                // selecting another version of this package won't fix this failure
+                return Err(failure);
+            }
+
+            for step in failure.steps {
+                combined.add_step(step);
+            }
+            combined
+                .involved
+                .extend(failure.involved.into_iter().filter(|&x| x != name));
+        }
+
+        if let Some(step) = self.locked_step(name, &requirements) {
+            combined.add_step(step);
+        }
+        combined.add_step(DerivationStep::AllVersionsConflict {
+            package: name.into(),
+            versions: tried,
+        });
+
+        Err(combined)
+    }
+
+    /// Returns the next package to select version.
+    fn next_package(&self) -> Option<&'a str> {
+        let legacy_packages = self.legacy_packages();
+
+        let required = self
+            .requirements
+            .iter()
+            .filter(|(_, requirements)| !requirements.is_empty())
+            .map(|(&name, _)| name);
+
+        required
+            .chain(self.locked.keys().copied())
+            .filter(|name| !self.assignments.contains_key(name))
+            .filter(|name| !self.ignored.contains(name))
+            .filter(|name| !legacy_packages.contains(name))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .next()
+    }
+
+    fn legacy_packages(&self) -> HashSet<&'a str> {
+        self.assignments
+            .values()
+            .flat_map(|x| x.legacy_packages.iter())
+            .map(|x| x.as_ref())
+            .collect()
+    }
+
+    fn active_requirements(&self, name: &str) -> Vec<Requirement<'a>> {
+        let legacy_packages = self.legacy_packages();
+        self.requirements
+            .get(name)
+            .map(|requirements| {
+                requirements
+                    .iter()
+                    .filter(|x| {
+                        x.source
+                            .map(|(source, _)| !legacy_packages.contains(source))
+                            .unwrap_or(true)
+                    })
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn prerelease_acceptance(
+        &self,
+        name: &str,
+        requirements: &[Requirement<'a>],
+    ) -> PrereleaseAcceptance {
+        let allow_pre = self.allow_prerelease
+            || self.requested.contains(name)
+            || self
+                .locked
+                .get(name)
+                .map(|x| x.version().is_pre())
+                .unwrap_or(false)
+            || requirements.iter().any(|x| {
+                x.range.contains_pre()
+                    || x.source
+                        .map(|(_, version)| version.is_pre())
+                        .unwrap_or(false)
+            });
+        PrereleaseAcceptance::allow_or_minimum(allow_pre)
+    }
+
+    fn matches_requirements(
+        &self,
+        name: &str,
+        requirements: &[Requirement<'a>],
+    ) -> impl Fn(&Version) -> bool {
+        let acceptance = self.prerelease_acceptance(name, requirements);
+        move |version: &Version| {
+            requirements
+                .iter()
+                .all(|x| x.range.match_pre(version, acceptance))
+        }
+    }
+
+    /// Returns the locked version of the package if it satisfies the requirements.
+    ///
+    /// We never select older versions than this since the user did not ask to downgrade it.
+    fn pinned_version(&self, name: &str, requirements: &[Requirement<'a>]) -> Option<&'a Version> {
+        let matches = self.matches_requirements(name, requirements);
+        self.locked
+            .get(name)
+            .map(|x| x.version())
+            .filter(|&version| matches(version))
+    }
+
+    /// Returns the step explaining the lock if the lock excluded some versions of the package.
+    fn locked_step(&self, name: &str, requirements: &[Requirement<'a>]) -> Option<DerivationStep> {
+        let pinned = self.pinned_version(name, requirements)?;
+        let matches = self.matches_requirements(name, requirements);
+        self.env
+            .find_packages(name)
+            .filter(|x| !x.is_yanked())
+            .any(|x| x.version() < pinned && matches(x.version()))
+            .then(|| DerivationStep::LockedVersion {
+                package: name.into(),
+                version: pinned.clone(),
+            })
+    }
+
+    /// Lists the versions of the package satisfying all the requirements, in the order of preference.
+    ///
+    /// The locked version is the most preferred, then the latest version compatible with the unity.
+    /// Versions older than the locked version are listed only if the locked version doesn't
+    /// satisfy the requirements.
+    fn candidates(
+        &self,
+        name: &'a str,
+        requirements: &[Requirement<'a>],
+    ) -> Vec<Candidate<'env, 'a>> {
+        let matches = self.matches_requirements(name, requirements);
+        let minimum = self.pinned_version(name, requirements);
+
+        let mut candidates = vec![];
+        let mut seen = HashSet::new();
+
+        if let Some(locked) = self.locked.get(name)
+            && matches(locked.version())
+        {
+            let package = self
+                .env
+                .find_packages(name)
+                .find(|x| x.version() == locked.version());
+            candidates.push(match package {
+                Some(package) => Candidate::from_package(package),
+                None => Candidate::from_locked(locked),
+            });
+            seen.insert(locked.version());
+        }
+
+        let mut packages = self
+            .env
+            .find_packages(name)
+            .filter(|x| !x.is_yanked())
+            .filter(|x| minimum.is_none_or(|minimum| x.version() >= minimum))
+            .filter(|x| matches(x.version()))
+            .collect::<Vec<_>>();
+
+        let unity_version = self.unity_version;
+        packages.sort_by_key(|x| {
+            let compatible = unity_version
+                .map(|unity| unity_compatible(x.package_json(), unity))
+                .unwrap_or(true);
+            (
+                std::cmp::Reverse(compatible),
+                std::cmp::Reverse(x.version().is_stable()),
+                std::cmp::Reverse(x.version()),
+            )
+        });
+
+        for package in packages {
+            if seen.insert(package.version()) {
+                candidates.push(Candidate::from_package(package));
+            }
+        }
+
+        candidates
+    }
+
+    /// Checks the dependencies of the candidate with the already selected packages.
+    fn check_dependencies(
+        &self,
+        name: &'a str,
+        candidate: &Candidate<'env, 'a>,
+    ) -> Option<Failure<'a>> {
+        let legacy_packages = self.legacy_packages();
+
+        for (dependency, range) in candidate.dependencies {
+            let dependency = dependency.as_ref();
+            if legacy_packages.contains(dependency) || self.ignored.contains(dependency) {
+                continue;
+            }
+            let Some((&dependency, selected)) = self.assignments.get_key_value(dependency) else {
+                continue;
+            };
+            if dependency == name {
+                continue;
+            }
+
+            let mut requirements = self.active_requirements(dependency);
+            requirements.push(Requirement {
+                source: Some((name, candidate.version)),
+                range,
+            });
+            let acceptance = self.prerelease_acceptance(dependency, &requirements);
+
+            if !range.match_pre(selected.version, acceptance) {
+                log::debug!(
+                    "backtracking: {name} {} requires {dependency} {range} but {} is selected",
+                    candidate.version,
+                    selected.version,
+                );
+                return Some(Failure {
+                    steps: vec![DerivationStep::RequiresOtherVersion {
+                        dependant: (name.into(), candidate.version.clone()),
+                        dependency: dependency.into(),
+                        range: range.clone(),
+                        selected: selected.version.clone(),
+                    }],
+                    involved: HashSet::from([name, dependency]),
+                    abort: false,
+                });
+            }
+        }
+
+        None
+    }
+
+    fn assign(&mut self, name: &'a str, candidate: Candidate<'env, 'a>) {
+        for (dependency, range) in candidate.dependencies {
+            self.requirements
+                .entry(dependency.as_ref())
+                .or_default()
+                .push(Requirement {
+                    source: Some((name, candidate.version)),
+                    range,
+                });
+        }
+        self.assignments.insert(name, candidate);
+    }
+
+    fn unassign(&mut self, name: &'a str) {
+        let Some(candidate) = self.assignments.shift_remove(name) else {
+            return;
+        };
+        for dependency in candidate.dependencies.keys() {
+            if let Some(requirements) = self.requirements.get_mut(dependency.as_ref()) {
+                requirements.retain(|x| x.source.map(|(source, _)| source) != Some(name));
+            }
+        }
+    }
+}
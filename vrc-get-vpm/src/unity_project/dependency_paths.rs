@@ -0,0 +1,148 @@
+use crate::UnityProject;
+use crate::version::{Version, VersionRange};
+use std::collections::{HashMap, HashSet};
+
+/// An edge of the dependency graph of the project.
+#[derive(Debug, Clone)]
+pub struct DependencyEdge<'a> {
+    dependant: Option<&'a str>,
+    dependant_version: Option<&'a Version>,
+    dependency: &'a str,
+    range: Option<VersionRange>,
+}
+
+impl<'a> DependencyEdge<'a> {
+    /// The name of the package that requires the dependency.
+    ///
+    /// `None` if the dependency is in the `dependencies` section of the project.
+    pub fn dependant(&self) -> Option<&'a str> {
+        self.dependant
+    }
+
+    /// The locked version of the dependant package.
+    ///
+    /// `None` for the project itself or for the legacy package replaced by the dependency.
+    pub fn dependant_version(&self) -> Option<&'a Version> {
+        self.dependant_version
+    }
+
+    pub fn dependency(&self) -> &'a str {
+        self.dependency
+    }
+
+    /// The version range the dependant imposes on the dependency.
+    ///
+    /// `None` if the dependency is installed as a replacement of the legacy package.
+    pub fn range(&self) -> Option<&VersionRange> {
+        self.range.as_ref()
+    }
+
+    /// Returns true if the dependency is installed as a replacement of the legacy dependant package.
+    pub fn is_legacy_replacement(&self) -> bool {
+        self.range.is_none()
+    }
+}
+
+/// A path from the `dependencies` section of the project to a package.
+#[derive(Debug, Clone)]
+pub struct DependencyPath<'a> {
+    edges: Vec<DependencyEdge<'a>>,
+}
+
+impl<'a> DependencyPath<'a> {
+    pub fn edges(&self) -> &[DependencyEdge<'a>] {
+        &self.edges
+    }
+}
+
+impl UnityProject {
+    /// Lists every path from the `dependencies` of the project to the specified package
+    /// through the locked packages.
+    pub fn dependency_paths(&self, package: &str) -> Vec<DependencyPath<'_>> {
+        // None for the project itself
+        let mut graph = HashMap::<Option<&str>, Vec<DependencyEdge>>::new();
+
+        for (name, range) in self.manifest.dependencies() {
+            graph.entry(None).or_default().push(DependencyEdge {
+                dependant: None,
+                dependant_version: None,
+                dependency: name,
+                range: Some(range.as_range()),
+            });
+        }
+
+        for locked in self.locked_packages() {
+            for (dependency, range) in locked.dependencies() {
+                graph
+                    .entry(Some(locked.name()))
+                    .or_default()
+                    .push(DependencyEdge {
+                        dependant: Some(locked.name()),
+                        dependant_version: Some(locked.version()),
+                        dependency,
+                        range: Some(range.clone()),
+                    });
+            }
+
+            if let Some(installed) = self.get_installed_package(locked.name()) {
+                for legacy in installed.legacy_packages() {
+                    graph
+                        .entry(Some(legacy.as_ref()))
+                        .or_default()
+                        .push(DependencyEdge {
+                            dependant: Some(legacy),
+                            dependant_version: None,
+                            dependency: locked.name(),
+                            range: None,
+                        });
+                }
+            }
+        }
+
+        fn walk<'a>(
+            graph: &HashMap<Option<&'a str>, Vec<DependencyEdge<'a>>>,
+            node: Option<&'a str>,
+            target: &str,
+            visiting: &mut HashSet<&'a str>,
+            current: &mut Vec<DependencyEdge<'a>>,
+            paths: &mut Vec<DependencyPath<'a>>,
+        ) {
+            for edge in graph.get(&node).into_iter().flatten() {
+                if visiting.contains(edge.dependency) {
+                    // dependency loop
+                    continue;
+                }
+
+                current.push(edge.clone());
+                if edge.dependency == target {
+                    paths.push(DependencyPath {
+                        edges: current.clone(),
+                    });
+                } else {
+                    visiting.insert(edge.dependency);
+                    walk(
+                        graph,
+                        Some(edge.dependency),
+                        target,
+                        visiting,
+                        current,
+                        paths,
+                    );
+                    visiting.remove(edge.dependency);
+                }
+                current.pop();
+            }
+        }
+
+        let mut paths = vec![];
+        walk(
+            &graph,
+            None,
+            package,
+            &mut HashSet::new(),
+            &mut vec![],
+            &mut paths,
+        );
+        paths
+    }
+}
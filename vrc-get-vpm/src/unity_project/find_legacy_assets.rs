@@ -89,7 +89,7 @@ pub(crate) async fn collect_legacy_assets<'a>(
     }
 }
 
-fn valid_path(path: &Path) -> bool {
+pub(super) fn valid_path(path: &Path) -> bool {
     // removing folders other than Assets and Packages are not allowed.
     if !path.starts_with("Assets") && !path.starts_with("Packages") {
         return false;
@@ -169,7 +169,7 @@ async fn find_legacy_assets_by_path<'a>(
     (found_files, found_folders, find_guids)
 }
 
-async fn check_guid(io: &DefaultProjectIo, path: &Path, guid: Option<Guid>) -> bool {
+pub(super) async fn check_guid(io: &DefaultProjectIo, path: &Path, guid: Option<Guid>) -> bool {
     // for paths other than UdonSharp, we don't need to check the guid.
     if path != Path::new("Assets/UdonSharp") {
         return true;
@@ -184,7 +184,7 @@ async fn check_guid(io: &DefaultProjectIo, path: &Path, guid: Option<Guid>) -> b
     true
 }
 
-async fn try_parse_meta(io: &DefaultProjectIo, path: &Path) -> Option<Guid> {
+pub(super) async fn try_parse_meta(io: &DefaultProjectIo, path: &Path) -> Option<Guid> {
     let mut file = BufReader::new(io.open(path).await.ok()?);
     let mut buffer = String::new();
     while file.read_line(&mut buffer).await.ok()? != 0 {
@@ -270,10 +270,10 @@ enum LegacySearchResult<'a> {
 }
 
 #[derive(Copy, Clone, Hash, Eq, PartialEq)]
-struct Guid([u8; 16]);
+pub(crate) struct Guid([u8; 16]);
 
 impl Guid {
-    fn parse(guid: &str) -> Option<Guid> {
+    pub(crate) fn parse(guid: &str) -> Option<Guid> {
         FromHex::from_hex(guid).ok().map(Guid)
     }
 }
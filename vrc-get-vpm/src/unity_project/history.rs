@@ -0,0 +1,249 @@
+use crate::io::{DefaultProjectIo, DirEntry, IoTrait};
+use crate::unity_project::AddPackageErr;
+use crate::unity_project::pending_project_changes::{Builder, PendingProjectChanges, RemoveReason};
+use crate::unity_project::vpm_manifest::VpmManifest;
+use crate::utils::{save_json, try_load_json};
+use crate::version::{Version, VersionRange};
+use crate::{PackageCollection, UnityProject, VersionSelector, io};
+use futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const HISTORY_FOLDER: &str = ".vrc-get/history";
+/// The number of entries kept in the history. Older entries are removed on recording.
+const MAX_HISTORY_ENTRIES: usize = 50;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsJson {
+    /// unix time in milliseconds
+    created_at: u64,
+    changes: Vec<HistoryChange>,
+    /// the content of vpm-manifest.json before the operation
+    manifest: serde_json::Value,
+}
+
+/// The change of the locked version of a package by the recorded operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryChange {
+    name: Box<str>,
+    #[serde(default)]
+    before: Option<Version>,
+    #[serde(default)]
+    after: Option<Version>,
+}
+
+impl HistoryChange {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The locked version before the operation. `None` if the package was installed by the operation.
+    pub fn before(&self) -> Option<&Version> {
+        self.before.as_ref()
+    }
+
+    /// The locked version after the operation. `None` if the package was removed by the operation.
+    pub fn after(&self) -> Option<&Version> {
+        self.after.as_ref()
+    }
+}
+
+/// An entry of the project history, which holds the state of the project before an operation.
+#[derive(Debug)]
+pub struct HistoryEntry {
+    id: Box<str>,
+    created_at: SystemTime,
+    changes: Vec<HistoryChange>,
+    manifest: VpmManifest,
+}
+
+impl HistoryEntry {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+
+    pub fn changes(&self) -> &[HistoryChange] {
+        &self.changes
+    }
+}
+
+impl UnityProject {
+    /// Returns the history of the package operations on the project, newest first.
+    pub async fn history(&self) -> io::Result<Vec<HistoryEntry>> {
+        let mut read_dir = match self.io.read_dir(HISTORY_FOLDER.as_ref()).await {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = vec![];
+        while let Some(dir_entry) = read_dir.try_next().await? {
+            let file_name = dir_entry.file_name();
+            let Some(id) = file_name.to_str().and_then(|x| x.strip_suffix(".json")) else {
+                continue;
+            };
+            match load_entry(&self.io, id).await {
+                Ok(Some(entry)) => entries.push(entry),
+                Ok(None) => {}
+                Err(e) => log::warn!("error loading history entry {id}: {e}"),
+            }
+        }
+
+        // ids start with the timestamp
+        entries.sort_by(|a, b| b.id.cmp(&a.id));
+
+        Ok(entries)
+    }
+
+    /// Returns the history entry with the specified id.
+    pub async fn get_history(&self, id: &str) -> io::Result<Option<HistoryEntry>> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Ok(None);
+        }
+        load_entry(&self.io, id).await
+    }
+
+    /// Creates the changes to restore the project to the state recorded in the history entry.
+    ///
+    /// The packages are installed with the versions in the recorded `vpm-manifest.json`,
+    /// so the versions must be available in the repositories or the package cache.
+    pub async fn restore_history_request<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+        entry: &HistoryEntry,
+    ) -> Result<PendingProjectChanges<'env>, AddPackageErr> {
+        let mut changes = Builder::new();
+        let mut not_found = Vec::new();
+
+        for locked in entry.manifest.all_locked() {
+            // packages not changed since the entry are kept as is.
+            // missing packages should be installed with resolve, not with restore
+            if self.get_locked(locked.name()).map(|x| x.version()) == Some(locked.version()) {
+                continue;
+            }
+
+            match env.find_package_by_name(
+                locked.name(),
+                VersionSelector::specific_version(locked.version()),
+            ) {
+                Some(package) => {
+                    changes.install_to_locked(package);
+                }
+                None => not_found.push((
+                    locked.name().into(),
+                    VersionRange::specific(locked.version().clone()),
+                )),
+            }
+        }
+
+        if !not_found.is_empty() {
+            return Err(AddPackageErr::DependenciesNotFound {
+                dependencies: not_found,
+            });
+        }
+
+        for locked in self.locked_packages() {
+            if entry.manifest.get_locked(locked.name()).is_none() {
+                changes.remove(locked.name().into(), RemoveReason::Requested);
+            }
+        }
+
+        Ok(changes.build_restore(entry.manifest.clone()))
+    }
+}
+
+fn entry_path(id: &str) -> PathBuf {
+    PathBuf::from(format!("{HISTORY_FOLDER}/{id}.json"))
+}
+
+async fn load_entry(io: &DefaultProjectIo, id: &str) -> io::Result<Option<HistoryEntry>> {
+    let Some(json) = try_load_json::<AsJson>(io, &entry_path(id)).await? else {
+        return Ok(None);
+    };
+
+    Ok(Some(HistoryEntry {
+        id: id.into(),
+        created_at: SystemTime::UNIX_EPOCH + Duration::from_millis(json.created_at),
+        changes: json.changes,
+        manifest: VpmManifest::from_value(json.manifest)?,
+    }))
+}
+
+/// Records the manifest before the operation to the history.
+pub(super) async fn record_history(
+    io: &DefaultProjectIo,
+    before: &VpmManifest,
+    after: &VpmManifest,
+) -> io::Result<()> {
+    let manifest = before.to_value();
+    if manifest == after.to_value() {
+        // nothing changed
+        return Ok(());
+    }
+
+    let mut changes = Vec::new();
+    for locked in before.all_locked() {
+        let after_version = after.get_locked(locked.name()).map(|x| x.version());
+        if after_version != Some(locked.version()) {
+            changes.push(HistoryChange {
+                name: locked.name().into(),
+                before: Some(locked.version().clone()),
+                after: after_version.cloned(),
+            });
+        }
+    }
+    for locked in after.all_locked() {
+        if before.get_locked(locked.name()).is_none() {
+            changes.push(HistoryChange {
+                name: locked.name().into(),
+                before: None,
+                after: Some(locked.version().clone()),
+            });
+        }
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let id = format!(
+        "{created_at:013}-{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+
+    let json = AsJson {
+        created_at,
+        changes,
+        manifest,
+    };
+    save_json(io, &entry_path(&id), &json).await?;
+
+    prune_history(io).await
+}
+
+async fn prune_history(io: &DefaultProjectIo) -> io::Result<()> {
+    let mut read_dir = io.read_dir(HISTORY_FOLDER.as_ref()).await?;
+    let mut ids = vec![];
+    while let Some(dir_entry) = read_dir.try_next().await? {
+        if let Some(id) = (dir_entry.file_name().to_str()).and_then(|x| x.strip_suffix(".json")) {
+            ids.push(id.to_owned());
+        }
+    }
+
+    if ids.len() <= MAX_HISTORY_ENTRIES {
+        return Ok(());
+    }
+
+    ids.sort();
+    for id in &ids[..ids.len() - MAX_HISTORY_ENTRIES] {
+        io.remove_file(&entry_path(id)).await?;
+    }
+
+    Ok(())
+}
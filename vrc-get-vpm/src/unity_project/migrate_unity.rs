@@ -0,0 +1,604 @@
+use crate::environment::PackageHashMismatchError;
+use crate::unity_project::{
+    AddPackageErr, AddPackageOperation, ApplyPendingChangesErr, RemovePackageErr,
+};
+use crate::version::{UnityVersion, Version};
+use crate::{PackageCollection, PackageManifest, UnityProject, VersionSelector, unity_compatible};
+use crate::{PackageInstaller, VRCHAT_RECOMMENDED_2022_UNITY, io};
+use lazy_static::lazy_static;
+use log::warn;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The requirements on the project to migrate to a Unity version.
+///
+/// The targets are defined in the table of this module, one for each major.minor Unity version.
+#[derive(Debug)]
+pub struct UnityMigrationTarget {
+    /// major.minor of the target Unity versions
+    unity: (u16, u8),
+    recommended_unity: UnityVersion,
+    /// the major Unity versions projects can be migrated from
+    migrate_from: &'static [u16],
+    /// the minimum versions of the packages, upgraded to the latest version if older
+    minimum_packages: Vec<(&'static str, Version)>,
+    /// the packages replaced with the other packages
+    swap_packages: &'static [(&'static str, &'static str)],
+    /// the UPM packages not supported in the target
+    remove_upm_packages: &'static [&'static str],
+}
+
+lazy_static! {
+    static ref MIGRATION_TARGETS: Vec<UnityMigrationTarget> = vec![UnityMigrationTarget {
+        // See https://misskey.niri.la/notes/9nod7sk4sr for migration process
+        unity: (2022, 3),
+        recommended_unity: VRCHAT_RECOMMENDED_2022_UNITY,
+        migrate_from: &[2019, 2022],
+        minimum_packages: vec![
+            ("com.vrchat.base", Version::new(3, 5, 0)),
+            ("com.vrchat.avatars", Version::new(3, 5, 0)),
+            ("com.vrchat.worlds", Version::new(3, 5, 0)),
+            ("com.vrchat.core.vpm-resolver", Version::new(0, 1, 27)),
+        ],
+        swap_packages: &[],
+        // legacy XR packages
+        remove_upm_packages: &[
+            "com.unity.xr.oculus.standalone",
+            "com.unity.xr.openvr.standalone",
+        ],
+    }];
+}
+
+impl UnityMigrationTarget {
+    pub fn all() -> &'static [UnityMigrationTarget] {
+        &MIGRATION_TARGETS
+    }
+
+    /// Finds the target for the major.minor of the Unity version.
+    pub fn find(unity: UnityVersion) -> Option<&'static UnityMigrationTarget> {
+        (MIGRATION_TARGETS.iter()).find(|x| x.unity == (unity.major(), unity.minor()))
+    }
+
+    pub fn unity(&self) -> (u16, u8) {
+        self.unity
+    }
+
+    pub fn recommended_unity(&self) -> UnityVersion {
+        self.recommended_unity
+    }
+
+    pub fn minimum_packages(&self) -> impl Iterator<Item = (&str, &Version)> {
+        self.minimum_packages.iter().map(|(name, v)| (*name, v))
+    }
+
+    pub fn swap_packages(&self) -> &[(&'static str, &'static str)] {
+        self.swap_packages
+    }
+
+    pub fn remove_upm_packages(&self) -> &[&'static str] {
+        self.remove_upm_packages
+    }
+
+    /// Returns whether a project with `current` Unity can be migrated to `target` Unity.
+    pub fn can_migrate_from(&self, current: UnityVersion, target: UnityVersion) -> bool {
+        current < target && self.migrate_from.contains(&current.major())
+    }
+}
+
+/// A package in the project not compatible with the target Unity version.
+#[derive(Debug, Clone)]
+pub struct BlockingPackage {
+    name: Box<str>,
+    version: Version,
+    minimum_unity: Option<(u16, u8)>,
+}
+
+impl BlockingPackage {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// The minimum Unity version declared by the package.
+    ///
+    /// `None` for VRCSDK and the resolver only for Unity 2019.
+    pub fn minimum_unity(&self) -> Option<(u16, u8)> {
+        self.minimum_unity
+    }
+}
+
+impl fmt::Display for BlockingPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.minimum_unity {
+            Some((major, minor)) => write!(
+                f,
+                "{}@{} requires Unity {major}.{minor} or later",
+                self.name, self.version
+            ),
+            None => write!(f, "{}@{} is only for Unity 2019", self.name, self.version),
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum MigrateUnityError {
+    /// There are no migration targets for the Unity version.
+    UnsupportedTarget(UnityVersion),
+    UnityVersionMismatch {
+        current: UnityVersion,
+        target: UnityVersion,
+    },
+    VpmPackageNotFound(Box<str>),
+    /// Some packages are not compatible with the target Unity version, and are not upgraded.
+    BlockingPackages(Vec<BlockingPackage>),
+    AddPackageErr(AddPackageErr),
+    RemovePackageErr(RemovePackageErr),
+    /// The zip file of a package doesn't match the expected hash.
+    HashMismatch(PackageHashMismatchError),
+    Io(io::Error),
+}
+
+impl std::error::Error for MigrateUnityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MigrateUnityError::AddPackageErr(err) => Some(err),
+            MigrateUnityError::RemovePackageErr(err) => Some(err),
+            MigrateUnityError::HashMismatch(err) => Some(err),
+            MigrateUnityError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MigrateUnityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrateUnityError::UnsupportedTarget(target) => {
+                write!(f, "migrating to Unity {target} is not supported")
+            }
+            MigrateUnityError::UnityVersionMismatch { current, target } => {
+                write!(f, "Unity {current} cannot be migrated to Unity {target}")
+            }
+            MigrateUnityError::VpmPackageNotFound(name) => {
+                write!(f, "VPM package {name} not found")
+            }
+            MigrateUnityError::BlockingPackages(packages) => {
+                writeln!(
+                    f,
+                    "following packages are not compatible with the target Unity:"
+                )?;
+                for package in packages {
+                    writeln!(f, "- {package}")?;
+                }
+                Ok(())
+            }
+            MigrateUnityError::AddPackageErr(err) => write!(f, "{err}"),
+            MigrateUnityError::RemovePackageErr(err) => write!(f, "{err}"),
+            MigrateUnityError::HashMismatch(err) => write!(f, "{err}"),
+            MigrateUnityError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<AddPackageErr> for MigrateUnityError {
+    fn from(err: AddPackageErr) -> Self {
+        MigrateUnityError::AddPackageErr(err)
+    }
+}
+
+impl From<RemovePackageErr> for MigrateUnityError {
+    fn from(err: RemovePackageErr) -> Self {
+        MigrateUnityError::RemovePackageErr(err)
+    }
+}
+
+impl From<ApplyPendingChangesErr> for MigrateUnityError {
+    fn from(err: ApplyPendingChangesErr) -> Self {
+        match err {
+            ApplyPendingChangesErr::HashMismatch(err) => MigrateUnityError::HashMismatch(err),
+            ApplyPendingChangesErr::Io(err) => MigrateUnityError::Io(err),
+        }
+    }
+}
+
+impl From<io::Error> for MigrateUnityError {
+    fn from(err: io::Error) -> Self {
+        MigrateUnityError::Io(err)
+    }
+}
+
+type Result<T = (), E = MigrateUnityError> = std::result::Result<T, E>;
+
+/// Why a package needs to be changed to migrate to the target Unity version.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum MigrationIssueReason {
+    /// The package is older than the minimum version required for the target.
+    BelowMinimum(Version),
+    /// The package declares a newer minimum Unity version than the target.
+    RequiresNewerUnity((u16, u8)),
+    /// The VRCSDK or the resolver version is only for Unity 2019.
+    OnlyForUnity2019,
+    /// The package is replaced with the other package.
+    ReplacedWith(Box<str>),
+}
+
+impl fmt::Display for MigrationIssueReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationIssueReason::BelowMinimum(minimum) => {
+                write!(f, "{minimum} or later is required")
+            }
+            MigrationIssueReason::RequiresNewerUnity((major, minor)) => {
+                write!(f, "requires Unity {major}.{minor} or later")
+            }
+            MigrationIssueReason::OnlyForUnity2019 => f.write_str("only for Unity 2019"),
+            MigrationIssueReason::ReplacedWith(name) => write!(f, "replaced with {name}"),
+        }
+    }
+}
+
+/// A package in the project to be changed to migrate to the target Unity version.
+#[derive(Debug, Clone)]
+pub struct MigrationIssue {
+    name: Box<str>,
+    version: Version,
+    locked: bool,
+    reason: MigrationIssueReason,
+    proposed: Option<Version>,
+    upgraded_by_migration: bool,
+}
+
+impl MigrationIssue {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The version currently in the project.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Whether the package is locked in `vpm-manifest.json`, or an unlocked package in `Packages`.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn reason(&self) -> &MigrationIssueReason {
+        &self.reason
+    }
+
+    /// The latest version compatible with the target Unity version.
+    ///
+    /// This is the version of the new package for [`MigrationIssueReason::ReplacedWith`].
+    /// `None` if no version in the collection is usable.
+    pub fn proposed_version(&self) -> Option<&Version> {
+        self.proposed.as_ref()
+    }
+
+    /// Whether the migration upgrades or replaces the package to the proposed version.
+    pub fn upgraded_by_migration(&self) -> bool {
+        self.upgraded_by_migration
+    }
+
+    /// Whether the migration fails because of this package.
+    ///
+    /// Packages not upgraded by the migration have to be upgraded or removed before migration.
+    pub fn blocks_migration(&self) -> bool {
+        !self.upgraded_by_migration || self.proposed.is_none()
+    }
+}
+
+impl fmt::Display for MigrationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}: {}", self.name, self.version, self.reason)?;
+        match (&self.reason, &self.proposed) {
+            (MigrationIssueReason::ReplacedWith(name), Some(proposed)) => {
+                write!(f, " (proposed: {name}@{proposed})")
+            }
+            (_, Some(proposed)) => write!(f, " (proposed: {proposed})"),
+            (_, None) => f.write_str(" (no compatible version found)"),
+        }
+    }
+}
+
+impl From<&MigrationIssue> for BlockingPackage {
+    fn from(issue: &MigrationIssue) -> Self {
+        BlockingPackage {
+            name: issue.name.clone(),
+            version: issue.version.clone(),
+            minimum_unity: match issue.reason {
+                MigrationIssueReason::RequiresNewerUnity(minimum) => Some(minimum),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl UnityProject {
+    /// Migrates the project to the recommended Unity 2022.
+    ///
+    /// Unlike [`migrate_unity`](Self::migrate_unity), VRCSDK and the resolver are upgraded to
+    /// the latest version even if they already satisfy the minimum versions,
+    /// and the project is not saved if no packages are changed.
+    pub async fn migrate_unity_2022(
+        &mut self,
+        collection: &impl PackageCollection,
+        installer: &impl PackageInstaller,
+    ) -> Result {
+        migrate_unity(
+            self,
+            VRCHAT_RECOMMENDED_2022_UNITY,
+            collection,
+            installer,
+            true,
+        )
+        .await
+    }
+
+    /// Migrates the project to the target Unity version.
+    ///
+    /// The packages are upgraded or replaced based on the [`UnityMigrationTarget`] for the target,
+    /// and the migration fails before changing anything if other packages in the project
+    /// are not compatible with the target.
+    /// This doesn't launch Unity, so you have to open the project with the target Unity
+    /// to finish migration.
+    pub async fn migrate_unity(
+        &mut self,
+        target: UnityVersion,
+        collection: &impl PackageCollection,
+        installer: &impl PackageInstaller,
+    ) -> Result {
+        migrate_unity(self, target, collection, installer, false).await?;
+        self.save().await?;
+        Ok(())
+    }
+
+    /// Lists the packages to be changed to migrate the project to the target Unity version
+    /// without changing the project.
+    pub fn check_unity_migration(
+        &self,
+        target: UnityVersion,
+        collection: &impl PackageCollection,
+    ) -> Result<Vec<MigrationIssue>> {
+        let migration = find_migration(self, target)?;
+        Ok(migration_issues(self, migration, target, collection))
+    }
+}
+
+fn find_migration(
+    project: &UnityProject,
+    target: UnityVersion,
+) -> Result<&'static UnityMigrationTarget> {
+    let Some(migration) = UnityMigrationTarget::find(target) else {
+        return Err(MigrateUnityError::UnsupportedTarget(target));
+    };
+    if !migration.can_migrate_from(project.unity_version(), target) {
+        return Err(MigrateUnityError::UnityVersionMismatch {
+            current: project.unity_version(),
+            target,
+        });
+    }
+    Ok(migration)
+}
+
+async fn migrate_unity(
+    project: &mut UnityProject,
+    target: UnityVersion,
+    collection: &impl PackageCollection,
+    installer: &impl PackageInstaller,
+    upgrade_to_latest: bool,
+) -> Result {
+    let migration = find_migration(project, target)?;
+
+    // since this command is made for projects with VPM VRCSDK, wan if not
+    if !is_vpm_vrcsdk_installed(project) {
+        warn!("It looks migrating projects without vpm VRCSDK. this may not intended");
+    }
+
+    let issues = migration_issues(project, migration, target, collection);
+
+    let mut packages = vec![];
+    let mut removing = vec![];
+    for issue in issues.iter().filter(|x| x.upgraded_by_migration) {
+        let name = match &issue.reason {
+            MigrationIssueReason::ReplacedWith(new) => {
+                removing.push(issue.name());
+                new.as_ref()
+            }
+            _ => issue.name(),
+        };
+        let package = (issue.proposed.as_ref())
+            .and_then(|v| {
+                collection.find_package_by_name(name, VersionSelector::specific_version(v))
+            })
+            .ok_or_else(|| MigrateUnityError::VpmPackageNotFound(name.into()))?;
+        packages.push(package);
+    }
+
+    if upgrade_to_latest {
+        // the packages satisfying the minimum versions are also upgraded to the latest
+        let selector = VersionSelector::latest_for(Some(target), false);
+        for (name, _) in migration.minimum_packages() {
+            if project.get_locked(name).is_none() || issues.iter().any(|x| x.name() == name) {
+                continue;
+            }
+            let package = collection
+                .find_package_by_name(name, selector)
+                .ok_or_else(|| MigrateUnityError::VpmPackageNotFound(name.into()))?;
+            packages.push(package);
+        }
+    }
+
+    let blocking = (issues.iter())
+        .filter(|x| !x.upgraded_by_migration)
+        .map(BlockingPackage::from)
+        .collect::<Vec<_>>();
+    if !blocking.is_empty() {
+        return Err(MigrateUnityError::BlockingPackages(blocking));
+    }
+
+    for name in migration.remove_upm_packages() {
+        project.upm_manifest.remove_dependency(name);
+    }
+
+    if !packages.is_empty() {
+        // install packages
+        let request = project
+            .add_package_request(
+                collection,
+                &packages,
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await?;
+        project.apply_pending_changes(installer, request).await?;
+    }
+
+    // the replaced packages may be removed as legacy packages of the new packages
+    removing.retain(|name| project.get_locked(name).is_some());
+    if !removing.is_empty() {
+        let request = project.remove_request(&removing).await?;
+        project.apply_pending_changes(installer, request).await?;
+    }
+
+    Ok(())
+}
+
+/// The manifest of the locked package, from the installed package or the collection.
+fn locked_manifest<'a>(
+    project: &'a UnityProject,
+    collection: &'a impl PackageCollection,
+    name: &str,
+    version: &Version,
+) -> Option<&'a PackageManifest> {
+    project
+        .get_installed_package(name)
+        .filter(|x| x.version() == version)
+        .or_else(|| {
+            collection
+                .find_package_by_name(name, VersionSelector::specific_version(version))
+                .map(|x| x.package_json())
+        })
+}
+
+fn incompatible_reason(
+    manifest: &PackageManifest,
+    target: UnityVersion,
+) -> Option<MigrationIssueReason> {
+    if unity_compatible(manifest, target) {
+        return None;
+    }
+    match manifest.unity().map(|x| (x.major(), x.minor())) {
+        Some(minimum) if (target.major(), target.minor()) < minimum => {
+            Some(MigrationIssueReason::RequiresNewerUnity(minimum))
+        }
+        // the minimum unity is satisfied so it's the VRCSDK or resolver for 2019
+        _ => Some(MigrationIssueReason::OnlyForUnity2019),
+    }
+}
+
+fn migration_issues(
+    project: &UnityProject,
+    migration: &UnityMigrationTarget,
+    target: UnityVersion,
+    collection: &impl PackageCollection,
+) -> Vec<MigrationIssue> {
+    let selector = VersionSelector::latest_for(Some(target), false);
+    let latest = |name: &str| {
+        collection
+            .find_package_by_name(name, selector)
+            .map(|x| x.version().clone())
+    };
+
+    let mut issues = vec![];
+    let mut migrating = HashSet::new();
+
+    // packages older than the minimum versions are upgraded to the latest
+    for (name, minimum) in migration.minimum_packages() {
+        let Some(locked) = project.get_locked(name) else {
+            continue;
+        };
+        let incompatible = locked_manifest(project, collection, name, locked.version())
+            .and_then(|manifest| incompatible_reason(manifest, target));
+        let reason = if locked.version() < minimum {
+            MigrationIssueReason::BelowMinimum(minimum.clone())
+        } else if let Some(reason) = incompatible {
+            reason
+        } else {
+            continue;
+        };
+
+        issues.push(MigrationIssue {
+            name: name.into(),
+            version: locked.version().clone(),
+            locked: true,
+            reason,
+            proposed: latest(name).filter(|x| x >= minimum),
+            upgraded_by_migration: true,
+        });
+        migrating.insert(name);
+    }
+
+    // packages replaced with other packages
+    for &(old, new) in migration.swap_packages() {
+        if let Some(locked) = project.get_locked(old) {
+            issues.push(MigrationIssue {
+                name: old.into(),
+                version: locked.version().clone(),
+                locked: true,
+                reason: MigrationIssueReason::ReplacedWith(new.into()),
+                proposed: latest(new),
+                upgraded_by_migration: true,
+            });
+            migrating.insert(old);
+            migrating.insert(new);
+        }
+    }
+
+    // other packages have to be upgraded by the user
+    let locked = (project.locked_packages())
+        .filter(|x| !migrating.contains(x.name()))
+        .filter_map(|x| locked_manifest(project, collection, x.name(), x.version()))
+        .map(|x| (x, true));
+    let unlocked = (project.unlocked_packages().iter())
+        .filter_map(|(_, x)| x.as_ref())
+        .map(|x| (x, false));
+
+    for (manifest, locked) in locked.chain(unlocked) {
+        let Some(reason) = incompatible_reason(manifest, target) else {
+            continue;
+        };
+        issues.push(MigrationIssue {
+            name: manifest.name().into(),
+            version: manifest.version().clone(),
+            locked,
+            reason,
+            proposed: latest(manifest.name()).filter(|x| x > manifest.version()),
+            upgraded_by_migration: false,
+        });
+    }
+
+    issues
+}
+
+// memo /Applications/Unity/Hub/Editor/2022.3.6f1/Unity.app/Contents/MacOS/Unity -quit -batchmode -projectPath .
+fn is_vpm_vrcsdk_installed(project: &UnityProject) -> bool {
+    if project.get_locked("com.vrchat.base").is_some()
+        || project.get_locked("com.vrchat.avatars").is_some()
+        || project.get_locked("com.vrchat.worlds").is_some()
+    {
+        // VRCSDK is installed
+        return true;
+    }
+    if project.get_locked("com.vrchat.core.vpm-resolver").is_some() {
+        // VPM Resolver is installed so It looks it's a vpm project.
+        return true;
+    }
+    // otherwice warn
+    false
+}
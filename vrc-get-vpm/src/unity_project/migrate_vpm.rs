@@ -1,12 +1,18 @@
 use futures::prelude::*;
 use log::{debug, info};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
 
+use crate::environment::PackageHashMismatchError;
 use crate::io::IoTrait;
-use crate::unity_project::{AddPackageErr, AddPackageOperation};
-use crate::{PackageCollection, UnityProject, VersionSelector};
+use crate::unity_project::find_legacy_assets::collect_legacy_assets;
+use crate::unity_project::{AddPackageErr, AddPackageOperation, ApplyPendingChangesErr};
+use crate::{PackageCollection, PackageInfo, UnityProject, VersionSelector};
 use crate::{PackageInstaller, ProjectType, io};
 
+/// The VRCSDK packages, installed based on the project type instead of legacy assets.
+const VRCSDK_PACKAGES: &[&str] = &["com.vrchat.base", "com.vrchat.avatars", "com.vrchat.worlds"];
+
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum MigrateVpmError {
@@ -14,6 +20,8 @@ pub enum MigrateVpmError {
     UnityVersionMismatch,
     VpmPackageNotFound(&'static str),
     AddPackageErr(AddPackageErr),
+    /// The zip file of a package doesn't match the expected hash.
+    HashMismatch(PackageHashMismatchError),
     Io(io::Error),
 }
 
@@ -21,6 +29,7 @@ impl std::error::Error for MigrateVpmError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             MigrateVpmError::AddPackageErr(err) => Some(err),
+            MigrateVpmError::HashMismatch(err) => Some(err),
             MigrateVpmError::Io(err) => Some(err),
             _ => None,
         }
@@ -38,6 +47,7 @@ impl std::fmt::Display for MigrateVpmError {
                 write!(f, "VPM package {name} not found")
             }
             MigrateVpmError::AddPackageErr(err) => write!(f, "{err}"),
+            MigrateVpmError::HashMismatch(err) => write!(f, "{err}"),
             MigrateVpmError::Io(err) => write!(f, "{err}"),
         }
     }
@@ -49,6 +59,15 @@ impl From<AddPackageErr> for MigrateVpmError {
     }
 }
 
+impl From<ApplyPendingChangesErr> for MigrateVpmError {
+    fn from(err: ApplyPendingChangesErr) -> Self {
+        match err {
+            ApplyPendingChangesErr::HashMismatch(err) => MigrateVpmError::HashMismatch(err),
+            ApplyPendingChangesErr::Io(err) => MigrateVpmError::Io(err),
+        }
+    }
+}
+
 impl From<io::Error> for MigrateVpmError {
     fn from(err: io::Error) -> Self {
         MigrateVpmError::Io(err)
@@ -57,22 +76,131 @@ impl From<io::Error> for MigrateVpmError {
 
 type Result<T = (), E = MigrateVpmError> = std::result::Result<T, E>;
 
+/// A package whose copy imported with unitypackage is found in the project.
+#[derive(Debug, Clone)]
+pub struct LegacyImportedPackage<'env> {
+    package: PackageInfo<'env>,
+    folders: Vec<Box<Path>>,
+    files: Vec<Box<Path>>,
+}
+
+impl<'env> LegacyImportedPackage<'env> {
+    /// The latest version of the package replacing the imported copy.
+    pub fn package(&self) -> PackageInfo<'env> {
+        self.package
+    }
+
+    /// The folders in the project declared in `legacyFolders` of the package.
+    pub fn folders(&self) -> &[Box<Path>] {
+        &self.folders
+    }
+
+    /// The files in the project declared in `legacyFiles` of the package.
+    pub fn files(&self) -> &[Box<Path>] {
+        &self.files
+    }
+}
+
 impl UnityProject {
+    /// Migrates the project to the VPM project.
+    ///
+    /// Legacy copies of VRChat-curated packages are replaced with the packages.
     pub async fn migrate_vpm(
         &mut self,
         collection: &impl PackageCollection,
         installer: &impl PackageInstaller,
         include_prerelease: bool,
     ) -> Result {
-        migrate_vpm(self, collection, installer, include_prerelease).await
+        migrate_vpm(self, collection, installer, include_prerelease, None).await
+    }
+
+    /// Migrates the project to the VPM project, replacing legacy copies with the specified packages.
+    ///
+    /// Unlike [`migrate_vpm`](Self::migrate_vpm), the curated packages are not detected automatically.
+    /// The packages are usually selected from [`find_legacy_imported_packages`](Self::find_legacy_imported_packages).
+    pub async fn migrate_vpm_with_packages<'env>(
+        &mut self,
+        collection: &'env impl PackageCollection,
+        installer: &impl PackageInstaller,
+        include_prerelease: bool,
+        packages: &[PackageInfo<'env>],
+    ) -> Result {
+        migrate_vpm(
+            self,
+            collection,
+            installer,
+            include_prerelease,
+            Some(packages),
+        )
+        .await
+    }
+
+    /// Finds the packages in the collection whose `legacyFolders` or `legacyFiles` exist in the project.
+    ///
+    /// The latest version of every package in the collection is checked, and packages already
+    /// installed to the project and the VRCSDK packages are skipped.
+    pub async fn find_legacy_imported_packages<'env>(
+        &self,
+        collection: &'env impl PackageCollection,
+        include_prerelease: bool,
+    ) -> Vec<LegacyImportedPackage<'env>> {
+        let version_selector =
+            VersionSelector::latest_for(Some(self.unity_version()), include_prerelease);
+
+        let names = (collection.get_all_packages())
+            .map(|x| x.name())
+            .filter(|name| !VRCSDK_PACKAGES.contains(name))
+            .filter(|name| self.get_locked(name).is_none())
+            .filter(|name| self.get_installed_package(name).is_none())
+            .collect::<HashSet<_>>();
+
+        let candidates = (names.into_iter())
+            .filter_map(|name| collection.find_package_by_name(name, version_selector))
+            .filter(|x| {
+                !x.package_json().legacy_folders().is_empty()
+                    || !x.package_json().legacy_files().is_empty()
+            })
+            .collect::<Vec<_>>();
+
+        let legacy_assets = collect_legacy_assets(&self.io, &candidates, self).await;
+
+        let folders = (legacy_assets.folders.into_iter()).map(|(path, name)| (path, name, false));
+        let files = (legacy_assets.files.into_iter()).map(|(path, name)| (path, name, true));
+
+        let mut found = BTreeMap::<&str, LegacyImportedPackage>::new();
+        for (path, name, is_file) in folders.chain(files) {
+            let Some(&package) = candidates.iter().find(|x| x.name() == name) else {
+                continue;
+            };
+            let imported = found
+                .entry(package.name())
+                .or_insert_with(|| LegacyImportedPackage {
+                    package,
+                    folders: vec![],
+                    files: vec![],
+                });
+            if is_file {
+                imported.files.push(path);
+            } else {
+                imported.folders.push(path);
+            }
+        }
+
+        let mut found = found.into_values().collect::<Vec<_>>();
+        for package in &mut found {
+            package.folders.sort();
+            package.files.sort();
+        }
+        found
     }
 }
 
-async fn migrate_vpm(
+async fn migrate_vpm<'env>(
     project: &mut UnityProject,
-    collection: &impl PackageCollection,
+    collection: &'env impl PackageCollection,
     installer: &impl PackageInstaller,
     include_prerelease: bool,
+    legacy_packages: Option<&[PackageInfo<'env>]>,
 ) -> Result {
     let is_worlds = match project.detect_project_type().await {
         // we only can migrate legacy VRCSDK3 projects
@@ -106,9 +234,15 @@ async fn migrate_vpm(
         );
     }
 
-    // additional part: migrate VRChat-curated packages
-    // we find legacy curated package by trying to install it and check if the project has legacy assets
-    {
+    // additional part: migrate packages imported with unitypackage
+    if let Some(legacy_packages) = legacy_packages {
+        for x in legacy_packages {
+            info!("Migrating legacy package: {}", x.name());
+        }
+        adding_packages.extend(legacy_packages.iter().copied());
+    } else {
+        // migrate VRChat-curated packages
+        // we find legacy curated package by trying to install it and check if the project has legacy assets
         let mut curated_packages = collection
             .get_curated_packages(version_selector)
             .collect::<Vec<_>>();
@@ -1,5 +1,8 @@
 use crate::traits::PackageCollection;
 use crate::unity_project::LockedDependencyInfo;
+use crate::unity_project::backtracking_resolution::{
+    BacktrackingResolver, ResolutionConflict, ResolverMode,
+};
 use crate::version::{DependencyRange, PrereleaseAcceptance, UnityVersion, Version, VersionRange};
 use crate::{PackageInfo, PackageManifest, VersionSelector};
 use std::collections::hash_map::Entry;
@@ -388,6 +391,7 @@ impl<'env> ResolutionContext<'env, '_> {
             new_packages,
             conflicts,
             found_legacy_packages,
+            resolution_conflict: None,
         }
     }
 }
@@ -398,6 +402,8 @@ pub struct PackageResolutionResult<'env> {
     pub conflicts: HashMap<Box<str>, Vec<Box<str>>>,
     // list of names of legacy packages we found
     pub found_legacy_packages: Vec<Box<str>>,
+    // why the conflicts cannot be avoided. only available with backtracking resolver
+    pub resolution_conflict: Option<ResolutionConflict>,
 }
 
 pub struct MissingDependencies {
@@ -441,9 +447,13 @@ pub(crate) fn collect_adding_packages<'a, 'env>(
     env: &'env impl PackageCollection,
     packages: Vec<PackageInfo<'env>>,
     allow_prerelease: bool,
+    resolver_mode: ResolverMode,
     missing_dependencies: &mut MissingDependencies,
 ) -> PackageResolutionResult<'env> {
-    let mut context = ResolutionContext::<'env, '_>::new(allow_prerelease, packages);
+    let locked_dependencies = locked_dependencies.collect::<Vec<_>>();
+    let unlocked_packages = unlocked_packages.collect::<Vec<_>>();
+
+    let mut context = ResolutionContext::<'env, '_>::new(allow_prerelease, packages.clone());
 
     // first, add dependencies
     let root_dependencies = dependencies
@@ -474,12 +484,12 @@ pub(crate) fn collect_adding_packages<'a, 'env>(
     }
 
     // then, add locked dependencies info
-    for locked in locked_dependencies {
+    for locked in locked_dependencies.iter().cloned() {
         context.add_locked_dependency(locked, env);
     }
 
     // add unlocked packages
-    for (unlocked_name, unlocked_manifest) in unlocked_packages {
+    for (unlocked_name, unlocked_manifest) in unlocked_packages.iter().copied() {
         context.add_unlocked_name(unlocked_name.as_ref());
         if let Some(unlocked_package) = unlocked_manifest {
             context.add_unlocked_name(unlocked_package.name());
@@ -487,6 +497,9 @@ pub(crate) fn collect_adding_packages<'a, 'env>(
         }
     }
 
+    // missing dependencies may be avoided by backtracking
+    let mut greedy_missing = MissingDependencies::new();
+
     while let Some((x, force)) = context.pending_queue.next_package() {
         log::debug!("processing package {} version {}", x.name(), x.version());
         let name = x.name();
@@ -563,12 +576,50 @@ pub(crate) fn collect_adding_packages<'a, 'env>(
                     if let Some(found) = found {
                         context.pending_queue.add_pending_package(found);
                     } else {
-                        missing_dependencies.add(dependency, range);
+                        greedy_missing.add(dependency, range);
                     }
                 }
             }
         }
     }
 
-    context.build_result()
+    let mut result = context.build_result();
+
+    if resolver_mode == ResolverMode::Backtracking
+        && (!result.conflicts.is_empty() || !greedy_missing.is_empty())
+    {
+        log::debug!("greedy resolution failed, trying backtracking resolution");
+
+        let mut resolver = BacktrackingResolver::new(env, unity_version, allow_prerelease);
+        for (name, range, _) in &root_dependencies {
+            resolver.add_root_dependency(name, range);
+        }
+        for locked in locked_dependencies {
+            resolver.add_locked_dependency(locked);
+        }
+        for (unlocked_name, unlocked_manifest) in unlocked_packages {
+            resolver.add_unlocked_name(unlocked_name.as_ref());
+            if let Some(unlocked_package) = unlocked_manifest {
+                resolver.add_unlocked_name(unlocked_package.name());
+                resolver.add_unlocked_manifest(unlocked_package);
+            }
+        }
+        for package in packages {
+            resolver.add_requested_package(package);
+        }
+
+        match resolver.resolve() {
+            Ok(resolved) => return resolved,
+            Err(conflict) => {
+                log::debug!("backtracking resolution failed:\n{conflict}");
+                result.resolution_conflict = Some(conflict);
+            }
+        }
+    }
+
+    for (dependency, range) in greedy_missing.dependencies {
+        missing_dependencies.add(&dependency, &range);
+    }
+
+    result
 }
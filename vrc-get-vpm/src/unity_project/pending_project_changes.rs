@@ -1,6 +1,11 @@
+use crate::environment::PackageHashMismatchError;
 use crate::io::{DefaultProjectIo, DirEntry, IoTrait};
-use crate::traits::AbortCheck;
+use crate::traits::{AbortCheck, InstallProgress};
+use crate::unity_project::LockedRepository;
+use crate::unity_project::ResolutionConflict;
 use crate::unity_project::find_legacy_assets::collect_legacy_assets;
+use crate::unity_project::history::record_history;
+use crate::unity_project::vpm_manifest::VpmManifest;
 use crate::utils::{PathBufExt, walk_dir_relative};
 use crate::version::DependencyRange;
 use crate::{PackageInfo, UnityProject, unity_compatible};
@@ -12,11 +17,11 @@ use indexmap::IndexSet;
 use log::debug;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::future::ready;
+use std::fmt;
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::pin::pin;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 
 /// Represents Packages to be added and folders / packages to be removed
 ///
@@ -33,6 +38,12 @@ pub struct PendingProjectChanges<'env> {
     pub(crate) remove_legacy_folders: Vec<(Box<Path>, &'env str)>,
 
     pub(crate) conflicts: HashMap<Box<str>, ConflictInfo>,
+
+    pub(crate) resolution_conflict: Option<ResolutionConflict>,
+
+    /// The manifest replacing the whole manifest instead of applying the package changes.
+    /// This is used to restore the project to the state in the history.
+    pub(super) manifest_override: Option<VpmManifest>,
 }
 
 #[derive(Debug)]
@@ -122,6 +133,7 @@ impl ConflictInfo {
 pub(crate) struct Builder<'env> {
     package_changes: HashMap<Box<str>, PackageChange<'env>>,
     conflicts: HashMap<Box<str>, ConflictInfo>,
+    resolution_conflict: Option<ResolutionConflict>,
 }
 
 impl<'env> Builder<'env> {
@@ -129,6 +141,7 @@ impl<'env> Builder<'env> {
         Self {
             package_changes: HashMap::new(),
             conflicts: HashMap::new(),
+            resolution_conflict: None,
         }
     }
 
@@ -245,6 +258,11 @@ impl<'env> Builder<'env> {
         self
     }
 
+    pub fn resolution_conflict(&mut self, conflict: ResolutionConflict) -> &mut Self {
+        self.resolution_conflict = Some(conflict);
+        self
+    }
+
     pub fn conflicts_unity(&mut self, name: Box<str>) -> &mut Self {
         self.conflicts.entry(name).or_default().conflicts_with_unity = true;
         self
@@ -315,6 +333,14 @@ impl<'env> Builder<'env> {
             .filter_map(|x| x.package)
     }
 
+    /// Returns all packages to be installed, including the packages already locked.
+    pub(crate) fn get_all_install_packages(&self) -> impl Iterator<Item = PackageInfo<'env>> + '_ {
+        self.package_changes
+            .values()
+            .filter_map(|x| x.as_install())
+            .filter_map(|x| x.package)
+    }
+
     pub fn build_no_resolve(self) -> PendingProjectChanges<'env> {
         for change in self.package_changes.values() {
             match change {
@@ -332,9 +358,27 @@ impl<'env> Builder<'env> {
         PendingProjectChanges {
             package_changes: self.package_changes,
             conflicts: self.conflicts,
+            resolution_conflict: self.resolution_conflict,
 
             remove_legacy_files: vec![],
             remove_legacy_folders: vec![],
+            manifest_override: None,
+        }
+    }
+
+    /// Builds the changes which replace the manifest with the specified one.
+    ///
+    /// Unlike other changes, this doesn't remove unused packages or legacy assets
+    /// since the manifest is restored as is.
+    pub(super) fn build_restore(self, manifest: VpmManifest) -> PendingProjectChanges<'env> {
+        PendingProjectChanges {
+            package_changes: self.package_changes,
+            conflicts: self.conflicts,
+            resolution_conflict: self.resolution_conflict,
+
+            remove_legacy_files: vec![],
+            remove_legacy_folders: vec![],
+            manifest_override: Some(manifest),
         }
     }
 
@@ -374,9 +418,11 @@ impl<'env> Builder<'env> {
         PendingProjectChanges {
             package_changes: self.package_changes,
             conflicts: self.conflicts,
+            resolution_conflict: self.resolution_conflict,
 
             remove_legacy_files: legacy_assets.files,
             remove_legacy_folders: legacy_assets.folders,
+            manifest_override: None,
         }
     }
 
@@ -520,6 +566,52 @@ impl PendingProjectChanges<'_> {
     pub fn conflicts(&self) -> &HashMap<Box<str>, ConflictInfo> {
         &self.conflicts
     }
+
+    /// The explanation why the conflicts cannot be avoided.
+    ///
+    /// This is only available with [`ResolverMode::Backtracking`](crate::unity_project::ResolverMode::Backtracking).
+    pub fn resolution_conflict(&self) -> Option<&ResolutionConflict> {
+        self.resolution_conflict.as_ref()
+    }
+}
+
+/// The error while applying [`PendingProjectChanges`] to the project.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ApplyPendingChangesErr {
+    /// The zip file of a package doesn't match the expected hash.
+    HashMismatch(PackageHashMismatchError),
+    Io(io::Error),
+}
+
+impl std::error::Error for ApplyPendingChangesErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApplyPendingChangesErr::HashMismatch(err) => Some(err),
+            ApplyPendingChangesErr::Io(err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for ApplyPendingChangesErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyPendingChangesErr::HashMismatch(err) => fmt::Display::fmt(err, f),
+            ApplyPendingChangesErr::Io(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl From<io::Error> for ApplyPendingChangesErr {
+    fn from(err: io::Error) -> Self {
+        // installers report the mismatch as io::Error since the trait returns io::Result
+        if PackageHashMismatchError::from_io_error(&err).is_some() {
+            let inner = err.into_inner().expect("checked above");
+            let mismatch = inner.downcast().expect("checked above");
+            return ApplyPendingChangesErr::HashMismatch(*mismatch);
+        }
+        ApplyPendingChangesErr::Io(err)
+    }
 }
 
 static TEMP_DIR: &str = "Temp";
@@ -533,29 +625,40 @@ impl UnityProject {
         &mut self,
         env: &'env Env,
         request: PendingProjectChanges<'env>,
-    ) -> io::Result<()> {
+    ) -> Result<(), ApplyPendingChangesErr> {
+        self.apply_pending_changes_with_progress(env, request, &())
+            .await
+    }
+
+    /// Applies the changes specified in `AddPackageRequest` to the project,
+    /// reporting the progress of installing packages to `progress`.
+    ///
+    /// Packages are installed concurrently up to [`PackageInstaller::max_concurrency`].
+    pub async fn apply_pending_changes_with_progress<'env, Env: PackageInstaller>(
+        &mut self,
+        env: &'env Env,
+        request: PendingProjectChanges<'env>,
+        progress: &dyn InstallProgress,
+    ) -> Result<(), ApplyPendingChangesErr> {
         /*
         Apply pending changes consists of following steps:
-        - Move packages to temp directory (remove packages)
-        - Apply changes to manifest (add packages)
-        - Install packages
+        - Install packages to the staging directory
+        - Move packages to the trash directory (remove packages)
+        - Move staged packages to Packages
+        - Apply changes to manifest and save
+        - Remove the trash directory
         - Remove legacy assets
 
-        This function will do those steps in the order above.
-        There are several things to consider:
-        - We remove package before applying changes to manifest because:
-          - If we update manifest before removing packages,
-            failing to remove packages will leave previously installed packages as unlocked packages.
-          - If we remove packages before updating manifest,
-            failing to install packages will leave packages as uninstalled locked packages,
-            which is easy to fix with Resolve command.
-        - We install packages after applying changes to manifest because:
-          - If we install packages before updating manifest,
-            failing to update manifest will leave packages as unlocked packages.
-          - If we update manifest before installing packages,
-            failing to install packages will leave packages as uninstalled locked packages,
-            which is easy to fix with Resolve command.
-        - We remove legacy assets after installing packages because:
+        Both the staging directory and the trash directory are in the transaction directory
+        under Temp/vrc-get, which Unity doesn't import.
+
+        Until the manifest is saved, every step can be reverted, so if any step fails,
+        we remove the staged packages, restore the packages in the trash directory,
+        and restore the manifest so the project is kept as before the operation.
+        We install packages before touching Packages since installing includes downloading,
+        which is the most likely step to fail.
+
+        Legacy assets are removed after the changes are committed because:
           - If we remove legacy assets before installing packages,
             failing to install package will leave legacy assets removed.
           - If we install packages before removing legacy assets,
@@ -586,57 +689,127 @@ impl UnityProject {
             }
         }
 
-        // remove packages
-        let remove_temp_dir = format!("{PKG_TEMP_DIR}/{}", uuid::Uuid::new_v4());
-        let remove_temp_dir = Path::new(&remove_temp_dir);
+        // the hash recorded in the locked section is used to verify the package
+        let target_manifest = request.manifest_override.as_ref().unwrap_or(&self.manifest);
+        let installs = installs
+            .into_iter()
+            .map(|package| {
+                let locked_sha256 = (target_manifest.get_locked(package.name()))
+                    .filter(|locked| locked.version() == package.version())
+                    .and_then(|locked| locked.zip_sha_256())
+                    .map(Box::<str>::from);
+                (package, locked_sha256)
+            })
+            .collect::<Vec<_>>();
+
+        let transaction_dir = format!("{PKG_TEMP_DIR}/{}", uuid::Uuid::new_v4());
+        let transaction_dir = Path::new(&transaction_dir);
+        let staging_dir = transaction_dir.join("staging");
+        let trash_dir = transaction_dir.join("trash");
 
-        self.io.create_dir_all(remove_temp_dir).await?;
+        self.io.create_dir_all(&staging_dir).await?;
+        self.io.create_dir_all(&trash_dir).await?;
+
+        // install packages to staging directory
+
+        let installed_hashes =
+            match install_packages(&self.io, env, &installs, &staging_dir, progress).await {
+                Ok(hashes) => hashes,
+                Err(err) => {
+                    remove_transaction_dir(&self.io, transaction_dir).await;
+                    return Err(err.into());
+                }
+            };
 
-        move_packages_to_temp(
+        // remove packages
+
+        let removed = match move_packages_to_temp(
             &self.io,
             (remove_names.iter().copied())
-                .chain(installs.iter().map(|x| x.name()))
+                .chain(installs.iter().map(|(x, _)| x.name()))
                 .chain(remove_unlocked_names.iter().copied()),
-            remove_temp_dir,
+            &trash_dir,
         )
-        .await?;
+        .await
+        {
+            Ok(removed) => removed,
+            Err(err) => {
+                // move_packages_to_temp restores moved packages by itself
+                remove_transaction_dir(&self.io, transaction_dir).await;
+                return Err(err.into());
+            }
+        };
+
+        // move staged packages to Packages
+
+        let mut committed = Vec::new();
+        for (package, _) in &installs {
+            let package_dir = format!("Packages/{}", package.name());
+            let result = self
+                .io
+                .rename(&staging_dir.join(package.name()), package_dir.as_ref())
+                .await;
+            if let Err(err) = result {
+                log::error!(gui_toast = false; "error moving {} to Packages: {err}", package.name());
+                rollback_packages(&self.io, &committed, &trash_dir, &removed).await;
+                remove_transaction_dir(&self.io, transaction_dir).await;
+                return Err(err.into());
+            }
+            committed.push(package.name());
+        }
 
         // apply changes to manifest
-        for (name, change) in &request.package_changes {
-            match change {
-                PackageChange::Install(change) => {
-                    if let Some(package) = change.package
-                        && change.add_to_locked
-                    {
-                        self.manifest.add_locked(
-                            package.name(),
-                            package.version().clone(),
-                            package.vpm_dependencies().clone(),
-                        );
-                    }
 
-                    if let Some(version) = &change.to_dependencies {
-                        self.manifest.add_dependency(name, version.clone());
+        let manifest_backup = self.manifest.clone();
+
+        if let Some(manifest) = &request.manifest_override {
+            self.manifest.replace_with(manifest);
+        } else {
+            for (name, change) in &request.package_changes {
+                match change {
+                    PackageChange::Install(change) => {
+                        if let Some(package) = change.package
+                            && change.add_to_locked
+                        {
+                            // the hash of the installed zip file pins the bytes actually installed
+                            let zip_sha_256 = (installed_hashes.get(package.name()).cloned())
+                                .or_else(|| package.package_json().zip_sha_256().map(Into::into));
+                            self.manifest.add_locked(
+                                package.name(),
+                                package.version().clone(),
+                                package.vpm_dependencies().clone(),
+                                zip_sha_256,
+                                LockedRepository::from_package(package),
+                            );
+                        }
+
+                        if let Some(version) = &change.to_dependencies {
+                            self.manifest.add_dependency(name, version.clone());
+                        }
                     }
+                    PackageChange::Remove(_) => {}
                 }
-                PackageChange::Remove(_) => {}
             }
-        }
 
-        self.manifest.remove_packages(remove_names.iter().copied());
+            self.manifest.remove_packages(remove_names.iter().copied());
+        }
 
         // save manifest
 
-        self.save().await?;
+        if let Err(err) = self.save().await {
+            self.manifest = manifest_backup;
+            rollback_packages(&self.io, &committed, &trash_dir, &removed).await;
+            remove_transaction_dir(&self.io, transaction_dir).await;
+            return Err(err.into());
+        }
 
-        // add packages
+        // the changes are committed. remove the trash and record the history
 
-        install_packages(&self.io, env, &installs).await?;
+        remove_transaction_dir(&self.io, transaction_dir).await;
 
-        self.io.remove_dir_all(remove_temp_dir).await.ok();
-        self.io.remove_dir_all(PKG_TEMP_DIR.as_ref()).await.ok();
-        // remove temp dir also if it's empty
-        self.io.remove_dir(TEMP_DIR.as_ref()).await.ok();
+        if let Err(err) = record_history(&self.io, &manifest_backup, &self.manifest).await {
+            log::warn!("error recording history: {err}");
+        }
 
         // remove legacy assets
 
@@ -654,6 +827,30 @@ impl UnityProject {
     }
 }
 
+/// Removes the packages moved to Packages and restores the packages in the trash directory.
+async fn rollback_packages(
+    io: &DefaultProjectIo,
+    committed: &[&str],
+    trash_dir: &Path,
+    removed: &[&str],
+) {
+    for name in committed {
+        let package_dir = format!("Packages/{name}");
+        if let Err(err) = io.remove_dir_all(package_dir.as_ref()).await {
+            log::error!(gui_toast = false; "error removing {package_dir} while rolling back: {err}");
+        }
+    }
+
+    restore_remove(io, trash_dir, removed.iter().copied()).await;
+}
+
+async fn remove_transaction_dir(io: &DefaultProjectIo, transaction_dir: &Path) {
+    io.remove_dir_all(transaction_dir).await.ok();
+    // remove temp dir also if it's empty
+    io.remove_dir(PKG_TEMP_DIR.as_ref()).await.ok();
+    io.remove_dir(TEMP_DIR.as_ref()).await.ok();
+}
+
 static REMOVED_FILE_PREFIX: &str = ".__removed_";
 
 async fn move_packages_to_temp<'a>(
@@ -793,31 +990,56 @@ async fn restore_remove(io: &DefaultProjectIo, temp_dir: &Path, names: impl Iter
     io.remove_dir(TEMP_DIR.as_ref()).await.ok();
 }
 
-async fn install_packages<Env: PackageInstaller>(
+/// Installs the packages to the staging directory and returns the hashes of the installed zip files.
+async fn install_packages<'a, Env: PackageInstaller>(
     io: &DefaultProjectIo,
     env: &Env,
-    packages: &[PackageInfo<'_>],
-) -> io::Result<()> {
+    packages: &'a [(PackageInfo<'_>, Option<Box<str>>)],
+    staging_dir: &Path,
+    progress: &dyn InstallProgress,
+) -> io::Result<HashMap<&'a str, Box<str>>> {
     let abort = AbortCheck::new();
     let mut error_store = OnceLock::new();
-
-    // resolve all packages
-    join_all(packages.iter().map(|package| {
-        env.install_package(io, *package, &abort).then(|x| {
-            if let Err(e) = x {
-                error_store.set(e).ok();
-                abort.abort();
+    let hashes = Mutex::new(HashMap::new());
+
+    // install packages with at most max_concurrency packages at once
+    let limit = env.max_concurrency().map(|x| x.get());
+    stream::iter(packages)
+        .for_each_concurrent(limit, |(package, locked_sha256)| {
+            let error_store = &error_store;
+            let abort = &abort;
+            let hashes = &hashes;
+            async move {
+                let dest_folder = staging_dir.join(package.name());
+                let result = env
+                    .install_package(
+                        io,
+                        *package,
+                        &dest_folder,
+                        locked_sha256.as_deref(),
+                        progress,
+                        abort,
+                    )
+                    .await;
+                match result {
+                    Ok(Some(hash)) => {
+                        hashes.lock().unwrap().insert(package.name(), hash);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error_store.set(e).ok();
+                        abort.abort();
+                    }
+                }
             }
-            ready(())
         })
-    }))
-    .await;
+        .await;
 
     if let Some(err) = error_store.take() {
         return Err(err);
     }
 
-    Ok(())
+    Ok(hashes.into_inner().unwrap())
 }
 
 async fn remove_assets(
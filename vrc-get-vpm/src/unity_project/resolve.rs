@@ -2,12 +2,14 @@ use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
+use crate::advisory::AdvisoryMatch;
 use crate::unity_project::package_resolution::MissingDependencies;
 use crate::unity_project::{
-    LockedDependencyInfo, PendingProjectChanges, package_resolution, pending_project_changes,
+    LockedDependencyInfo, LockedRepository, PendingProjectChanges, package_resolution,
+    pending_project_changes,
 };
-use crate::version::{DependencyRange, PrereleaseAcceptance, VersionRange};
-use crate::{PackageCollection, UnityProject, VersionSelector};
+use crate::version::{DependencyRange, PrereleaseAcceptance, Version, VersionRange};
+use crate::{PackageCollection, PackageInfo, UnityProject, VersionSelector};
 
 #[derive(Debug)]
 #[non_exhaustive]
@@ -15,6 +17,61 @@ pub enum ResolvePackageErr {
     DependenciesNotFound {
         dependencies: Vec<(Box<str>, VersionRange)>,
     },
+    /// The `locked` section is not consistent with `dependencies` in frozen mode.
+    LockedMismatch { mismatches: Vec<LockedMismatch> },
+    /// Some packages to be installed match the advisories of the project.
+    DeniedByAdvisories { matches: Vec<AdvisoryMatch> },
+}
+
+/// The inconsistency between `dependencies` and `locked` found in frozen mode.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LockedMismatch {
+    /// The package is required but not in the `locked` section.
+    NotLocked {
+        package: Box<str>,
+        /// The name of the package requires this package. `None` for `dependencies`.
+        required_by: Option<Box<str>>,
+        range: VersionRange,
+    },
+    /// The locked version does not satisfy the required range.
+    VersionMismatch {
+        package: Box<str>,
+        /// The name of the package requires this package. `None` for `dependencies`.
+        required_by: Option<Box<str>>,
+        range: VersionRange,
+        locked: Version,
+    },
+}
+
+impl fmt::Display for LockedMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn required_by(required_by: &Option<Box<str>>) -> &str {
+            required_by.as_deref().unwrap_or("dependencies")
+        }
+
+        match self {
+            LockedMismatch::NotLocked {
+                package,
+                required_by: source,
+                range,
+            } => write!(
+                f,
+                "{package}: required by {} ({range}) but not locked",
+                required_by(source)
+            ),
+            LockedMismatch::VersionMismatch {
+                package,
+                required_by: source,
+                range,
+                locked,
+            } => write!(
+                f,
+                "{package}: locked at {locked} but {} requires {range}",
+                required_by(source)
+            ),
+        }
+    }
 }
 
 impl fmt::Display for ResolvePackageErr {
@@ -32,6 +89,20 @@ impl fmt::Display for ResolvePackageErr {
                 }
                 Ok(())
             }
+            ResolvePackageErr::LockedMismatch { mismatches } => {
+                writeln!(f, "locked packages are not consistent with dependencies:")?;
+                for mismatch in mismatches {
+                    writeln!(f, "- {mismatch}")?;
+                }
+                Ok(())
+            }
+            ResolvePackageErr::DeniedByAdvisories { matches } => {
+                writeln!(f, "Following packages are denied by security advisories:")?;
+                for advisory_match in matches {
+                    writeln!(f, "- {advisory_match}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -134,13 +205,154 @@ impl UnityProject {
         // finally, process dependencies of unlocked packages.
         self.resolve_unlocked(env, &mut changes, &mut missing_dependencies)?;
 
-        if missing_dependencies.is_empty() {
-            Ok(changes.build_resolve(self).await)
-        } else {
-            Err(ResolvePackageErr::DependenciesNotFound {
+        if !missing_dependencies.is_empty() {
+            return Err(ResolvePackageErr::DependenciesNotFound {
                 dependencies: missing_dependencies.into_vec(),
-            })
+            });
+        }
+
+        let matches = (self.advisories).check_packages(changes.get_all_install_packages());
+        if !matches.is_empty() {
+            return Err(ResolvePackageErr::DeniedByAdvisories { matches });
         }
+
+        Ok(changes.build_resolve(self).await)
+    }
+
+    /// Creates a request that installs exactly the versions recorded in the `locked` section.
+    ///
+    /// Unlike [`resolve_request`](Self::resolve_request), this never changes any locked version.
+    /// If some packages required by `dependencies`, locked packages, or unlocked packages
+    /// are not locked or the locked version doesn't satisfy the requirement,
+    /// this returns [`ResolvePackageErr::LockedMismatch`] with every mismatch found.
+    pub async fn resolve_request_frozen<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+    ) -> Result<PendingProjectChanges<'env>, ResolvePackageErr> {
+        let mut changes = pending_project_changes::Builder::new();
+        let mut missing_dependencies = MissingDependencies::new();
+
+        // packages satisfied without being locked: legacy packages and unlocked packages
+        let mut satisfied_names = HashSet::<&str>::new();
+
+        for dep in self.manifest.all_locked() {
+            if let Some(pkg) = env
+                .find_package_by_name(dep.name(), VersionSelector::specific_version(dep.version()))
+            {
+                satisfied_names.extend(pkg.legacy_packages().iter().map(Box::as_ref));
+                changes.install_already_locked(pkg);
+            } else {
+                missing_dependencies
+                    .add(dep.name(), &VersionRange::specific(dep.version().clone()));
+            }
+        }
+
+        for (_, pkg) in self.unlocked_packages() {
+            if let Some(pkg) = pkg {
+                satisfied_names.insert(pkg.name());
+                satisfied_names.extend(pkg.legacy_packages().iter().map(Box::as_ref));
+            }
+        }
+
+        let mut mismatches = Vec::new();
+
+        let mut check = |package: &str, required_by: Option<&str>, range: VersionRange| match self
+            .manifest
+            .get_locked(package)
+        {
+            None if satisfied_names.contains(package) => {}
+            None => mismatches.push(LockedMismatch::NotLocked {
+                package: package.into(),
+                required_by: required_by.map(Into::into),
+                range,
+            }),
+            Some(locked) if range.match_pre(locked.version(), PrereleaseAcceptance::Allow) => {}
+            Some(locked) => mismatches.push(LockedMismatch::VersionMismatch {
+                package: package.into(),
+                required_by: required_by.map(Into::into),
+                range,
+                locked: locked.version().clone(),
+            }),
+        };
+
+        for (name, range) in self.manifest.dependencies() {
+            check(name, None, range.as_range());
+        }
+
+        for locked in self.manifest.all_locked() {
+            for (dependency, range) in locked.dependencies() {
+                check(dependency, Some(locked.name()), range.clone());
+            }
+        }
+
+        for (_, pkg) in self.unlocked_packages() {
+            if let Some(pkg) = pkg {
+                for (dependency, range) in pkg.vpm_dependencies() {
+                    check(dependency, Some(pkg.name()), range.clone());
+                }
+            }
+        }
+
+        if !mismatches.is_empty() {
+            return Err(ResolvePackageErr::LockedMismatch { mismatches });
+        }
+
+        if !missing_dependencies.is_empty() {
+            return Err(ResolvePackageErr::DependenciesNotFound {
+                dependencies: missing_dependencies.into_vec(),
+            });
+        }
+
+        let matches = (self.advisories).check_packages(changes.get_all_install_packages());
+        if !matches.is_empty() {
+            return Err(ResolvePackageErr::DeniedByAdvisories { matches });
+        }
+
+        Ok(changes.build_resolve(self).await)
+    }
+
+    /// Returns the packages in the collection with the versions in the `locked` section.
+    ///
+    /// Download the zip files of the packages and pass the hashes to
+    /// [`refresh_locked_hashes`](Self::refresh_locked_hashes) to update the records.
+    pub fn find_locked_packages<'env>(
+        &self,
+        env: &'env impl PackageCollection,
+    ) -> Vec<PackageInfo<'env>> {
+        self.manifest
+            .all_locked()
+            .filter_map(|locked| {
+                env.find_package_by_name(
+                    locked.name(),
+                    VersionSelector::specific_version(locked.version()),
+                )
+            })
+            .collect()
+    }
+
+    /// Updates the zip hash and the repository recorded in the `locked` section
+    /// with the downloaded packages and the sha256 hash of the downloaded zip files.
+    ///
+    /// The hash of the zip file is recorded instead of `zipSHA256` in the repository
+    /// so the lock pins the bytes actually installed.
+    ///
+    /// Returns the names of the packages whose records are updated.
+    /// This only modifies the manifest, so you should call [`save`](Self::save) to save the changes.
+    pub fn refresh_locked_hashes(
+        &mut self,
+        downloaded: &[(PackageInfo<'_>, [u8; 256 / 8])],
+    ) -> Vec<Box<str>> {
+        downloaded
+            .iter()
+            .filter(|(pkg, hash)| {
+                self.manifest.set_locked_source(
+                    pkg.name(),
+                    Some(hex::encode(hash).into()),
+                    LockedRepository::from_package(*pkg),
+                )
+            })
+            .map(|(pkg, _)| pkg.name().into())
+            .collect()
     }
 
     fn add_just_dependency<'env>(
@@ -185,6 +397,7 @@ impl UnityProject {
             env,
             to_install,
             allow_prerelease,
+            self.resolver_mode,
             missing_dependencies,
         );
 
@@ -202,6 +415,10 @@ impl UnityProject {
             changes.conflict_multiple(package, conflicts_with);
         }
 
+        if let Some(conflict) = result.resolution_conflict {
+            changes.resolution_conflict(conflict);
+        }
+
         Ok(())
     }
 
@@ -311,6 +528,7 @@ impl UnityProject {
             env,
             unlocked_dependencies,
             allow_prerelease,
+            self.resolver_mode,
             missing_dependencies,
         );
 
@@ -322,6 +540,10 @@ impl UnityProject {
             changes.conflict_multiple(package, conflicts_with);
         }
 
+        if let Some(conflict) = result.resolution_conflict {
+            changes.resolution_conflict(conflict);
+        }
+
         Ok(())
     }
 }
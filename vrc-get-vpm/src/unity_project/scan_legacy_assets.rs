@@ -0,0 +1,157 @@
+use crate::io::{DefaultProjectIo, DirEntry, IoTrait};
+use crate::unity_project::find_legacy_assets::{Guid, check_guid, try_parse_meta, valid_path};
+use crate::utils::walk_dir_relative;
+use crate::{PackageCollection, PackageInfo, UnityProject, VersionSelector};
+use futures::prelude::*;
+use log::debug;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+
+/// A folder or file in the project declared as legacy by packages.
+#[derive(Debug, Clone)]
+pub struct LegacyAsset<'env> {
+    path: Box<Path>,
+    is_file: bool,
+    packages: Vec<PackageInfo<'env>>,
+}
+
+impl<'env> LegacyAsset<'env> {
+    /// The path relative to the project, which may be moved from the declared path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.is_file
+    }
+
+    /// The packages declaring the asset in `legacyFolders` or `legacyFiles`, which would replace it.
+    pub fn packages(&self) -> &[PackageInfo<'env>] {
+        &self.packages
+    }
+}
+
+/// The assets in `Assets` folder, collected by walking the folder once.
+struct AssetIndex {
+    /// path to whether it's a file
+    paths: HashMap<PathBuf, bool>,
+    guids: HashMap<Guid, PathBuf>,
+}
+
+impl AssetIndex {
+    async fn build(io: &DefaultProjectIo) -> Self {
+        let mut paths = HashMap::new();
+        let mut metas = vec![];
+
+        let mut stream = pin!(walk_dir_relative(io, [PathBuf::from("Assets")]));
+        while let Some((relative, entry)) = stream.next().await {
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            if file_type.is_file() && relative.extension() == Some(OsStr::new("meta")) {
+                metas.push(relative.clone());
+            }
+            paths.insert(relative, file_type.is_file());
+        }
+
+        let guids = stream::iter(metas)
+            .map(|meta| async move {
+                let guid = try_parse_meta(io, &meta).await?;
+                // remove .meta extension
+                let mut path = meta;
+                path.set_extension("");
+                Some((guid, path))
+            })
+            .buffer_unordered(16)
+            .filter_map(|x| async move { x })
+            .collect::<HashMap<_, _>>()
+            .await;
+
+        debug!("Indexed {} assets with {} guids", paths.len(), guids.len());
+
+        Self { paths, guids }
+    }
+
+    async fn exists(&self, io: &DefaultProjectIo, path: &Path, is_file: bool) -> bool {
+        if path.starts_with("Assets") {
+            self.paths.get(path) == Some(&is_file)
+        } else {
+            // legacy packages in Packages folder
+            (io.metadata(path).await)
+                .map(|x| x.is_file() == is_file)
+                .unwrap_or(false)
+        }
+    }
+
+    fn find_guid(&self, guid: Guid, is_file: bool) -> Option<&Path> {
+        let path = self.guids.get(&guid)?;
+        (self.paths.get(path) == Some(&is_file)).then_some(path.as_path())
+    }
+}
+
+impl UnityProject {
+    /// Scans the project for the folders and files declared in `legacyFolders` or `legacyFiles`
+    /// of the latest version of every package in the collection.
+    ///
+    /// `Assets` is walked only once, and the assets moved from the declared path
+    /// are found with the GUID in the `.meta` files.
+    pub async fn scan_legacy_assets<'env>(
+        &self,
+        collection: &'env impl PackageCollection,
+        include_prerelease: bool,
+    ) -> Vec<LegacyAsset<'env>> {
+        let version_selector =
+            VersionSelector::latest_for(Some(self.unity_version()), include_prerelease);
+
+        let names = (collection.get_all_packages())
+            .map(|x| x.name())
+            .collect::<HashSet<_>>();
+        let packages = (names.into_iter())
+            .filter_map(|name| collection.find_package_by_name(name, version_selector))
+            .collect::<Vec<_>>();
+
+        let index = AssetIndex::build(&self.io).await;
+        let mut found = BTreeMap::<Box<Path>, LegacyAsset>::new();
+
+        for package in packages {
+            let json = package.package_json();
+            let folders = (json.legacy_folders().iter()).map(|(path, guid)| (path, guid, false));
+            let files = (json.legacy_files().iter()).map(|(path, guid)| (path, guid, true));
+
+            for (path, guid, is_file) in folders.chain(files) {
+                // some packages uses '/' as path separator.
+                let declared = PathBuf::from(path.replace('\\', "/"));
+                let guid = guid.as_deref().and_then(Guid::parse);
+
+                let path = if !declared.is_absolute()
+                    && valid_path(&declared)
+                    && index.exists(&self.io, &declared, is_file).await
+                    && check_guid(&self.io, &declared, guid).await
+                {
+                    declared.as_path()
+                } else if let Some(path) = guid.and_then(|guid| index.find_guid(guid, is_file)) {
+                    path
+                } else {
+                    continue;
+                };
+
+                let asset = found.entry(path.into()).or_insert_with(|| LegacyAsset {
+                    path: path.into(),
+                    is_file,
+                    packages: vec![],
+                });
+                if !asset.packages.iter().any(|x| x.name() == package.name()) {
+                    asset.packages.push(package);
+                }
+            }
+        }
+
+        let mut found = found.into_values().collect::<Vec<_>>();
+        for asset in &mut found {
+            asset.packages.sort_by_key(|x| x.name());
+        }
+        found
+    }
+}
@@ -18,7 +18,6 @@ struct Parsed {
 }
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub(super) enum UpmDependency {
     // minimum version name. build meta is not supported by upm
     Version(Version),
@@ -26,6 +25,15 @@ pub(super) enum UpmDependency {
     OtherNotation(Box<str>),
 }
 
+impl std::fmt::Display for UpmDependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpmDependency::Version(version) => std::fmt::Display::fmt(version, f),
+            UpmDependency::OtherNotation(notation) => f.write_str(notation),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for UpmDependency {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -112,7 +120,6 @@ impl UpmManifest {
         })
     }
 
-    #[allow(dead_code)]
     pub(super) fn dependencies(&self) -> impl Iterator<Item = (&str, &UpmDependency)> {
         self.controller
             .as_json
@@ -1,3 +1,4 @@
+use crate::PackageInfo;
 use crate::io;
 use crate::io::DefaultProjectIo;
 use crate::unity_project::LockedDependencyInfo;
@@ -5,10 +6,11 @@ use crate::utils::{SaveController, load_json_or_default, save_json};
 use crate::version::{DependencyRange, Version, VersionRange};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 const MANIFEST_PATH: &str = "Packages/vpm-manifest.json";
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct AsJson {
     #[serde(default)]
@@ -27,9 +29,49 @@ struct VpmLockedDependency {
     pub version: Version,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dependencies: Option<IndexMap<Box<str>, VersionRange>>,
+    // vrc-get specific: the hash and the repository the package is installed from
+    #[serde(rename = "zipSHA256", default, skip_serializing_if = "Option::is_none")]
+    pub zip_sha_256: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<LockedRepository>,
+}
+
+/// The repository the locked package is installed from.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LockedRepository {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<Url>,
 }
 
-#[derive(Debug)]
+impl LockedRepository {
+    /// Returns the repository of the package. `None` for local packages.
+    pub(crate) fn from_package(package: PackageInfo) -> Option<Self> {
+        let repo = package.repo()?;
+        Some(Self {
+            id: repo.id().map(Into::into),
+            url: repo.url().cloned(),
+        })
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+}
+
+impl VpmLockedDependency {
+    fn as_info<'a>(&'a self, name: &'a str) -> LockedDependencyInfo<'a> {
+        LockedDependencyInfo::new(name, &self.version, self.dependencies.as_ref())
+            .with_source(self.zip_sha_256.as_deref(), self.repository.as_ref())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(super) struct VpmManifest {
     controller: SaveController<AsJson>,
 }
@@ -58,18 +100,17 @@ impl VpmManifest {
     }
 
     pub(super) fn all_locked(&self) -> impl Iterator<Item = LockedDependencyInfo<'_>> {
-        self.controller.locked.iter().map(|(name, dep)| {
-            LockedDependencyInfo::new(name.as_ref(), &dep.version, dep.dependencies.as_ref())
-        })
+        self.controller
+            .locked
+            .iter()
+            .map(|(name, dep)| dep.as_info(name))
     }
 
     pub(super) fn get_locked(&self, package: &str) -> Option<LockedDependencyInfo<'_>> {
         self.controller
             .locked
             .get_key_value(package)
-            .map(|(package, x)| {
-                LockedDependencyInfo::new(package, &x.version, x.dependencies.as_ref())
-            })
+            .map(|(package, x)| x.as_info(package))
     }
 
     pub(super) fn add_dependency(&mut self, name: &str, version: DependencyRange) {
@@ -84,16 +125,47 @@ impl VpmManifest {
         name: &str,
         version: Version,
         dependencies: IndexMap<Box<str>, VersionRange>,
+        zip_sha_256: Option<Box<str>>,
+        repository: Option<LockedRepository>,
     ) {
         self.controller.as_mut().locked.insert(
             name.into(),
             VpmLockedDependency {
                 version,
                 dependencies: Some(dependencies),
+                zip_sha_256,
+                repository,
             },
         );
     }
 
+    /// Updates the recorded hash and repository of the locked package.
+    ///
+    /// Returns `true` if the recorded information is changed.
+    pub(super) fn set_locked_source(
+        &mut self,
+        name: &str,
+        zip_sha_256: Option<Box<str>>,
+        repository: Option<LockedRepository>,
+    ) -> bool {
+        let Some(locked) = self.controller.locked.get(name) else {
+            return false;
+        };
+        let same_repository = match (&locked.repository, &repository) {
+            (Some(a), Some(b)) => a.id == b.id && a.url == b.url,
+            (None, None) => true,
+            _ => false,
+        };
+        if locked.zip_sha_256 == zip_sha_256 && same_repository {
+            return false;
+        }
+
+        let locked = self.controller.as_mut().locked.get_mut(name).unwrap();
+        locked.zip_sha_256 = zip_sha_256;
+        locked.repository = repository;
+        true
+    }
+
     pub(crate) fn remove_packages<'a>(&mut self, names: impl Iterator<Item = &'a str>) {
         for name in names {
             self.controller.as_mut().locked.shift_remove(name);
@@ -114,4 +186,20 @@ impl VpmManifest {
     pub(super) fn to_json(&self) -> io::Result<Vec<u8>> {
         crate::utils::to_vec_pretty_os_eol(&*self.controller)
     }
+
+    /// Returns the json value of the manifest to embed the manifest in other files.
+    pub(super) fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(&*self.controller).expect("serializing manifest")
+    }
+
+    pub(super) fn from_value(value: serde_json::Value) -> serde_json::Result<Self> {
+        Ok(Self {
+            controller: SaveController::new(serde_json::from_value(value)?),
+        })
+    }
+
+    /// Replaces the whole content of the manifest. The manifest will be saved with the next save.
+    pub(super) fn replace_with(&mut self, other: &VpmManifest) {
+        *self.controller.as_mut() = (*other.controller).clone();
+    }
 }
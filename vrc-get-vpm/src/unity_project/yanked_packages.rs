@@ -0,0 +1,97 @@
+use crate::version::{PrereleaseAcceptance, Version, VersionRange};
+use crate::{PackageCollection, PackageInfo, UnityProject, VersionSelector};
+
+/// A locked package whose locked version is yanked.
+#[derive(Debug, Clone, Copy)]
+pub struct YankedLockedPackage<'env> {
+    package: PackageInfo<'env>,
+    replacement: Option<PackageInfo<'env>>,
+}
+
+impl<'env> YankedLockedPackage<'env> {
+    pub fn name(&self) -> &'env str {
+        self.package.name()
+    }
+
+    /// The locked version, which is yanked.
+    pub fn version(&self) -> &'env Version {
+        self.package.version()
+    }
+
+    pub fn yank_reason(&self) -> Option<&'env str> {
+        self.package.yank_reason()
+    }
+
+    /// The nearest non-yanked version satisfying every range on the package.
+    ///
+    /// The oldest newer version is preferred, and the newest older version is used
+    /// if there are no newer versions. `None` if no version satisfies the ranges.
+    pub fn replacement(&self) -> Option<PackageInfo<'env>> {
+        self.replacement
+    }
+}
+
+impl UnityProject {
+    /// Lists the locked packages whose locked version is yanked in the collection.
+    pub fn yanked_locked_packages<'env>(
+        &self,
+        collection: &'env impl PackageCollection,
+        allow_prerelease: bool,
+    ) -> Vec<YankedLockedPackage<'env>> {
+        let mut yanked = Vec::new();
+
+        for locked in self.locked_packages() {
+            let Some(package) = collection.find_package_by_name(
+                locked.name(),
+                VersionSelector::specific_version(locked.version()),
+            ) else {
+                continue;
+            };
+            if !package.is_yanked() {
+                continue;
+            }
+
+            let ranges = self.ranges_on(locked.name());
+            let ranges = ranges.iter().collect::<Vec<_>>();
+            let selector = VersionSelector::ranges_for(
+                Some(self.unity_version()),
+                &ranges,
+                PrereleaseAcceptance::allow_or_minimum(allow_prerelease),
+            );
+
+            let candidates = collection
+                .find_packages(locked.name())
+                .filter(|x| selector.satisfies(x.package_json()))
+                .collect::<Vec<_>>();
+            let replacement = (candidates.iter().copied())
+                .filter(|x| x.version() > locked.version())
+                .min_by_key(|x| x.version())
+                .or_else(|| {
+                    (candidates.iter().copied())
+                        .filter(|x| x.version() < locked.version())
+                        .max_by_key(|x| x.version())
+                });
+
+            yanked.push(YankedLockedPackage {
+                package,
+                replacement,
+            });
+        }
+
+        yanked
+    }
+
+    /// The ranges the project and the other locked packages impose on the package.
+    fn ranges_on(&self, name: &str) -> Vec<VersionRange> {
+        let mut ranges = Vec::new();
+        if let Some(range) = self.manifest.get_dependency(name) {
+            ranges.push(range.as_range());
+        }
+        for package in self.all_packages() {
+            if let Some(range) = package.dependencies().get(name) {
+                ranges.push(range.clone());
+            }
+        }
+        ranges
+    }
+}
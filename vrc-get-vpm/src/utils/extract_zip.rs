@@ -1,21 +1,41 @@
 use crate::io;
+use crate::io::IoTrait;
 use crate::io::SeekFrom;
-use crate::io::{DefaultProjectIo, IoTrait};
 use crate::utils::MapResultExt;
 use async_zip::base::read::seek::ZipFileReader;
 use futures::prelude::*;
 use std::path::{Component, Path};
 
+/// Reads the file at `name` in the zip file. Returns `None` if the zip file doesn't have the file.
+pub(crate) async fn read_zip_entry(
+    zip_file: impl AsyncBufRead + AsyncSeek + Unpin,
+    name: &str,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut zip_reader = ZipFileReader::new(zip_file).await.err_mapped()?;
+    let Some(index) =
+        (zip_reader.file().entries().iter()).position(|x| x.filename().as_str().ok() == Some(name))
+    else {
+        return Ok(None);
+    };
+    let mut content = Vec::new();
+    (zip_reader.reader_without_entry(index).await.err_mapped()?)
+        .read_to_end(&mut content)
+        .await?;
+    Ok(Some(content))
+}
+
 pub(crate) async fn extract_zip(
     mut zip_file: impl AsyncBufRead + AsyncSeek + Unpin,
-    io: &DefaultProjectIo,
+    io: &impl IoTrait,
     dest_folder: &Path,
+    on_progress: impl Fn(usize, usize),
 ) -> io::Result<()> {
     // extract zip file
     zip_file.seek(SeekFrom::Start(0)).await?;
 
     let mut zip_reader = ZipFileReader::new(zip_file).await.err_mapped()?;
-    for i in 0..zip_reader.file().entries().len() {
+    let total = zip_reader.file().entries().len();
+    for i in 0..total {
         let entry = &zip_reader.file().entries()[i];
         let Some(filename) = entry.filename().as_str().ok() else {
             return Err(io::Error::new(
@@ -41,6 +61,8 @@ pub(crate) async fn extract_zip(
             io::copy(&mut reader, &mut dest_file).await?;
             dest_file.flush().await?;
         }
+
+        on_progress(i + 1, total);
     }
 
     Ok(())
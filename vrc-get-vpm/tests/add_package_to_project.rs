@@ -1,12 +1,16 @@
 use common::*;
 use std::collections::HashSet;
 use std::io;
+use std::num::NonZeroUsize;
 use std::path::Path;
-use vrc_get_vpm::PackageManifest;
-use vrc_get_vpm::io::IoTrait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use vrc_get_vpm::io::{DefaultProjectIo, IoTrait};
 use vrc_get_vpm::unity_project::pending_project_changes::RemoveReason;
-use vrc_get_vpm::unity_project::{AddPackageErr, AddPackageOperation};
+use vrc_get_vpm::unity_project::{
+    AddPackageErr, AddPackageOperation, DerivationStep, ResolverMode,
+};
 use vrc_get_vpm::version::Version;
+use vrc_get_vpm::{AbortCheck, InstallProgress, PackageInfo, PackageInstaller, PackageManifest};
 
 mod common;
 
@@ -1229,6 +1233,155 @@ fn no_temp_folder_after_add() {
     })
 }
 
+#[test]
+fn rollback_on_install_error() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency_range("com.vrchat.avatars", "~3.4.x")
+            .add_locked("com.vrchat.avatars", Version::new(3, 4, 2), &[])
+            .add_file(
+                "Packages/com.vrchat.avatars/package.json",
+                r#"{"name":"com.vrchat.avatars","version":"3.4.2"}"#,
+            )
+            .add_file("Packages/com.vrchat.avatars/content.txt", "text")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.vrchat.avatars",
+                Version::new(3, 5, 0),
+            ))
+            .build();
+
+        let avatars_package = collection.get_package("com.vrchat.avatars", Version::new(3, 5, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[avatars_package],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        // VirtualInstaller always fails to install packages
+        let env = VirtualInstaller::new();
+
+        project
+            .apply_pending_changes(&env, resolve)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            project.get_locked("com.vrchat.avatars").unwrap().version(),
+            &Version::new(3, 4, 2)
+        );
+        project
+            .io()
+            .metadata("Packages/com.vrchat.avatars/content.txt".as_ref())
+            .await
+            .unwrap();
+        assert_eq!(
+            project
+                .io()
+                .metadata("Temp".as_ref())
+                .await
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    })
+}
+
+#[test]
+fn install_with_limited_concurrency() {
+    struct LimitedInstaller {
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    impl PackageInstaller for LimitedInstaller {
+        async fn install_package(
+            &self,
+            io: &DefaultProjectIo,
+            package: PackageInfo<'_>,
+            dest_folder: &Path,
+            _: Option<&str>,
+            progress: &dyn InstallProgress,
+            _: &AbortCheck,
+        ) -> io::Result<Option<Box<str>>> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            io.create_dir_all(dest_folder).await?;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            progress.installed(package);
+            Ok(None)
+        }
+
+        fn max_concurrency(&self) -> Option<NonZeroUsize> {
+            NonZeroUsize::new(1)
+        }
+    }
+
+    struct CountProgress(AtomicUsize);
+
+    impl InstallProgress for CountProgress {
+        fn installed(&self, _: PackageInfo<'_>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new().build().await.unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let avatars_package = collection.get_package("com.vrchat.avatars", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[avatars_package],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let env = LimitedInstaller {
+            running: AtomicUsize::new(0),
+            max_running: AtomicUsize::new(0),
+        };
+        let progress = CountProgress(AtomicUsize::new(0));
+
+        project
+            .apply_pending_changes_with_progress(&env, resolve, &progress)
+            .await
+            .unwrap();
+
+        assert_eq!(env.max_running.load(Ordering::SeqCst), 1);
+        assert_eq!(progress.0.load(Ordering::SeqCst), 2);
+        project
+            .io()
+            .metadata("Packages/com.vrchat.base".as_ref())
+            .await
+            .unwrap();
+    })
+}
+
 #[test]
 #[ignore = "No suitable way to lock a file"]
 fn locked_in_package_folder() {
@@ -1370,3 +1523,276 @@ fn install_conflict_with_unlocked() {
 }
 
 // endregion
+
+// region backtracking resolver
+
+#[test]
+fn backtracking_selects_older_transitive_dependency() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.b", Version::new(1, 0, 0))
+            .add_locked(
+                "com.anatawa12.b",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.c", "<3.0.0")],
+            )
+            .add_locked("com.anatawa12.c", Version::new(2, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        project.set_resolver_mode(ResolverMode::Backtracking);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.x", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.a", ">=1.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.a", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.c", ">=2.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.a", Version::new(2, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.c", ">=3.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.b", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.c", "<3.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.c",
+                Version::new(2, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.c",
+                Version::new(3, 0, 0),
+            ))
+            .build();
+
+        let x = collection.get_package("com.anatawa12.x", Version::new(1, 0, 0));
+        let a_1_0_0 = collection.get_package("com.anatawa12.a", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[x],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 2);
+        assert_eq!(resolve.conflicts().len(), 0);
+        assert!(resolve.resolution_conflict().is_none());
+
+        assert_installing_to_both(&resolve, &x);
+        assert_installing_to_locked_only(&resolve, &a_1_0_0);
+    })
+}
+
+#[test]
+fn backtracking_upgrades_locked_dependant() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "1.0.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        project.set_resolver_mode(ResolverMode::Backtracking);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 1, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.1.0"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 1, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.1.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+        let avatars_1_1_0 = collection.get_package("com.vrchat.avatars", Version::new(1, 1, 0));
+        let base_1_1_0 = collection.get_package("com.vrchat.base", Version::new(1, 1, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolve.package_changes().len(), 3);
+        assert_eq!(resolve.conflicts().len(), 0);
+
+        assert_installing_to_both(&resolve, &tool);
+        assert_installing_to_locked_only(&resolve, &avatars_1_1_0);
+        assert_installing_to_locked_only(&resolve, &base_1_1_0);
+    })
+}
+
+#[test]
+fn backtracking_explains_conflict() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "1.0.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        project.set_resolver_mode(ResolverMode::Backtracking);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(1, 1, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.vrchat.base", "^1.1.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        // the result of the greedy resolver is kept
+        assert_eq!(resolve.conflicts().len(), 1);
+
+        let conflict = resolve.resolution_conflict().unwrap();
+        assert!(conflict.steps().contains(&DerivationStep::Requires {
+            dependant: Some(("com.anatawa12.tool".into(), Version::new(1, 0, 0))),
+            dependency: "com.vrchat.base".into(),
+            range: "^1.1.0".parse().unwrap(),
+        }));
+        assert!(conflict.steps().contains(&DerivationStep::Requires {
+            dependant: Some(("com.vrchat.avatars".into(), Version::new(1, 0, 0))),
+            dependency: "com.vrchat.base".into(),
+            range: "1.0.0".parse().unwrap(),
+        }));
+        assert_eq!(
+            conflict.steps().last(),
+            Some(&DerivationStep::AllVersionsConflict {
+                package: "com.vrchat.avatars".into(),
+                versions: vec![Version::new(1, 0, 0)],
+            })
+        );
+    })
+}
+
+#[test]
+fn backtracking_does_not_downgrade_locked_package() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.b", Version::new(1, 0, 0))
+            .add_locked(
+                "com.anatawa12.b",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.c", ">=1.0.0")],
+            )
+            .add_locked(
+                "com.anatawa12.c",
+                Version::new(2, 0, 0),
+                &[("com.anatawa12.e", ">=2.0.0")],
+            )
+            .add_locked("com.anatawa12.e", Version::new(2, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        project.set_resolver_mode(ResolverMode::Backtracking);
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.b", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.c", ">=1.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.c", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.e", ">=1.0.0"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.c", Version::new(2, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.e", ">=2.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.e",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.e",
+                Version::new(2, 0, 0),
+            ))
+            .add(
+                PackageManifest::new("com.anatawa12.tool", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.e", "<2.0.0"),
+            )
+            .build();
+
+        let tool = collection.get_package("com.anatawa12.tool", Version::new(1, 0, 0));
+
+        let resolve = project
+            .add_package_request(
+                &collection,
+                &[tool],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await
+            .unwrap();
+
+        // com.anatawa12.c 1.0.0 would resolve the conflict, but it's a downgrade nobody asked for
+        assert!(resolve.package_changes().get("com.anatawa12.c").is_none());
+
+        let conflict = resolve.resolution_conflict().unwrap();
+        assert!(conflict.steps().contains(&DerivationStep::LockedVersion {
+            package: "com.anatawa12.c".into(),
+            version: Version::new(2, 0, 0),
+        }));
+    })
+}
+
+// endregion
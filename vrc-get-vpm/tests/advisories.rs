@@ -0,0 +1,162 @@
+use crate::common::*;
+use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::advisory::{AdvisoryDatabase, AdvisorySeverity};
+use vrc_get_vpm::unity_project::{AddPackageErr, AddPackageOperation, ResolvePackageErr};
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+fn advisories() -> AdvisoryDatabase {
+    AdvisoryDatabase::parse(
+        br#"{
+            "advisories": [
+                {
+                    "id": "TEST-0001",
+                    "package": "com.anatawa12.library",
+                    "versions": ">=1.0.0 <1.2.0",
+                    "severity": "high",
+                    "description": "uploads the project"
+                },
+                {
+                    "id": "TEST-0002",
+                    "package": "com.anatawa12.library",
+                    "versions": "1.1.x",
+                    "severity": "critical",
+                    "description": "deletes the project",
+                    "url": "https://example.com/TEST-0002"
+                }
+            ]
+        }"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn find_most_severe_first() {
+    let advisories = advisories();
+
+    let found = advisories.find("com.anatawa12.library", &Version::new(1, 1, 0));
+    let found = (found.iter())
+        .map(|x| (x.id(), x.severity()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            ("TEST-0002", AdvisorySeverity::Critical),
+            ("TEST-0001", AdvisorySeverity::High),
+        ]
+    );
+
+    assert!(
+        (advisories.find("com.anatawa12.library", &"1.0.1-beta.1".parse().unwrap()))
+            .iter()
+            .any(|x| x.id() == "TEST-0001")
+    );
+    assert!((advisories.find("com.anatawa12.library", &Version::new(1, 2, 0))).is_empty());
+    assert!((advisories.find("com.anatawa12.package", &Version::new(1, 1, 0))).is_empty());
+}
+
+#[test]
+fn audit_locked_and_unlocked() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.library", "^1.0.0")],
+            )
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .add_package_json(
+                "com.anatawa12.unlocked",
+                r#"{ "name": "com.anatawa12.library", "version": "1.1.0" }"#,
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let matches = advisories().audit(&project);
+        let mut matches = (matches.iter())
+            .map(|x| (x.version().to_string(), x.advisory().id()))
+            .collect::<Vec<_>>();
+        matches.sort();
+
+        assert_eq!(
+            matches,
+            vec![
+                ("1.0.0".to_string(), "TEST-0001"),
+                ("1.1.0".to_string(), "TEST-0001"),
+                ("1.1.0".to_string(), "TEST-0002"),
+            ]
+        );
+    })
+}
+
+#[test]
+fn deny_adding_packages() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new().build().await.unwrap();
+        project.set_advisories(advisories());
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 1, 0),
+            ))
+            .build();
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 0, 0));
+        let result = project
+            .add_package_request(
+                &collection,
+                &[package],
+                AddPackageOperation::InstallToDependencies,
+                false,
+            )
+            .await;
+
+        let Err(AddPackageErr::DeniedByAdvisories { matches }) = result else {
+            panic!("adding package is not denied: {result:?}");
+        };
+        assert_eq!(matches.len(), 2);
+        assert!(
+            matches
+                .iter()
+                .all(|x| x.package() == "com.anatawa12.library")
+        );
+    })
+}
+
+#[test]
+fn deny_resolving_locked_packages() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.library", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        project.resolve_request(&collection).await.unwrap();
+
+        project.set_advisories(advisories());
+        let result = project.resolve_request(&collection).await;
+
+        let Err(ResolvePackageErr::DeniedByAdvisories { matches }) = result else {
+            panic!("resolving is not denied: {result:?}");
+        };
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].advisory().id(), "TEST-0001");
+    })
+}
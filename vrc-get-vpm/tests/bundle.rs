@@ -0,0 +1,238 @@
+use crate::common::*;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use indexmap::IndexMap;
+use serde_json::json;
+use std::convert::Infallible;
+use std::path::Path;
+use url::Url;
+use vrc_get_vpm::environment::{
+    PackageCollection, PackageInstaller, Settings, export_bundle, import_bundle,
+};
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::repository::LocalCachedRepository;
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+const PACKAGE: &str = "com.example.package";
+
+/// Adds the repository with the package to the environment, and the package to the package cache.
+async fn add_repository(io: &DefaultEnvironmentIo, settings: &mut Settings) {
+    let version = Version::new(1, 0, 0);
+    let files: &[(&str, &[u8])] = &[(RUNTIME_FILE, RUNTIME_CONTENT)];
+    let zip = build_package_zip(PACKAGE, &version, files).await;
+    let sha256 = add_package_cache(&io.resolve("".as_ref()), PACKAGE, &version, &zip);
+
+    let repository = example_repository(json!({
+        PACKAGE: {
+            "versions": {
+                "1.0.0": {
+                    "name": PACKAGE,
+                    "version": "1.0.0",
+                    "url": "https://example.com/com.example.package-1.0.0.zip",
+                    "zipSHA256": sha256,
+                },
+            },
+        },
+    }));
+    let cache = LocalCachedRepository::new(repository, IndexMap::new());
+    let cache_path = io.resolve("Repos/example.json".as_ref());
+    std::fs::write(&cache_path, serde_json::to_vec(&cache).unwrap()).unwrap();
+
+    let url = Url::parse(EXAMPLE_REPOSITORY_URL).unwrap();
+    assert!(settings.add_remote_repo(&url, None, IndexMap::new(), cache.repo(), &cache_path));
+}
+
+/// Writes the bundle with `manifest` and the files.
+async fn write_bundle(path: &Path, manifest: serde_json::Value, files: &[(&str, &[u8])]) {
+    let mut writer = ZipFileWriter::new(Vec::<u8>::new());
+    let manifest = manifest.to_string();
+    let files =
+        std::iter::once(("vrc-get-bundle.json", manifest.as_bytes())).chain(files.iter().copied());
+    for (name, content) in files {
+        let entry = ZipEntryBuilder::new(name.to_string().into(), Compression::Stored);
+        writer.write_entry_whole(entry, content).await.unwrap();
+    }
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, writer.close().await.unwrap()).unwrap();
+}
+
+#[test]
+fn export_and_import_bundle() {
+    block_on(async {
+        let bundle = get_temp_path("test_bundles").join("bundle.zip");
+        std::fs::create_dir_all(bundle.parent().unwrap()).unwrap();
+
+        // export from the environment with the package
+        let source = new_environment(get_temp_path("test_environments"));
+        let mut settings = Settings::load(&source).await.unwrap();
+        add_repository(&source, &mut settings).await;
+        let collection = PackageCollection::load_cache(&settings, &source)
+            .await
+            .unwrap();
+
+        let project = VirtualProjectBuilder::new()
+            .add_dependency(PACKAGE, Version::new(1, 0, 0))
+            .add_locked(PACKAGE, Version::new(1, 0, 0), &[])
+            .add_locked("com.example.user-package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let installer = PackageInstaller::new(&source, None::<&Infallible>);
+        let exported = export_bundle(&installer, &collection, &[project], &bundle)
+            .await
+            .unwrap();
+        assert_eq!(exported.packages().len(), 1);
+        assert_eq!(exported.packages()[0].name(), PACKAGE);
+        assert_eq!(exported.not_found().len(), 1);
+        assert_eq!(exported.not_found()[0].name(), "com.example.user-package");
+        assert_eq!(
+            exported.repositories(),
+            [Url::parse(EXAMPLE_REPOSITORY_URL).unwrap()]
+        );
+
+        // import to the empty environment
+        let target = new_environment(get_temp_path("test_environments"));
+        let mut settings = Settings::load(&target).await.unwrap();
+        let imported = import_bundle(&mut settings, &target, &bundle)
+            .await
+            .unwrap();
+        assert_eq!(imported.packages().len(), 1);
+        assert_eq!(
+            imported.added_repositories(),
+            [Url::parse(EXAMPLE_REPOSITORY_URL).unwrap()]
+        );
+        assert!(imported.updated_repositories().is_empty());
+
+        // resolve the project offline with the imported packages
+        let collection = PackageCollection::load_cache(&settings, &target)
+            .await
+            .unwrap();
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency(PACKAGE, Version::new(1, 0, 0))
+            .add_locked(PACKAGE, Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        let installer = PackageInstaller::new(&target, None::<&Infallible>);
+        let changes = project.resolve_request(&collection).await.unwrap();
+        project
+            .apply_pending_changes(&installer, changes)
+            .await
+            .unwrap();
+
+        let installed = project
+            .project_dir()
+            .join("Packages")
+            .join(PACKAGE)
+            .join(RUNTIME_FILE);
+        assert_eq!(std::fs::read(installed).unwrap(), RUNTIME_CONTENT);
+    })
+}
+
+#[test]
+fn import_bundle_rejects_hash_mismatch() {
+    block_on(async {
+        let bundle = get_temp_path("test_bundles").join("bundle.zip");
+        let zip = build_package_zip(PACKAGE, &Version::new(1, 0, 0), &[]).await;
+        let zip_path = "packages/com.example.package/vrc-get-com.example.package-1.0.0.zip";
+        write_bundle(
+            &bundle,
+            json!({
+                "formatVersion": 1,
+                "repositories": [],
+                "packages": [{
+                    "name": PACKAGE,
+                    "version": "1.0.0",
+                    "repository": EXAMPLE_REPOSITORY_URL,
+                    "sha256": "0".repeat(64),
+                    "path": zip_path,
+                }],
+            }),
+            &[(zip_path, &zip)],
+        )
+        .await;
+
+        let io = new_environment(get_temp_path("test_environments"));
+        let mut settings = Settings::load(&io).await.unwrap();
+        let error = import_bundle(&mut settings, &io, &bundle)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // the broken zip file must not be left in the package cache
+        let cache = io.resolve("Repos/com.example.package".as_ref());
+        assert!(!cache.join("vrc-get-com.example.package-1.0.0.zip").exists());
+        assert!(
+            !cache
+                .join("vrc-get-com.example.package-1.0.0.zip.sha256")
+                .exists()
+        );
+
+        // the existing cache is kept
+        let files: &[(&str, &[u8])] = &[(RUNTIME_FILE, RUNTIME_CONTENT)];
+        let existing = build_package_zip(PACKAGE, &Version::new(1, 0, 0), files).await;
+        let sha256 = add_package_cache(
+            &io.resolve("".as_ref()),
+            PACKAGE,
+            &Version::new(1, 0, 0),
+            &existing,
+        );
+        let error = import_bundle(&mut settings, &io, &bundle)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            std::fs::read(cache.join("vrc-get-com.example.package-1.0.0.zip")).unwrap(),
+            existing
+        );
+        assert!(
+            std::fs::read_to_string(cache.join("vrc-get-com.example.package-1.0.0.zip.sha256"))
+                .unwrap()
+                .starts_with(&sha256)
+        );
+        // no temporary files are left
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), 2);
+    })
+}
+
+#[test]
+fn import_bundle_rejects_bad_package_name() {
+    block_on(async {
+        for name in [
+            "",
+            "../escape",
+            "com.example/package",
+            "com.example\\package",
+            ".hidden",
+        ] {
+            let bundle = get_temp_path("test_bundles").join("bundle.zip");
+            write_bundle(
+                &bundle,
+                json!({
+                    "formatVersion": 1,
+                    "repositories": [],
+                    "packages": [{
+                        "name": name,
+                        "version": "1.0.0",
+                        "repository": EXAMPLE_REPOSITORY_URL,
+                        "sha256": "0".repeat(64),
+                        "path": "packages/package.zip",
+                    }],
+                }),
+                &[("packages/package.zip", b"")],
+            )
+            .await;
+
+            let io = new_environment(get_temp_path("test_environments"));
+            let mut settings = Settings::load(&io).await.unwrap();
+            let error = import_bundle(&mut settings, &io, &bundle)
+                .await
+                .unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{name}");
+            assert!(!io.resolve("escape".as_ref()).exists());
+        }
+    })
+}
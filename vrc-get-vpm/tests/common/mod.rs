@@ -13,6 +13,8 @@ pub use virtual_environment::VirtualInstaller;
 pub use virtual_project_builder::VirtualProjectBuilder;
 
 use vrc_get_vpm::PackageInfo;
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::repository::RemoteRepository;
 use vrc_get_vpm::unity_project::PendingProjectChanges;
 use vrc_get_vpm::unity_project::pending_project_changes::RemoveReason;
 use vrc_get_vpm::version::{DependencyRange, Version};
@@ -110,3 +112,97 @@ pub fn get_temp_path(base_name: &str) -> PathBuf {
         std::panic::Location::caller().line()
     ))
 }
+
+/// The file in the packages used in tests with the package store or the package cache.
+pub const RUNTIME_FILE: &str = "Runtime/Script.cs";
+pub const RUNTIME_CONTENT: &[u8] = b"public class Script {}\n";
+
+pub const EXAMPLE_REPOSITORY_URL: &str = "https://example.com/vpm.json";
+
+/// Creates the empty environment at `path`, removing the existing one.
+///
+/// The official and curated repositories are ignored since they are not available offline.
+pub fn new_environment(path: PathBuf) -> DefaultEnvironmentIo {
+    if path.exists() {
+        make_writable(&path);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+    std::fs::create_dir_all(path.join("vrc-get")).unwrap();
+    std::fs::write(
+        path.join("vrc-get/settings.json"),
+        r#"{ "ignoreOfficialRepository": true, "ignoreCuratedRepository": true }"#,
+    )
+    .unwrap();
+    DefaultEnvironmentIo::new(path.into_boxed_path())
+}
+
+/// Makes the files in `path` writable, e.g. the read-only files in the package store.
+pub fn make_writable(path: &Path) {
+    for entry in std::fs::read_dir(path).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_dir() {
+            make_writable(&entry.path());
+        } else {
+            let mut permissions = entry.metadata().unwrap().permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            std::fs::set_permissions(entry.path(), permissions).unwrap();
+        }
+    }
+}
+
+/// The repository at [`EXAMPLE_REPOSITORY_URL`] with `packages` in the format of `vpm.json`.
+pub fn example_repository(packages: serde_json::Value) -> RemoteRepository {
+    RemoteRepository::parse(
+        serde_json::json!({
+            "name": "Example Repository",
+            "id": "com.example.repository",
+            "url": EXAMPLE_REPOSITORY_URL,
+            "packages": packages,
+        })
+        .as_object()
+        .unwrap()
+        .clone(),
+    )
+    .unwrap()
+}
+
+/// Builds the zip file of the package with `package.json` and `files`.
+pub async fn build_package_zip(name: &str, version: &Version, files: &[(&str, &[u8])]) -> Vec<u8> {
+    use async_zip::base::write::ZipFileWriter;
+    use async_zip::{Compression, ZipEntryBuilder};
+
+    let package_json = serde_json::json!({
+        "name": name,
+        "version": version.to_string(),
+    })
+    .to_string();
+
+    let mut writer = ZipFileWriter::new(Vec::<u8>::new());
+    let files =
+        std::iter::once(("package.json", package_json.as_bytes())).chain(files.iter().copied());
+    for (name, content) in files {
+        let entry = ZipEntryBuilder::new(name.to_string().into(), Compression::Stored);
+        writer.write_entry_whole(entry, content).await.unwrap();
+    }
+    writer.close().await.unwrap()
+}
+
+/// Adds the zip file to the package cache of the environment at `env`.
+///
+/// Returns the hex-encoded sha256 hash of the zip file.
+pub fn add_package_cache(env: &Path, name: &str, version: &Version, zip: &[u8]) -> String {
+    use sha2::Digest;
+
+    let sha256 = hex::encode(sha2::Sha256::digest(zip));
+    let folder = env.join("Repos").join(name);
+    std::fs::create_dir_all(&folder).unwrap();
+    let zip_name = format!("vrc-get-{name}-{version}.zip");
+    std::fs::write(folder.join(&zip_name), zip).unwrap();
+    std::fs::write(
+        folder.join(format!("{zip_name}.sha256")),
+        format!("{sha256} {zip_name}\n"),
+    )
+    .unwrap();
+    sha256
+}
@@ -3,13 +3,14 @@ use indexmap::IndexMap;
 use serde_json::json;
 use std::future::Future;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use vrc_get_vpm::io::{DefaultProjectIo, IoTrait};
 use vrc_get_vpm::unity_project::pending_project_changes::Remove;
 use vrc_get_vpm::version::{Version, VersionRange};
 use vrc_get_vpm::{
-    AbortCheck, HttpClient, PackageInfo, PackageInstaller, PackageManifest, UnityProject,
+    AbortCheck, HttpClient, InstallProgress, PackageInfo, PackageInstaller, PackageManifest,
+    UnityProject,
 };
 
 pub struct VirtualInstaller {}
@@ -25,8 +26,11 @@ impl PackageInstaller for VirtualInstaller {
         &self,
         _: &DefaultProjectIo,
         _: PackageInfo<'_>,
+        _: &Path,
+        _: Option<&str>,
+        _: &dyn InstallProgress,
         _: &AbortCheck,
-    ) -> impl Future<Output = io::Result<()>> {
+    ) -> impl Future<Output = io::Result<Option<Box<str>>>> {
         std::future::ready(Err(io::Error::new(
             io::ErrorKind::Unsupported,
             "install_package not supported in VirtualEnvironment",
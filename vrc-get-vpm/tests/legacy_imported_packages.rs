@@ -0,0 +1,83 @@
+use crate::common::*;
+use std::path::Path;
+use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+#[test]
+fn find_legacy_imported_packages() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.installed", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.installed", Version::new(1, 0, 0), &[])
+            .add_dir("Assets/VRCSDK")
+            .add_dir("Assets/ByPath")
+            .add_file("Assets/ByPath.cs", "// empty file")
+            .add_dir("Assets/Moved/ByGuid")
+            .add_file(
+                "Assets/Moved/ByGuid.meta",
+                "guid: 1c54b633da4d4d2abc01c6dedae67e09",
+            )
+            .add_dir("Assets/Installed")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(3, 5, 0))
+                    .add_legacy_folder("Assets\\VRCSDK", ""),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.by-path", Version::new(1, 1, 0))
+                    .add_legacy_folder("Assets\\ByPath", "")
+                    .add_legacy_file("Assets\\ByPath.cs", ""),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.by-guid", Version::new(1, 0, 0))
+                    .add_legacy_folder("Assets\\ByGuid", "1c54b633da4d4d2abc01c6dedae67e09"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.installed", Version::new(1, 0, 0))
+                    .add_legacy_folder("Assets\\Installed", ""),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.not-imported", Version::new(1, 0, 0))
+                    .add_legacy_folder("Assets\\NotImported", ""),
+            )
+            .build();
+
+        let found = project
+            .find_legacy_imported_packages(&collection, false)
+            .await;
+        let found = (found.iter())
+            .map(|x| {
+                (
+                    x.package().name(),
+                    x.package().version().clone(),
+                    x.folders().to_vec(),
+                    x.files().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "com.anatawa12.by-guid",
+                    Version::new(1, 0, 0),
+                    vec![Path::new("Assets/Moved/ByGuid").into()],
+                    vec![],
+                ),
+                (
+                    "com.anatawa12.by-path",
+                    Version::new(1, 1, 0),
+                    vec![Path::new("Assets/ByPath").into()],
+                    vec![Path::new("Assets/ByPath.cs").into()],
+                ),
+            ]
+        );
+    })
+}
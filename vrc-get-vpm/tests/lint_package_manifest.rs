@@ -0,0 +1,120 @@
+use crate::common::*;
+use vrc_get_vpm::version::Version;
+use vrc_get_vpm::{ManifestIssueSeverity, PackageManifest, PackageManifestLint};
+
+mod common;
+
+fn issues(lint: &PackageManifestLint) -> Vec<(ManifestIssueSeverity, &str)> {
+    let mut issues = (lint.issues().iter())
+        .map(|x| (x.severity(), x.pointer()))
+        .collect::<Vec<_>>();
+    issues.sort_by_key(|x| x.1);
+    issues
+}
+
+#[test]
+fn valid_manifest() {
+    let lint = PackageManifestLint::new(
+        br#"{
+            "name": "com.anatawa12.package",
+            "version": "1.0.0",
+            "unity": "2022.3",
+            "url": "https://example.com/com.anatawa12.package-1.0.0.zip",
+            "vpmDependencies": { "com.vrchat.base": "^3.5.0" },
+            "legacyFolders": { "Assets/Legacy": "0123456789abcdef0123456789abcdef" }
+        }"#,
+    );
+
+    assert_eq!(issues(&lint), vec![]);
+    assert_eq!(lint.manifest().unwrap().name(), "com.anatawa12.package");
+}
+
+#[test]
+fn reports_every_issue() {
+    use ManifestIssueSeverity::*;
+
+    let lint = PackageManifestLint::new(
+        br#"{
+            "name": "com.anatawa12.package",
+            "version": "1.0.0",
+            "unity": "2022.x",
+            "vpmDependencies": {
+                "com.anatawa12.package": "^1.0.0",
+                "com.vrchat.base": "not a range"
+            },
+            "legacyFolders": { "Assets/Legacy": "not-a-guid" },
+            "changelogUrl": "ftp://example.com/CHANGELOG.md",
+            "documentationUrl": "not a url"
+        }"#,
+    );
+
+    assert_eq!(
+        issues(&lint),
+        vec![
+            (Error, "/changelogUrl"),
+            (Error, "/documentationUrl"),
+            (Error, "/legacyFolders/Assets~1Legacy"),
+            (Error, "/unity"),
+            (Warning, "/url"),
+            (Error, "/vpmDependencies/com.anatawa12.package"),
+            (Error, "/vpmDependencies/com.vrchat.base"),
+        ]
+    );
+    assert!(lint.has_errors());
+}
+
+#[test]
+fn invalid_required_fields() {
+    let lint = PackageManifestLint::new(br#"{ "version": "invalid" }"#);
+
+    assert_eq!(
+        issues(&lint),
+        vec![
+            (ManifestIssueSeverity::Error, "/name"),
+            (ManifestIssueSeverity::Error, "/version"),
+        ]
+    );
+    assert!(lint.manifest().is_none());
+}
+
+#[test]
+fn check_dependencies_and_zip_name() {
+    let yanked: PackageManifest = serde_json::from_value(serde_json::json!({
+        "name": "com.anatawa12.yanked",
+        "version": "1.0.0",
+        "vrc-get": { "yanked": true },
+    }))
+    .unwrap();
+    let collection = PackageCollectionBuilder::new()
+        .add(yanked)
+        .add(PackageManifest::new(
+            "com.anatawa12.partially-yanked",
+            Version::new(1, 0, 0),
+        ))
+        .build();
+
+    let mut lint = PackageManifestLint::new(
+        br#"{
+            "name": "com.anatawa12.package",
+            "version": "1.0.0",
+            "url": "https://example.com/package.zip",
+            "vpmDependencies": {
+                "com.anatawa12.yanked": "^1.0.0",
+                "com.anatawa12.partially-yanked": "^1.0.0"
+            }
+        }"#,
+    );
+    lint.check_dependencies(&collection);
+    lint.check_zip_file_name("com.anatawa12.package-2.0.0.zip");
+
+    assert_eq!(
+        issues(&lint),
+        vec![
+            (ManifestIssueSeverity::Warning, "/version"),
+            (
+                ManifestIssueSeverity::Warning,
+                "/vpmDependencies/com.anatawa12.yanked"
+            ),
+        ]
+    );
+}
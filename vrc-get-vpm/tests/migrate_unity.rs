@@ -0,0 +1,257 @@
+use crate::common::*;
+use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::unity_project::{MigrateUnityError, UnityMigrationTarget};
+use vrc_get_vpm::version::{UnityVersion, Version};
+
+mod common;
+
+fn manifest(name: &str, version: Version, unity: &str) -> PackageManifest {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "version": version.to_string(),
+        "unity": unity,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn find_migration_target() {
+    let target = UnityMigrationTarget::find(UnityVersion::new_f1(2022, 3, 6)).unwrap();
+    assert_eq!(target.unity(), (2022, 3));
+    assert!(target.can_migrate_from(
+        UnityVersion::new_f1(2019, 4, 31),
+        UnityVersion::new_f1(2022, 3, 6)
+    ));
+    assert!(target.can_migrate_from(
+        UnityVersion::new_f1(2022, 3, 6),
+        UnityVersion::new_f1(2022, 3, 22)
+    ));
+    assert!(!target.can_migrate_from(
+        UnityVersion::new_f1(2022, 3, 22),
+        UnityVersion::new_f1(2022, 3, 6)
+    ));
+
+    assert!(UnityMigrationTarget::find(UnityVersion::new_f1(2021, 3, 0)).is_none());
+}
+
+#[test]
+fn unsupported_target_and_source() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .with_unity("2022.3.22f1", "887be4894c44")
+            .build()
+            .await
+            .unwrap();
+        let collection = PackageCollectionBuilder::new().build();
+        let installer = VirtualInstaller::new();
+
+        let result = (project)
+            .migrate_unity(UnityVersion::new_f1(2021, 3, 0), &collection, &installer)
+            .await;
+        assert!(matches!(
+            result,
+            Err(MigrateUnityError::UnsupportedTarget(_))
+        ));
+
+        let result = (project)
+            .migrate_unity(UnityVersion::new_f1(2022, 3, 6), &collection, &installer)
+            .await;
+        assert!(matches!(
+            result,
+            Err(MigrateUnityError::UnityVersionMismatch { .. })
+        ));
+    })
+}
+
+#[test]
+fn report_blocking_packages() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.base", Version::new(3, 4, 0))
+            .add_dependency("com.anatawa12.compatible", Version::new(1, 0, 0))
+            .add_locked("com.vrchat.base", Version::new(3, 4, 0), &[])
+            .add_locked("com.anatawa12.compatible", Version::new(1, 0, 0), &[])
+            .add_package_json(
+                "com.anatawa12.unlocked",
+                r#"{ "name": "com.anatawa12.unlocked", "version": "1.0.0", "unity": "2023.1" }"#,
+            )
+            .build()
+            .await
+            .unwrap();
+        let collection = PackageCollectionBuilder::new()
+            .add(manifest("com.vrchat.base", Version::new(3, 4, 0), "2019.4"))
+            .add(manifest("com.vrchat.base", Version::new(3, 5, 0), "2022.3"))
+            .add(manifest(
+                "com.anatawa12.compatible",
+                Version::new(1, 0, 0),
+                "2019.4",
+            ))
+            .build();
+
+        let result = (project)
+            .migrate_unity(
+                UnityVersion::new_f1(2022, 3, 22),
+                &collection,
+                &VirtualInstaller::new(),
+            )
+            .await;
+
+        // VRCSDK only for 2019 is not blocking since it will be upgraded
+        let Err(MigrateUnityError::BlockingPackages(blocking)) = result else {
+            panic!("migration is not blocked: {result:?}");
+        };
+        assert_eq!(blocking.len(), 1);
+        assert_eq!(blocking[0].name(), "com.anatawa12.unlocked");
+        assert_eq!(blocking[0].minimum_unity(), Some((2023, 1)));
+    })
+}
+
+#[test]
+fn vrcsdk_not_found() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.base", Version::new(3, 4, 0))
+            .add_locked("com.vrchat.base", Version::new(3, 4, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        let collection = PackageCollectionBuilder::new()
+            .add(manifest("com.vrchat.base", Version::new(3, 4, 0), "2019.4"))
+            .build();
+
+        let result = (project)
+            .migrate_unity(
+                UnityVersion::new_f1(2022, 3, 22),
+                &collection,
+                &VirtualInstaller::new(),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(MigrateUnityError::VpmPackageNotFound(ref name)) if name.as_ref() == "com.vrchat.base"
+        ));
+    })
+}
+
+#[test]
+fn check_migration() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.base", Version::new(3, 4, 0))
+            .add_dependency("com.vrchat.core.vpm-resolver", Version::new(0, 1, 27))
+            .add_dependency("com.anatawa12.upgradable", Version::new(1, 0, 0))
+            .add_locked("com.vrchat.base", Version::new(3, 4, 0), &[])
+            .add_locked("com.vrchat.core.vpm-resolver", Version::new(0, 1, 27), &[])
+            .add_locked("com.anatawa12.upgradable", Version::new(1, 0, 0), &[])
+            .add_package_json(
+                "com.anatawa12.unlocked",
+                r#"{ "name": "com.anatawa12.unlocked", "version": "1.0.0", "unity": "2023.1" }"#,
+            )
+            .build()
+            .await
+            .unwrap();
+        let collection = PackageCollectionBuilder::new()
+            .add(manifest("com.vrchat.base", Version::new(3, 4, 0), "2019.4"))
+            .add(manifest("com.vrchat.base", Version::new(3, 5, 2), "2022.3"))
+            .add(manifest(
+                "com.vrchat.core.vpm-resolver",
+                Version::new(0, 1, 27),
+                "2019.4",
+            ))
+            .add(manifest(
+                "com.anatawa12.upgradable",
+                Version::new(1, 0, 0),
+                "2023.1",
+            ))
+            .add(manifest(
+                "com.anatawa12.upgradable",
+                Version::new(1, 1, 0),
+                "2022.3",
+            ))
+            .build();
+
+        let issues = project
+            .check_unity_migration(UnityVersion::new_f1(2022, 3, 22), &collection)
+            .unwrap();
+        let mut issues = (issues.iter())
+            .map(|x| {
+                (
+                    x.name(),
+                    x.to_string(),
+                    x.is_locked(),
+                    x.proposed_version().cloned(),
+                    x.blocks_migration(),
+                )
+            })
+            .collect::<Vec<_>>();
+        issues.sort();
+
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    "com.anatawa12.unlocked",
+                    "com.anatawa12.unlocked@1.0.0: requires Unity 2023.1 or later (no compatible version found)".to_string(),
+                    false,
+                    None,
+                    true,
+                ),
+                (
+                    "com.anatawa12.upgradable",
+                    "com.anatawa12.upgradable@1.0.0: requires Unity 2023.1 or later (proposed: 1.1.0)".to_string(),
+                    true,
+                    Some(Version::new(1, 1, 0)),
+                    true,
+                ),
+                (
+                    "com.vrchat.base",
+                    "com.vrchat.base@3.4.0: 3.5.0 or later is required (proposed: 3.5.2)".to_string(),
+                    true,
+                    Some(Version::new(3, 5, 2)),
+                    false,
+                ),
+            ]
+        );
+    })
+}
+
+#[test]
+fn migrate_2022_upgrades_vrcsdk_to_latest() {
+    block_on(async {
+        let collection = PackageCollectionBuilder::new()
+            .add(manifest("com.vrchat.base", Version::new(3, 6, 0), "2022.3"))
+            .build();
+        let build_project = || async {
+            VirtualProjectBuilder::new()
+                .add_dependency("com.vrchat.base", Version::new(3, 5, 0))
+                .add_locked("com.vrchat.base", Version::new(3, 5, 0), &[])
+                .build()
+                .await
+                .unwrap()
+        };
+
+        // VRCSDK satisfying the minimum version is kept
+        let mut project = build_project().await;
+        (project)
+            .migrate_unity(
+                UnityVersion::new_f1(2022, 3, 22),
+                &collection,
+                &VirtualInstaller::new(),
+            )
+            .await
+            .unwrap();
+        let locked = project.get_locked("com.vrchat.base").unwrap();
+        assert_eq!(locked.version(), &Version::new(3, 5, 0));
+
+        // migrating to 2022 upgrades VRCSDK to the latest, which VirtualInstaller cannot install
+        let mut project = build_project().await;
+        let result = (project)
+            .migrate_unity_2022(&collection, &VirtualInstaller::new())
+            .await;
+        assert!(matches!(
+            result,
+            Err(MigrateUnityError::Io(ref err)) if err.kind() == std::io::ErrorKind::Unsupported
+        ));
+    })
+}
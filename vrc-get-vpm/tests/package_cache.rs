@@ -0,0 +1,121 @@
+use crate::common::*;
+use std::time::{Duration, SystemTime};
+use vrc_get_vpm::environment::{
+    CachedPackage, CachedPackageHash, PackageCachePrunePolicy, package_cache_entries,
+    remove_cached_package,
+};
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+/// sha256 of the empty file
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+fn add_cache(env: &std::path::Path, name: &str, version: &str, sha256: Option<&str>) {
+    let folder = env.join("Repos").join(name);
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join(format!("vrc-get-{name}-{version}.zip")), b"").unwrap();
+    if let Some(sha256) = sha256 {
+        std::fs::write(
+            folder.join(format!("vrc-get-{name}-{version}.zip.sha256")),
+            sha256,
+        )
+        .unwrap();
+    }
+}
+
+fn find<'a>(entries: &'a [CachedPackage], name: &str, version: Version) -> &'a CachedPackage {
+    entries
+        .iter()
+        .find(|x| x.name() == name && *x.version() == version)
+        .unwrap()
+}
+
+#[test]
+fn list_and_prune_cache() {
+    block_on(async {
+        let env_path = get_temp_path("test_environments");
+        if env_path.exists() {
+            std::fs::remove_dir_all(&env_path).unwrap();
+        }
+        add_cache(&env_path, "com.example.a", "1.0.0", Some(EMPTY_SHA256));
+        add_cache(&env_path, "com.example.a", "1.1.0", Some(EMPTY_SHA256));
+        add_cache(
+            &env_path,
+            "com.example.a",
+            "1.2.0-beta.1",
+            Some(&"0".repeat(64)),
+        );
+        add_cache(&env_path, "com.example.b", "2.0.0", None);
+        // not a cache of vrc-get
+        std::fs::write(env_path.join("Repos/com.example.b/other.zip"), b"").unwrap();
+
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.example.a", Version::new(1, 0, 0))
+            .add_locked("com.example.a", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let io = DefaultEnvironmentIo::new(env_path.into_boxed_path());
+        let entries = package_cache_entries(&io, [project.project_dir()])
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 4);
+
+        let a_1_0_0 = find(&entries, "com.example.a", Version::new(1, 0, 0));
+        assert_eq!(a_1_0_0.hash(), CachedPackageHash::Valid);
+        assert!(a_1_0_0.used());
+        let a_1_1_0 = find(&entries, "com.example.a", Version::new(1, 1, 0));
+        assert_eq!(a_1_1_0.hash(), CachedPackageHash::Valid);
+        assert!(!a_1_1_0.used());
+        let a_beta = find(&entries, "com.example.a", "1.2.0-beta.1".parse().unwrap());
+        assert_eq!(a_beta.hash(), CachedPackageHash::Mismatch);
+        let b = find(&entries, "com.example.b", Version::new(2, 0, 0));
+        assert_eq!(b.hash(), CachedPackageHash::Missing);
+
+        let now = SystemTime::now();
+
+        // no condition selects nothing
+        assert!(
+            PackageCachePrunePolicy::new()
+                .select(&entries, now)
+                .is_empty()
+        );
+
+        let mut keep_latest = PackageCachePrunePolicy::new();
+        keep_latest.set_keep_latest(Some(1));
+        let mut selected = keep_latest
+            .select(&entries, now)
+            .into_iter()
+            .map(|x| x.version().clone())
+            .collect::<Vec<_>>();
+        selected.sort();
+        assert_eq!(selected, [Version::new(1, 0, 0), Version::new(1, 1, 0)]);
+
+        let mut unused_and_old = PackageCachePrunePolicy::new();
+        unused_and_old.set_keep_latest(Some(1));
+        unused_and_old.set_unused_only(true);
+        let selected = unused_and_old.select(&entries, now);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(*selected[0].version(), Version::new(1, 1, 0));
+
+        let mut older_than = PackageCachePrunePolicy::new();
+        older_than.set_older_than(Some(Duration::from_secs(24 * 60 * 60)));
+        assert!(older_than.select(&entries, now).is_empty());
+        let later = now + Duration::from_secs(2 * 24 * 60 * 60);
+        assert_eq!(older_than.select(&entries, later).len(), 4);
+
+        remove_cached_package(&io, selected[0]).await.unwrap();
+        remove_cached_package(&io, b).await.unwrap();
+        let entries = package_cache_entries(&io, [project.project_dir()])
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(
+            !io.resolve("Repos/com.example.a/vrc-get-com.example.a-1.1.0.zip.sha256".as_ref())
+                .exists()
+        );
+    })
+}
@@ -0,0 +1,293 @@
+use crate::common::*;
+use indexmap::IndexMap;
+use serde_json::json;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use vrc_get_vpm::environment::{PackageInstaller, gc_package_store, package_store_entries};
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::repository::LocalCachedRepository;
+use vrc_get_vpm::version::Version;
+use vrc_get_vpm::{PackageInfo, PackageManifest, UnityProject, VersionSelector};
+
+mod common;
+
+/// The collection of packages from the remote repository, which are installed from the package cache
+struct RemoteCollection {
+    packages: Vec<PackageManifest>,
+    repository: LocalCachedRepository,
+}
+
+impl RemoteCollection {
+    fn new(packages: Vec<PackageManifest>) -> Self {
+        let repository = example_repository(json!({}));
+        Self {
+            packages,
+            repository: LocalCachedRepository::new(repository, IndexMap::new()),
+        }
+    }
+}
+
+impl vrc_get_vpm::PackageCollection for RemoteCollection {
+    fn get_all_packages(&self) -> impl Iterator<Item = PackageInfo<'_>> {
+        (self.packages.iter()).map(|json| PackageInfo::remote(json, &self.repository))
+    }
+
+    fn find_packages(&self, package: &str) -> impl Iterator<Item = PackageInfo<'_>> {
+        self.get_all_packages()
+            .filter(move |pkg| pkg.name() == package)
+    }
+
+    fn find_package_by_name(
+        &self,
+        name: &str,
+        version: VersionSelector,
+    ) -> Option<PackageInfo<'_>> {
+        self.find_packages(name)
+            .filter(|pkg| version.satisfies(pkg.package_json()))
+            .max_by_key(|pkg| pkg.version())
+    }
+}
+
+async fn add_zip_cache(io: &DefaultEnvironmentIo, package: &PackageManifest) -> String {
+    let files: &[(&str, &[u8])] = &[(RUNTIME_FILE, RUNTIME_CONTENT)];
+    let zip = build_package_zip(package.name(), package.version(), files).await;
+    add_package_cache(
+        &io.resolve("".as_ref()),
+        package.name(),
+        package.version(),
+        &zip,
+    )
+}
+
+async fn install(
+    io: &DefaultEnvironmentIo,
+    collection: &RemoteCollection,
+    project: &mut UnityProject,
+) {
+    let mut installer = PackageInstaller::new(io, None::<&Infallible>);
+    installer.set_use_package_store(true);
+
+    let changes = project.resolve_request(collection).await.unwrap();
+    project
+        .apply_pending_changes(&installer, changes)
+        .await
+        .unwrap();
+}
+
+fn store_file(io: &DefaultEnvironmentIo, sha256: &str) -> PathBuf {
+    io.resolve(
+        format!("vrc-get/package-store/com.example.package/1.0.0-{sha256}/{RUNTIME_FILE}").as_ref(),
+    )
+}
+
+#[test]
+fn install_from_store() {
+    block_on(async {
+        let io = new_environment(get_temp_path("test_environments"));
+        let collection = RemoteCollection::new(vec![PackageManifest::new(
+            "com.example.package",
+            Version::new(1, 0, 0),
+        )]);
+        let sha256 = add_zip_cache(&io, &collection.packages[0]).await;
+
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.example.package", Version::new(1, 0, 0))
+            .add_locked("com.example.package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        install(&io, &collection, &mut project).await;
+
+        let installed = project
+            .project_dir()
+            .join("Packages/com.example.package")
+            .join(RUNTIME_FILE);
+        assert_eq!(std::fs::read(&installed).unwrap(), RUNTIME_CONTENT);
+
+        let entries = package_store_entries(&io).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name(), "com.example.package");
+        assert_eq!(*entries[0].version(), Version::new(1, 0, 0));
+        assert_eq!(entries[0].sha256(), sha256);
+
+        // the files in the store must not be modified through hard links
+        let store_file = store_file(&io, &sha256);
+        assert!(
+            std::fs::metadata(&store_file)
+                .unwrap()
+                .permissions()
+                .readonly()
+        );
+        // the index of the entry is not installed to the project
+        assert!(
+            !project
+                .project_dir()
+                .join("Packages/com.example.package/.vrc-get-store-index.json")
+                .exists()
+        );
+    })
+}
+
+#[test]
+fn projects_share_store_entry() {
+    block_on(async {
+        let io = new_environment(get_temp_path("test_environments"));
+        let collection = RemoteCollection::new(vec![PackageManifest::new(
+            "com.example.package",
+            Version::new(1, 0, 0),
+        )]);
+        let sha256 = add_zip_cache(&io, &collection.packages[0]).await;
+
+        let mut project1 = VirtualProjectBuilder::new()
+            .add_dependency("com.example.package", Version::new(1, 0, 0))
+            .add_locked("com.example.package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        let mut project2 = VirtualProjectBuilder::new()
+            .add_dependency("com.example.package", Version::new(1, 0, 0))
+            .add_locked("com.example.package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        install(&io, &collection, &mut project1).await;
+        install(&io, &collection, &mut project2).await;
+
+        for project in [&project1, &project2] {
+            let installed = project
+                .project_dir()
+                .join("Packages/com.example.package")
+                .join(RUNTIME_FILE);
+            assert_eq!(std::fs::read(&installed).unwrap(), RUNTIME_CONTENT);
+        }
+
+        // both projects are installed from the same entry
+        assert_eq!(package_store_entries(&io).await.unwrap().len(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            // files are either hard-linked to both projects or cloned with copy-on-write
+            let links = std::fs::metadata(store_file(&io, &sha256)).unwrap().nlink();
+            assert!(
+                links == 1 || links == 3,
+                "unexpected number of links: {links}"
+            );
+        }
+    })
+}
+
+#[test]
+fn modified_store_entry_is_extracted_again() {
+    block_on(async {
+        let io = new_environment(get_temp_path("test_environments"));
+        let collection = RemoteCollection::new(vec![PackageManifest::new(
+            "com.example.package",
+            Version::new(1, 0, 0),
+        )]);
+        let sha256 = add_zip_cache(&io, &collection.packages[0]).await;
+
+        let mut project1 = VirtualProjectBuilder::new()
+            .add_dependency("com.example.package", Version::new(1, 0, 0))
+            .add_locked("com.example.package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        install(&io, &collection, &mut project1).await;
+
+        // modify the file in the store, e.g. through a hard link by an editor
+        let store_file = store_file(&io, &sha256);
+        make_writable(store_file.parent().unwrap());
+        std::fs::write(&store_file, b"// modified\n").unwrap();
+
+        let mut project2 = VirtualProjectBuilder::new()
+            .add_dependency("com.example.package", Version::new(1, 0, 0))
+            .add_locked("com.example.package", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+        install(&io, &collection, &mut project2).await;
+
+        let installed = project2
+            .project_dir()
+            .join("Packages/com.example.package")
+            .join(RUNTIME_FILE);
+        assert_eq!(std::fs::read(&installed).unwrap(), RUNTIME_CONTENT);
+        assert_eq!(std::fs::read(&store_file).unwrap(), RUNTIME_CONTENT);
+    })
+}
+
+#[test]
+fn gc_package_store_removes_unused_entries() {
+    block_on(async {
+        let io = new_environment(get_temp_path("test_environments"));
+
+        let store = io.resolve("vrc-get/package-store".as_ref());
+        let used = store.join(format!("com.example.used/1.0.0-{}", "a".repeat(64)));
+        let unused_version = store.join(format!("com.example.used/2.0.0-{}", "b".repeat(64)));
+        let unused = store.join(format!("com.example.unused/1.0.0-{}", "c".repeat(64)));
+        let temp = store.join("com.example.used/.tmp-interrupted");
+        for folder in [&used, &unused_version, &unused, &temp] {
+            std::fs::create_dir_all(folder).unwrap();
+            std::fs::write(folder.join("package.json"), b"{}").unwrap();
+        }
+
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.example.used", Version::new(1, 0, 0))
+            .add_locked("com.example.used", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let mut removed = gc_package_store(&io, [project.project_dir()])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.name().to_owned(), x.version().clone()))
+            .collect::<Vec<_>>();
+        removed.sort();
+        assert_eq!(
+            removed,
+            [
+                ("com.example.unused".to_owned(), Version::new(1, 0, 0)),
+                ("com.example.used".to_owned(), Version::new(2, 0, 0)),
+            ]
+        );
+
+        assert!(used.exists());
+        assert!(!unused_version.exists());
+        assert!(!temp.exists());
+        // empty package folders are removed
+        assert!(!store.join("com.example.unused").exists());
+
+        let entries = package_store_entries(&io).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name(), "com.example.used");
+    })
+}
+
+#[test]
+fn lock_records_hash_of_installed_zip() {
+    block_on(async {
+        let io = new_environment(get_temp_path("test_environments"));
+        // the repository doesn't publish zipSHA256 of the package
+        let collection = RemoteCollection::new(vec![PackageManifest::new(
+            "com.example.package",
+            Version::new(1, 0, 0),
+        )]);
+        let sha256 = add_zip_cache(&io, &collection.packages[0]).await;
+
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.example.package", Version::new(1, 0, 0))
+            .build()
+            .await
+            .unwrap();
+
+        install(&io, &collection, &mut project).await;
+
+        let locked = project.get_locked("com.example.package").unwrap();
+        assert_eq!(locked.zip_sha_256(), Some(sha256.as_str()));
+    })
+}
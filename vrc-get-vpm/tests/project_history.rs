@@ -0,0 +1,110 @@
+use crate::common::*;
+use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+#[test]
+fn no_history() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new().build().await.unwrap();
+
+        assert!(project.history().await.unwrap().is_empty());
+    })
+}
+
+#[test]
+fn record_and_restore_remove() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(3, 4, 2))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(3, 4, 2),
+                &[("com.vrchat.base", "3.4.2")],
+            )
+            .add_locked("com.vrchat.base", Version::new(3, 4, 2), &[])
+            .add_package_json(
+                "com.vrchat.avatars",
+                r#"{"name":"com.vrchat.avatars","version":"3.4.2"}"#,
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let env = VirtualInstaller::new();
+
+        let changes = project
+            .remove_request(&["com.vrchat.avatars"])
+            .await
+            .unwrap();
+        project.apply_pending_changes(&env, changes).await.unwrap();
+
+        let history = project.history().await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        let entry = &history[0];
+        assert_eq!(entry.changes().len(), 2);
+        for change in entry.changes() {
+            assert_eq!(change.before(), Some(&Version::new(3, 4, 2)));
+            assert_eq!(change.after(), None);
+        }
+
+        let loaded = project.get_history(entry.id()).await.unwrap().unwrap();
+        assert_eq!(loaded.id(), entry.id());
+        assert!(project.get_history("../manifest").await.unwrap().is_none());
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(3, 4, 2))
+                    .add_vpm_dependency("com.vrchat.base", "3.4.2"),
+            )
+            .add(PackageManifest::new(
+                "com.vrchat.base",
+                Version::new(3, 4, 2),
+            ))
+            .build();
+
+        let avatars = collection.get_package("com.vrchat.avatars", Version::new(3, 4, 2));
+        let base = collection.get_package("com.vrchat.base", Version::new(3, 4, 2));
+
+        let result = project
+            .restore_history_request(&collection, entry)
+            .await
+            .unwrap();
+
+        assert_eq!(result.package_changes().len(), 2);
+        assert_installing_to_locked_only(&result, &avatars);
+        assert_installing_to_locked_only(&result, &base);
+    })
+}
+
+#[test]
+fn restore_missing_version() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.gists", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.gists", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let env = VirtualInstaller::new();
+
+        let changes = project
+            .remove_request(&["com.anatawa12.gists"])
+            .await
+            .unwrap();
+        project.apply_pending_changes(&env, changes).await.unwrap();
+
+        let history = project.history().await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        let collection = PackageCollectionBuilder::new().build();
+
+        project
+            .restore_history_request(&collection, &history[0])
+            .await
+            .unwrap_err();
+    })
+}
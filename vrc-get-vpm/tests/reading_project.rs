@@ -59,3 +59,105 @@ fn read_package_json_with_bad_url() {
         assert_eq!(package_json.name(), "com.anatawa12.package");
     })
 }
+
+#[test]
+fn read_locked_hash_and_repository() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_file(
+                "Packages/vpm-manifest.json",
+                r#"{
+                    "dependencies": {},
+                    "locked": {
+                        "com.anatawa12.package": {
+                            "version": "1.0.0",
+                            "zipSHA256": "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                            "repository": {
+                                "id": "com.anatawa12.vpm",
+                                "url": "https://vpm.anatawa12.com/vpm.json"
+                            }
+                        },
+                        "com.anatawa12.library": {
+                            "version": "1.0.0"
+                        }
+                    }
+                }"#,
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let package = project.get_locked("com.anatawa12.package").unwrap();
+        assert_eq!(
+            package.zip_sha_256(),
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        let repository = package.repository().unwrap();
+        assert_eq!(repository.id(), Some("com.anatawa12.vpm"));
+        assert_eq!(
+            repository.url().map(|x| x.as_str()),
+            Some("https://vpm.anatawa12.com/vpm.json")
+        );
+
+        let library = project.get_locked("com.anatawa12.library").unwrap();
+        assert_eq!(library.zip_sha_256(), None);
+        assert!(library.repository().is_none());
+    })
+}
+
+#[test]
+fn dependency_paths() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.vrchat.avatars", Version::new(1, 0, 0))
+            .add_dependency("com.vrchat.worlds", Version::new(1, 0, 0))
+            .add_dependency("com.anatawa12.legacy", Version::new(1, 0, 0))
+            .add_locked(
+                "com.vrchat.avatars",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "1.0.0")],
+            )
+            .add_locked(
+                "com.vrchat.worlds",
+                Version::new(1, 0, 0),
+                &[("com.vrchat.base", "^1.0.0")],
+            )
+            .add_locked("com.vrchat.base", Version::new(1, 0, 0), &[])
+            .add_locked("com.anatawa12.modern", Version::new(2, 0, 0), &[])
+            .add_package_json(
+                "com.anatawa12.modern",
+                r#"{
+                    "name": "com.anatawa12.modern",
+                    "version": "2.0.0",
+                    "legacyPackages": ["com.anatawa12.legacy"]
+                }"#,
+            )
+            .build()
+            .await
+            .unwrap();
+
+        let paths = project.dependency_paths("com.vrchat.base");
+        assert_eq!(paths.len(), 2);
+        for path in &paths {
+            let [root, locked] = path.edges() else {
+                panic!("unexpected path length: {}", path.edges().len())
+            };
+            assert_eq!(root.dependant(), None);
+            assert_eq!(locked.dependant(), Some(root.dependency()));
+            assert_eq!(locked.dependant_version(), Some(&Version::new(1, 0, 0)));
+            assert_eq!(locked.dependency(), "com.vrchat.base");
+        }
+
+        let paths = project.dependency_paths("com.anatawa12.modern");
+        assert_eq!(paths.len(), 1);
+        let [root, replacement] = paths[0].edges() else {
+            panic!("unexpected path length: {}", paths[0].edges().len())
+        };
+        assert_eq!(root.dependency(), "com.anatawa12.legacy");
+        assert!(!root.is_legacy_replacement());
+        assert_eq!(replacement.dependant(), Some("com.anatawa12.legacy"));
+        assert!(replacement.is_legacy_replacement());
+
+        assert!(project.dependency_paths("com.anatawa12.unknown").is_empty());
+    })
+}
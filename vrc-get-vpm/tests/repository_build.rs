@@ -0,0 +1,174 @@
+use crate::common::*;
+use serde_json::Value;
+use std::path::Path;
+use url::Url;
+use vrc_get_vpm::repository::{RemoteRepository, RepositoryBuilder, pack_package};
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+fn write_package(folder: &Path, name: &str, version: &str) {
+    let package = folder.join(name);
+    std::fs::create_dir_all(package.join("Runtime")).unwrap();
+    std::fs::write(
+        package.join("package.json"),
+        format!(r#"{{"name":"{name}","version":"{version}"}}"#),
+    )
+    .unwrap();
+    std::fs::write(package.join("Runtime/file.txt"), b"content").unwrap();
+    std::fs::write(package.join(".DS_Store"), b"junk").unwrap();
+}
+
+#[test]
+fn pack_is_deterministic() {
+    block_on(async {
+        let path = get_temp_path("test_repository_build");
+        if path.exists() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        std::fs::create_dir_all(&path).unwrap();
+        write_package(&path, "com.anatawa12.package", "1.0.0");
+
+        let folder = path.join("com.anatawa12.package");
+        let first = pack_package(&folder, &path.join("first.zip"))
+            .await
+            .unwrap();
+        std::fs::write(folder.join("Runtime/file.txt"), b"content").unwrap();
+        let second = pack_package(&folder, &path.join("second.zip"))
+            .await
+            .unwrap();
+
+        assert_eq!(first.sha256(), second.sha256());
+        assert_eq!(first.manifest().name(), "com.anatawa12.package");
+        assert_eq!(
+            std::fs::read(path.join("first.zip")).unwrap(),
+            std::fs::read(path.join("second.zip")).unwrap()
+        );
+    })
+}
+
+#[test]
+fn pack_excludes_output_and_root_zips() {
+    block_on(async {
+        let path = get_temp_path("test_repository_build");
+        if path.exists() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        std::fs::create_dir_all(&path).unwrap();
+        write_package(&path, "com.anatawa12.package", "1.0.0");
+
+        let folder = path.join("com.anatawa12.package");
+        // zip files in subfolders are part of the package
+        std::fs::write(folder.join("Runtime/archive.zip"), b"zip").unwrap();
+        let outside = pack_package(&folder, &path.join("outside.zip"))
+            .await
+            .unwrap();
+
+        // packing to the package folder must not include the zip file itself
+        let inside = pack_package(&folder, &folder.join("package.zip"))
+            .await
+            .unwrap();
+        assert_eq!(outside.sha256(), inside.sha256());
+
+        // previously packed zip files are not included
+        let repacked = pack_package(&folder, &folder.join("package.tmp"))
+            .await
+            .unwrap();
+        assert_eq!(outside.sha256(), repacked.sha256());
+    })
+}
+
+#[test]
+fn build_and_merge_repository() {
+    block_on(async {
+        let path = get_temp_path("test_repository_build");
+        if path.exists() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        let source = path.join("source");
+        let output = path.join("output");
+        std::fs::create_dir_all(&source).unwrap();
+        write_package(&source, "com.anatawa12.package", "1.0.0");
+
+        let base_url = Url::parse("https://example.com/packages").unwrap();
+
+        let mut builder = RepositoryBuilder::new(base_url.clone(), output.clone());
+        builder.set_id("com.anatawa12.repository");
+        builder.add_all_in(&source).await.unwrap();
+        let first = builder.to_json().unwrap();
+
+        // the new version is added and the old version is kept
+        std::fs::remove_dir_all(source.join("com.anatawa12.package")).unwrap();
+        write_package(&source, "com.anatawa12.package", "1.1.0");
+
+        let mut builder = RepositoryBuilder::new(base_url, output.clone());
+        builder
+            .load_existing(&first, &path.join("index.json"))
+            .unwrap();
+        builder.add_all_in(&source).await.unwrap();
+        assert_eq!(builder.added_packages().len(), 1);
+
+        let json: Value = serde_json::from_slice(&builder.to_json().unwrap()).unwrap();
+        let versions = &json["packages"]["com.anatawa12.package"]["versions"];
+        assert_eq!(json["id"], "com.anatawa12.repository");
+        assert_eq!(
+            versions["1.1.0"]["url"],
+            "https://example.com/packages/com.anatawa12.package-1.1.0.zip"
+        );
+        assert!(versions["1.0.0"]["zipSHA256"].is_string());
+        assert!(output.join("com.anatawa12.package-1.0.0.zip").exists());
+        assert!(output.join("com.anatawa12.package-1.1.0.zip").exists());
+
+        let repository = RemoteRepository::parse(json.as_object().unwrap().clone()).unwrap();
+        assert_eq!(
+            repository.get_versions_of("com.anatawa12.package").count(),
+            2
+        );
+    })
+}
+
+#[test]
+fn pack_rejects_invalid_manifest() {
+    block_on(async {
+        let path = get_temp_path("test_repository_build");
+        if path.exists() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        let folder = path.join("package");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(
+            folder.join("package.json"),
+            r#"{"name":"com.anatawa12.package","version":"1.0.0","legacyFolders":{"Assets/Legacy":"not-a-guid"}}"#,
+        )
+        .unwrap();
+
+        let output = path.join("package.zip");
+        let error = pack_package(&folder, &output).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("not-a-guid"));
+        assert!(!output.exists());
+    })
+}
+
+#[test]
+fn build_rejects_invalid_package_name() {
+    block_on(async {
+        let path = get_temp_path("test_repository_build");
+        if path.exists() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        let source = path.join("source");
+        let output = path.join("output");
+        std::fs::create_dir_all(&source).unwrap();
+        let zip = build_package_zip("../escaped", &Version::new(1, 0, 0), &[]).await;
+        std::fs::write(source.join("package.zip"), zip).unwrap();
+
+        let base_url = Url::parse("https://example.com/packages").unwrap();
+        let mut builder = RepositoryBuilder::new(base_url, output.clone());
+        let error = builder.add_all_in(&source).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("../escaped"));
+        assert!(!path.join("escaped-1.0.0.zip").exists());
+        assert!(builder.added_packages().is_empty());
+    })
+}
@@ -0,0 +1,139 @@
+use crate::common::*;
+use vrc_get_vpm::repository::RepositoryCheck;
+use vrc_get_vpm::version::Version;
+use vrc_get_vpm::{ManifestIssueSeverity, PackageManifest};
+
+mod common;
+
+fn issues(check: &RepositoryCheck) -> Vec<(ManifestIssueSeverity, &str)> {
+    let mut issues = (check.issues().iter())
+        .map(|x| (x.severity(), x.pointer()))
+        .collect::<Vec<_>>();
+    issues.sort_by_key(|x| x.1);
+    issues
+}
+
+#[test]
+fn reports_problems_per_version() {
+    use ManifestIssueSeverity::*;
+
+    let check = RepositoryCheck::new(
+        br#"{
+            "packages": {
+                "com.anatawa12.package": {
+                    "versions": {
+                        "1.0.0": {
+                            "name": "com.anatawa12.other",
+                            "version": "1.0.0",
+                            "url": "https://example.com/1.0.0.zip",
+                            "zipSHA256": "0123"
+                        },
+                        "1.0.0": {
+                            "name": "com.anatawa12.package",
+                            "version": "1.0.0",
+                            "url": "https://example.com/1.0.0.zip",
+                            "zipSHA256": "0123"
+                        },
+                        "1.1.0": {
+                            "name": "com.anatawa12.package",
+                            "version": "1.2.0"
+                        },
+                        "2.0.0": {
+                            "name": "com.anatawa12.package",
+                            "version": "2.0.0",
+                            "url": "https://example.com/2.0.0.zip",
+                            "zipSHA256": "0123",
+                            "vpmDependencies": { "com.anatawa12.package": "^1.0.0" }
+                        }
+                    }
+                }
+            }
+        }"#,
+    );
+
+    assert_eq!(
+        issues(&check),
+        vec![
+            (Error, "/packages/com.anatawa12.package/versions/1.0.0"),
+            (Error, "/packages/com.anatawa12.package/versions/1.0.0/name"),
+            (Error, "/packages/com.anatawa12.package/versions/1.1.0/url"),
+            (
+                Error,
+                "/packages/com.anatawa12.package/versions/1.1.0/version"
+            ),
+            (
+                Warning,
+                "/packages/com.anatawa12.package/versions/1.1.0/zipSHA256"
+            ),
+            (
+                Error,
+                "/packages/com.anatawa12.package/versions/2.0.0/vpmDependencies/com.anatawa12.package"
+            ),
+        ]
+    );
+    assert_eq!(check.versions().count(), 4);
+}
+
+#[test]
+fn broken_structure() {
+    let check = RepositoryCheck::new(br#"{ "packages": { "com.anatawa12.package": [] } }"#);
+
+    assert_eq!(
+        issues(&check),
+        vec![(
+            ManifestIssueSeverity::Error,
+            "/packages/com.anatawa12.package"
+        )]
+    );
+}
+
+#[test]
+fn dependencies_not_in_any_repository() {
+    let collection = PackageCollectionBuilder::new()
+        .add(PackageManifest::new(
+            "com.vrchat.base",
+            Version::new(3, 5, 0),
+        ))
+        .build();
+
+    let mut check = RepositoryCheck::new(
+        br#"{
+            "packages": {
+                "com.anatawa12.package": {
+                    "versions": {
+                        "1.0.0": {
+                            "name": "com.anatawa12.package",
+                            "version": "1.0.0",
+                            "url": "https://example.com/1.0.0.zip",
+                            "zipSHA256": "0123",
+                            "vpmDependencies": {
+                                "com.vrchat.base": "^3.5.0",
+                                "com.anatawa12.library": "^1.0.0",
+                                "com.anatawa12.unknown": "^1.0.0"
+                            }
+                        }
+                    }
+                },
+                "com.anatawa12.library": {
+                    "versions": {
+                        "1.0.0": {
+                            "name": "com.anatawa12.library",
+                            "version": "1.0.0",
+                            "url": "https://example.com/library-1.0.0.zip",
+                            "zipSHA256": "0123"
+                        }
+                    }
+                }
+            }
+        }"#,
+    );
+    check.check_dependencies(&collection);
+
+    assert_eq!(
+        issues(&check),
+        vec![(
+            ManifestIssueSeverity::Error,
+            "/packages/com.anatawa12.package/versions/1.0.0/vpmDependencies/com.anatawa12.unknown"
+        )]
+    );
+}
@@ -1,5 +1,6 @@
 use crate::common::*;
 use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::unity_project::{LockedMismatch, ResolvePackageErr};
 use vrc_get_vpm::version::Version;
 
 mod common;
@@ -251,3 +252,176 @@ fn resolve_both_dependencies_of_unlocked_and_dependencies() {
         assert_installing_to_both(&result, &library2);
     })
 }
+
+#[test]
+fn resolve_frozen_fully_locked() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.library", "^1.0.0")],
+            )
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^1.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            // newer version must not be used in frozen mode
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 1, 0),
+            ))
+            .build();
+
+        let result = project.resolve_request_frozen(&collection).await.unwrap();
+
+        assert_eq!(result.package_changes().len(), 2);
+        assert_eq!(result.conflicts().len(), 0);
+
+        let package = collection.get_package("com.anatawa12.package", Version::new(1, 0, 0));
+        let library = collection.get_package("com.anatawa12.library", Version::new(1, 0, 0));
+        assert_install_only(&result, &package);
+        assert_install_only(&result, &library);
+    })
+}
+
+#[test]
+fn resolve_frozen_not_locked() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency_range("com.anatawa12.package", "^1.0.0")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let err = project
+            .resolve_request_frozen(&collection)
+            .await
+            .unwrap_err();
+        let ResolvePackageErr::LockedMismatch { mismatches } = err else {
+            panic!("unexpected error: {err}");
+        };
+
+        assert_eq!(mismatches.len(), 1);
+        let LockedMismatch::NotLocked {
+            package,
+            required_by,
+            ..
+        } = &mismatches[0]
+        else {
+            panic!("unexpected mismatch: {}", mismatches[0]);
+        };
+        assert_eq!(package.as_ref(), "com.anatawa12.package");
+        assert_eq!(required_by, &None);
+    })
+}
+
+#[test]
+fn resolve_frozen_version_mismatch() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(2, 0, 0))
+            .add_locked(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+                &[("com.anatawa12.library", "^2.0.0")],
+            )
+            .add_locked("com.anatawa12.library", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.anatawa12.package", Version::new(1, 0, 0))
+                    .add_vpm_dependency("com.anatawa12.library", "^2.0.0"),
+            )
+            .add(PackageManifest::new(
+                "com.anatawa12.library",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let err = project
+            .resolve_request_frozen(&collection)
+            .await
+            .unwrap_err();
+        let ResolvePackageErr::LockedMismatch { mismatches } = err else {
+            panic!("unexpected error: {err}");
+        };
+
+        assert_eq!(mismatches.len(), 2);
+        for mismatch in &mismatches {
+            let LockedMismatch::VersionMismatch {
+                package, locked, ..
+            } = mismatch
+            else {
+                panic!("unexpected mismatch: {mismatch}");
+            };
+            assert_eq!(locked, &Version::new(1, 0, 0));
+            assert!(
+                package.as_ref() == "com.anatawa12.package"
+                    || package.as_ref() == "com.anatawa12.library"
+            );
+        }
+    })
+}
+
+#[test]
+fn refresh_locked_hashes() {
+    block_on(async {
+        let mut project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.package", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.package", Version::new(1, 0, 0), &[])
+            .add_locked("com.anatawa12.not-found", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 0, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.package",
+                Version::new(1, 1, 0),
+            ))
+            .build();
+
+        let packages = project.find_locked_packages(&collection);
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name(), "com.anatawa12.package");
+        assert_eq!(packages[0].version(), &Version::new(1, 0, 0));
+
+        // the hash of the downloaded zip file is recorded
+        let downloaded = vec![(packages[0], [0xab; 256 / 8])];
+        let updated = project.refresh_locked_hashes(&downloaded);
+        assert_eq!(updated, vec!["com.anatawa12.package".into()]);
+        assert_eq!(
+            (project.get_locked("com.anatawa12.package").unwrap()).zip_sha_256(),
+            Some("ab".repeat(256 / 8).as_str())
+        );
+
+        // nothing is updated if the hash is the same
+        assert!(project.refresh_locked_hashes(&downloaded).is_empty());
+    })
+}
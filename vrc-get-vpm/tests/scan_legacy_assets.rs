@@ -0,0 +1,107 @@
+use crate::common::*;
+use std::path::Path;
+use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+#[test]
+fn scan_legacy_assets() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency("com.anatawa12.installed", Version::new(1, 0, 0))
+            .add_locked("com.anatawa12.installed", Version::new(1, 0, 0), &[])
+            .add_dir("Assets/VRCSDK")
+            .add_dir("Assets/ByPath")
+            .add_file("Assets/ByPath.cs", "// empty file")
+            .add_dir("Assets/Moved/ByGuid")
+            .add_file(
+                "Assets/Moved/ByGuid.meta",
+                "guid: 1c54b633da4d4d2abc01c6dedae67e09",
+            )
+            .add_file("Assets/Installed.cs", "// empty file")
+            .add_dir("Packages/legacy.package")
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(
+                PackageManifest::new("com.vrchat.avatars", Version::new(3, 5, 0))
+                    .add_legacy_folder("Assets\\VRCSDK", ""),
+            )
+            .add(
+                PackageManifest::new("com.vrchat.worlds", Version::new(3, 5, 0))
+                    .add_legacy_folder("Assets/VRCSDK", "")
+                    .add_legacy_folder("Packages\\legacy.package", ""),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.by-path", Version::new(1, 0, 0))
+                    .add_legacy_folder("Assets\\ByPath", "")
+                    .add_legacy_file("Assets\\ByPath.cs", ""),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.by-guid", Version::new(1, 0, 0))
+                    .add_legacy_folder("Assets\\ByGuid", "1c54b633da4d4d2abc01c6dedae67e09")
+                    // type mismatch
+                    .add_legacy_file("Assets\\ByGuid.cs", "1c54b633da4d4d2abc01c6dedae67e09"),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.installed", Version::new(1, 0, 0))
+                    .add_legacy_file("Assets\\Installed.cs", ""),
+            )
+            .add(
+                PackageManifest::new("com.anatawa12.not-found", Version::new(1, 0, 0))
+                    .add_legacy_folder("Assets\\NotFound", "62a9615044174c818622c19d0181d036")
+                    .add_legacy_folder("..\\Outside", ""),
+            )
+            .build();
+
+        let assets = project.scan_legacy_assets(&collection, false).await;
+        let assets = (assets.iter())
+            .map(|x| {
+                (
+                    x.path(),
+                    x.is_file(),
+                    (x.packages().iter()).map(|x| x.name()).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            assets,
+            vec![
+                (
+                    Path::new("Assets/ByPath"),
+                    false,
+                    vec!["com.anatawa12.by-path"]
+                ),
+                (
+                    Path::new("Assets/ByPath.cs"),
+                    true,
+                    vec!["com.anatawa12.by-path"]
+                ),
+                (
+                    Path::new("Assets/Installed.cs"),
+                    true,
+                    vec!["com.anatawa12.installed"]
+                ),
+                (
+                    Path::new("Assets/Moved/ByGuid"),
+                    false,
+                    vec!["com.anatawa12.by-guid"]
+                ),
+                (
+                    Path::new("Assets/VRCSDK"),
+                    false,
+                    vec!["com.vrchat.avatars", "com.vrchat.worlds"]
+                ),
+                (
+                    Path::new("Packages/legacy.package"),
+                    false,
+                    vec!["com.vrchat.worlds"]
+                ),
+            ]
+        );
+    })
+}
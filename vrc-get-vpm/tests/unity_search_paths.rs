@@ -0,0 +1,77 @@
+#![cfg(all(feature = "unity-hub", target_os = "linux"))]
+
+use crate::common::*;
+use std::path::Path;
+use vrc_get_vpm::unity_hub::{ChipArchitecture, find_unity_editors_in_search_paths};
+use vrc_get_vpm::version::UnityVersion;
+
+mod common;
+
+fn add_editor(folder: &Path, version: Option<&str>) {
+    let editor = folder.join("Editor");
+    std::fs::create_dir_all(&editor).unwrap();
+    // ELF header of little endian x86_64 executable, up to e_machine
+    let mut header = [0u8; 20];
+    header[0..4].copy_from_slice(b"\x7fELF");
+    header[4] = 2; // ELFCLASS64
+    header[5] = 1; // ELFDATA2LSB
+    header[18..20].copy_from_slice(&0x3eu16.to_le_bytes());
+    std::fs::write(editor.join("Unity"), header).unwrap();
+
+    if let Some(version) = version {
+        let module = editor.join("Data/PlaybackEngines/LinuxStandaloneSupport");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(
+            module.join("ivy.xml"),
+            format!(
+                r#"<ivy-module version="2.0"><info e:unityVersion="{version}" /></ivy-module>"#
+            ),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn find_editors_in_search_paths() {
+    block_on(async {
+        let search_path = get_temp_path("test_unity_search_paths");
+        if search_path.exists() {
+            std::fs::remove_dir_all(&search_path).unwrap();
+        }
+        // the version is read from the modules
+        add_editor(&search_path.join("unity/custom-name"), Some("2022.3.22f1"));
+        // the version is read from the folder name
+        add_editor(&search_path.join("2019.4.31f1"), None);
+        // too deep to be found
+        add_editor(&search_path.join("a/b/c/2022.3.6f1"), None);
+        // not an editor
+        std::fs::create_dir_all(search_path.join("unity/empty")).unwrap();
+
+        let mut editors = find_unity_editors_in_search_paths(&[
+            search_path.clone(),
+            search_path.join("not-found"),
+        ])
+        .await;
+        editors.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let editors = (editors.iter())
+            .map(|x| (x.version, x.path.clone(), x.architecture))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            editors,
+            vec![
+                (
+                    UnityVersion::new_f1(2019, 4, 31),
+                    search_path.join("2019.4.31f1/Editor/Unity"),
+                    Some(ChipArchitecture::X86_64),
+                ),
+                (
+                    UnityVersion::new_f1(2022, 3, 22),
+                    search_path.join("unity/custom-name/Editor/Unity"),
+                    Some(ChipArchitecture::X86_64),
+                ),
+            ]
+        );
+    })
+}
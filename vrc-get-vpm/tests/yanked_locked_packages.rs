@@ -0,0 +1,104 @@
+use crate::common::*;
+use vrc_get_vpm::PackageManifest;
+use vrc_get_vpm::version::Version;
+
+mod common;
+
+fn yanked(name: &str, version: Version, reason: Option<&str>) -> PackageManifest {
+    let yanked = match reason {
+        Some(reason) => serde_json::Value::from(reason),
+        None => serde_json::Value::from(true),
+    };
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "version": version.to_string(),
+        "vrc-get": { "yanked": yanked },
+    }))
+    .unwrap()
+}
+
+#[test]
+fn find_replacements_of_yanked_versions() {
+    block_on(async {
+        let project = VirtualProjectBuilder::new()
+            .add_dependency_range("com.anatawa12.upgrade", "^1.0.0")
+            .add_dependency_range("com.anatawa12.downgrade", "^1.0.0")
+            .add_dependency_range("com.anatawa12.no-replacement", "^1.0.0")
+            .add_dependency_range("com.anatawa12.not-yanked", "^1.0.0")
+            .add_locked("com.anatawa12.upgrade", Version::new(1, 1, 0), &[])
+            .add_locked("com.anatawa12.downgrade", Version::new(1, 1, 0), &[])
+            .add_locked("com.anatawa12.no-replacement", Version::new(1, 0, 0), &[])
+            .add_locked("com.anatawa12.not-yanked", Version::new(1, 0, 0), &[])
+            .build()
+            .await
+            .unwrap();
+
+        let collection = PackageCollectionBuilder::new()
+            .add(PackageManifest::new(
+                "com.anatawa12.upgrade",
+                Version::new(1, 0, 0),
+            ))
+            .add(yanked(
+                "com.anatawa12.upgrade",
+                Version::new(1, 1, 0),
+                Some("broken build"),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.upgrade",
+                Version::new(1, 2, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.upgrade",
+                Version::new(1, 3, 0),
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.downgrade",
+                Version::new(1, 0, 0),
+            ))
+            .add(yanked(
+                "com.anatawa12.downgrade",
+                Version::new(1, 1, 0),
+                None,
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.downgrade",
+                Version::new(2, 0, 0),
+            ))
+            .add(yanked(
+                "com.anatawa12.no-replacement",
+                Version::new(1, 0, 0),
+                None,
+            ))
+            .add(PackageManifest::new(
+                "com.anatawa12.not-yanked",
+                Version::new(1, 0, 0),
+            ))
+            .build();
+
+        let mut yanked = project
+            .yanked_locked_packages(&collection, false)
+            .into_iter()
+            .map(|x| {
+                (
+                    x.name(),
+                    x.yank_reason(),
+                    x.replacement().map(|x| x.version().clone()),
+                )
+            })
+            .collect::<Vec<_>>();
+        yanked.sort();
+
+        assert_eq!(
+            yanked,
+            vec![
+                ("com.anatawa12.downgrade", None, Some(Version::new(1, 0, 0))),
+                ("com.anatawa12.no-replacement", None, None),
+                (
+                    "com.anatawa12.upgrade",
+                    Some("broken build"),
+                    Some(Version::new(1, 2, 0))
+                ),
+            ]
+        );
+    })
+}
@@ -4,6 +4,8 @@ use itertools::Itertools;
 
 use futures::future::join_all;
 use log::warn;
+use plan::{PlanArgs, print_plan_json};
+use progress::{InstallProgressDisplay, format_bytes};
 use reqwest::Url;
 use reqwest::header::{HeaderName, HeaderValue, InvalidHeaderName, InvalidHeaderValue};
 use serde::Serialize;
@@ -12,24 +14,30 @@ use std::env;
 use std::error::Error as StdError;
 use std::ffi::OsStr;
 use std::fmt::{Debug, Display};
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::str::FromStr;
+use std::time::SystemTime;
 use tokio::fs::read_to_string;
+use vrc_get_vpm::advisory::AdvisoryDatabase;
 use vrc_get_vpm::environment::{
-    AddRepositoryErr, AddUserPackageResult, PackageCollection, PackageInstaller, Settings,
-    UserPackageCollection, add_remote_repo, cleanup_repos_folder, clear_package_cache,
+    AddRepositoryErr, AddUserPackageResult, CachedPackageHash, HashMismatchPolicy,
+    PackageCachePrunePolicy, PackageCollection, PackageInstaller, Settings, UserPackageCollection,
+    add_remote_repo, cleanup_repos_folder, clear_package_cache, gc_package_store,
+    package_cache_entries, remove_cached_package,
 };
 use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo, IoTrait};
 use vrc_get_vpm::repositories_file::RepositoriesFile;
-use vrc_get_vpm::repository::RemoteRepository;
+use vrc_get_vpm::repository::{RemoteRepository, RepositoryBuilder, RepositoryCheck};
 use vrc_get_vpm::unity_project::pending_project_changes::{PackageChange, RemoveReason};
-use vrc_get_vpm::unity_project::{AddPackageOperation, PendingProjectChanges};
+use vrc_get_vpm::unity_project::{
+    AddPackageOperation, PendingProjectChanges, ResolverMode, YankedLockedPackage,
+};
 use vrc_get_vpm::version::Version;
 use vrc_get_vpm::{
-    PackageCollection as _, PackageInfo, PackageManifest, UnityProject, UserRepoSetting,
-    VersionSelector,
+    ManifestIssueSeverity, PackageCollection as _, PackageInfo, PackageManifest, RetryPolicy,
+    UnityProject, UserRepoSetting, VersionSelector,
 };
 
 macro_rules! multi_command {
@@ -83,6 +91,19 @@ struct EnvArgs {
     /// do not update local repository cache.
     #[arg(long)]
     no_update: bool,
+    /// how to handle downloaded packages whose hash doesn't match the repository.
+    /// one of warn, error, or redownload-then-error. defaults to the vrc-get settings
+    #[arg(long, value_name = "POLICY")]
+    hash_mismatch_policy: Option<HashMismatchPolicy>,
+    /// the algorithm to resolve dependencies. greedy or backtracking. defaults to the vrc-get settings
+    #[arg(long, value_name = "MODE")]
+    resolver: Option<ResolverMode>,
+    /// the maximum number of packages downloaded at once. defaults to the vrc-get settings
+    #[arg(long, value_name = "COUNT")]
+    max_concurrent_downloads: Option<NonZeroUsize>,
+    /// the maximum number of attempts to download a package. defaults to the vrc-get settings
+    #[arg(long, value_name = "COUNT")]
+    download_attempts: Option<NonZeroU32>,
 }
 
 async fn load_collection(
@@ -106,6 +127,48 @@ async fn load_collection(
     collection
 }
 
+fn create_installer<'a>(
+    io: &'a DefaultEnvironmentIo,
+    http: Option<&'a reqwest::Client>,
+    settings: &Settings,
+    env_args: &EnvArgs,
+) -> PackageInstaller<'a, reqwest::Client> {
+    let policy = env_args
+        .hash_mismatch_policy
+        .unwrap_or_else(|| settings.hash_mismatch_policy());
+    let max_concurrency = env_args
+        .max_concurrent_downloads
+        .unwrap_or_else(|| settings.max_concurrent_downloads());
+    let download_attempts = env_args
+        .download_attempts
+        .or_else(|| settings.download_attempts());
+    let mut installer = PackageInstaller::new(io, http);
+    installer.set_hash_mismatch_policy(policy);
+    installer.set_max_concurrency(Some(max_concurrency));
+    installer.set_retry_policy(
+        download_attempts.map(|attempts| RetryPolicy::default().with_max_attempts(attempts)),
+    );
+    installer.set_use_package_store(settings.use_package_store());
+    installer
+}
+
+fn apply_resolver_mode(unity: &mut UnityProject, settings: &Settings, env_args: &EnvArgs) {
+    unity.set_resolver_mode(
+        env_args
+            .resolver
+            .unwrap_or_else(|| settings.resolver_mode()),
+    );
+}
+
+async fn load_advisories(
+    io: &DefaultEnvironmentIo,
+    collection: &PackageCollection,
+) -> AdvisoryDatabase {
+    AdvisoryDatabase::load(io, collection)
+        .await
+        .exit_context("loading advisories")
+}
+
 async fn load_unity(path: Option<Box<Path>>) -> UnityProject {
     let io = match path {
         None => {
@@ -189,7 +252,15 @@ fn confirm_prompt(msg: &str) -> bool {
     _impl(msg).unwrap_or(false)
 }
 
-fn print_prompt_install(changes: &PendingProjectChanges) {
+/// Prints the changes to be applied.
+///
+/// With `--dry-run` or `--json`, this exits the process after printing the changes.
+fn print_prompt_install(changes: &PendingProjectChanges, plan: &PlanArgs) {
+    if plan.is_json() {
+        print_plan_json(changes);
+        exit(0);
+    }
+
     if changes.package_changes().is_empty() {
         exit_with!("nothing to do")
     }
@@ -272,6 +343,13 @@ fn print_prompt_install(changes: &PendingProjectChanges) {
                 }
             }
         }
+
+        if let Some(conflict) = changes.resolution_conflict() {
+            println!("No combination of versions avoids the conflicts because:");
+            for step in conflict.steps() {
+                println!("- {step}");
+            }
+        }
     }
 
     // process unity conflicts
@@ -306,6 +384,11 @@ fn print_prompt_install(changes: &PendingProjectChanges) {
             }
         }
     }
+
+    if plan.is_dry_run() {
+        println!("--dry-run is set. not applying changes");
+        exit(0);
+    }
 }
 
 fn prompt_install(yes: bool) {
@@ -371,8 +454,12 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
     }
 }
 
+mod bundle;
 mod info;
 mod migrate;
+mod pack;
+mod plan;
+mod progress;
 #[cfg(feature = "experimental-vcc")]
 mod vcc;
 
@@ -388,9 +475,16 @@ pub enum Command {
     Reinstall(Reinstall),
     Update(Update),
     Outdated(Outdated),
+    Audit(Audit),
+    ScanLegacy(ScanLegacy),
     Upgrade(Upgrade),
     Downgrade(Downgrade),
+    History(History),
+    Undo(Undo),
     Search(Search),
+    Why(Why),
+    Pack(pack::Pack),
+    LintPackage(pack::LintPackage),
     #[command(subcommand)]
     Repo(Repo),
     #[command(subcommand)]
@@ -401,6 +495,8 @@ pub enum Command {
     Migrate(migrate::Migrate),
     #[command(subcommand)]
     Cache(Cache),
+    #[command(subcommand)]
+    Bundle(bundle::Bundle),
     #[cfg(feature = "experimental-vcc")]
     #[command(subcommand)]
     Vcc(vcc::Vcc),
@@ -418,14 +514,22 @@ multi_command!(Command is
     Reinstall,
     Update,
     Outdated,
+    Audit,
+    ScanLegacy,
     Upgrade,
     Downgrade,
+    History,
+    Undo,
     Search,
+    Why,
+    Pack,
+    LintPackage,
     Repo,
     UserPackage,
     Info,
     Migrate,
     Cache,
+    Bundle,
     Vcc,
     Completion,
 );
@@ -453,11 +557,18 @@ pub struct Install {
     #[arg(long = "name", short = 'n')]
     name: bool,
 
+    /// Install exactly the versions in the locked section without changing them.
+    /// Only available without package id.
+    #[arg(long, conflicts_with = "id")]
+    frozen: bool,
+
     /// Path to project dir. by default CWD or parents of CWD will be used
     #[arg(short = 'p', long = "project")]
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
@@ -471,6 +582,9 @@ impl Install {
             return Resolve {
                 project: self.project,
                 env_args: self.env_args,
+                plan: self.plan,
+                frozen: self.frozen,
+                refresh_hashes: false,
             }
             .run()
             .await;
@@ -479,8 +593,11 @@ impl Install {
         let client = crate::create_client(self.env_args.offline);
         let io = DefaultEnvironmentIo::new_default();
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
-        let installer = PackageInstaller::new(&io, client.as_ref());
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
         let mut unity = load_unity(self.project).await;
+        apply_resolver_mode(&mut unity, &settings, &self.env_args);
+        unity.set_advisories(load_advisories(&io, &collection).await);
 
         let version_selector = match self.version {
             None => VersionSelector::latest_for(Some(unity.unity_version()), self.prerelease),
@@ -523,14 +640,18 @@ impl Install {
             .await
             .exit_context("collecting packages to be installed");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan);
 
         if require_prompt_for_install(&changes, name.as_str(), None) {
             prompt_install(self.yes);
         }
 
         unity
-            .apply_pending_changes(&installer, changes)
+            .apply_pending_changes_with_progress(
+                &installer,
+                changes,
+                &InstallProgressDisplay::new(),
+            )
             .await
             .exit_context("adding package");
 
@@ -550,6 +671,20 @@ pub struct Resolve {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
+
+    /// Install exactly the versions in the locked section without changing them.
+    ///
+    /// Fails if the dependencies are not consistent with the locked section.
+    #[arg(long)]
+    frozen: bool,
+
+    /// Update the zip hashes and repositories recorded in the locked section before installing.
+    ///
+    /// Use this option when a repository re-published a package with the same version.
+    #[arg(long)]
+    refresh_hashes: bool,
 }
 
 impl Resolve {
@@ -557,19 +692,44 @@ impl Resolve {
         let client = crate::create_client(self.env_args.offline);
         let io = DefaultEnvironmentIo::new_default();
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+        let settings = Settings::load(&io).await.exit_context("loading settings");
         let mut unity = load_unity(self.project).await;
+        apply_resolver_mode(&mut unity, &settings, &self.env_args);
+        unity.set_advisories(load_advisories(&io, &collection).await);
+
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
+
+        if self.refresh_hashes {
+            let mut downloaded = Vec::new();
+            for package in unity.find_locked_packages(&collection) {
+                // the recorded hash is not used since it's what to be refreshed
+                let (_, hash) = (installer.download_package(package, None).await)
+                    .exit_context(&format!("downloading {}", package.name()));
+                downloaded.push((package, hash));
+            }
+            for name in unity.refresh_locked_hashes(&downloaded) {
+                println!("updated recorded hash of {name}");
+            }
+            if !self.plan.is_dry_run() {
+                unity.save().await.exit_context("saving manifest");
+            }
+        }
 
-        let installer = PackageInstaller::new(&io, client.as_ref());
-
-        let changes = unity
-            .resolve_request(&collection)
-            .await
-            .exit_context("collecting packages to be installed");
+        let changes = if self.frozen {
+            unity.resolve_request_frozen(&collection).await
+        } else {
+            unity.resolve_request(&collection).await
+        }
+        .exit_context("collecting packages to be installed");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan);
 
         unity
-            .apply_pending_changes(&installer, changes)
+            .apply_pending_changes_with_progress(
+                &installer,
+                changes,
+                &InstallProgressDisplay::new(),
+            )
             .await
             .exit_context("installing packages");
     }
@@ -588,6 +748,8 @@ pub struct Remove {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
@@ -605,7 +767,7 @@ impl Remove {
             .exit_context("collecting packages to be removed");
         let installer = PackageInstaller::new(&io, None::<&reqwest::Client>);
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan);
 
         let confirm =
             changes.package_changes().len() >= self.names.len() || !changes.conflicts().is_empty();
@@ -636,6 +798,8 @@ pub struct Reinstall {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
@@ -647,7 +811,8 @@ impl Reinstall {
         let io = DefaultEnvironmentIo::new_default();
         let client = crate::create_client(self.env_args.offline);
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
-        let installer = PackageInstaller::new(&io, client.as_ref());
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
 
         let mut unity = load_unity(self.project).await;
 
@@ -658,7 +823,7 @@ impl Reinstall {
             .await
             .exit_context("collecting packages to be removed");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan);
 
         let confirm =
             changes.package_changes().len() >= self.names.len() || !changes.conflicts().is_empty();
@@ -740,6 +905,8 @@ impl Outdated {
             }
         }
 
+        print_yanked_warnings(&unity.yanked_locked_packages(&collection, self.prerelease));
+
         match self.json_format.map(|x| x.get()).unwrap_or(0) {
             0 => {
                 for (name, (found, installed)) in &outdated_packages {
@@ -771,6 +938,188 @@ impl Outdated {
     }
 }
 
+/// Check the packages in the project with security advisories
+///
+/// The advisories are loaded from vrc-get/advisories.json in the vpm data folder
+/// and the repositories providing advisories.
+/// Exits with a non-zero code if any package matches an advisory.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Audit {
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+
+    /// With this option, output is printed in json format
+    #[arg(long = "json-format")]
+    json_format: Option<NonZeroU32>,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl Audit {
+    pub async fn run(self) {
+        let client = crate::create_client(self.env_args.offline);
+        let io = DefaultEnvironmentIo::new_default();
+        let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+        let unity = load_unity(self.project).await;
+        let advisories = load_advisories(&io, &collection).await;
+
+        if advisories.is_empty() {
+            warn!(
+                "no advisories are known. add vrc-get/advisories.json or repositories providing advisories"
+            );
+        }
+
+        let matches = advisories.audit(&unity);
+
+        match self.json_format.map(|x| x.get()).unwrap_or(0) {
+            0 => {
+                for advisory_match in &matches {
+                    println!("{advisory_match}");
+                }
+                if matches.is_empty() {
+                    println!("no packages match advisories");
+                } else {
+                    println!("{} advisory match(es) found", matches.len());
+                }
+            }
+            1 => {
+                #[derive(Serialize)]
+                struct AdvisoryInfo<'a> {
+                    package_name: &'a str,
+                    version: &'a Version,
+                    id: &'a str,
+                    severity: String,
+                    description: &'a str,
+                    url: Option<&'a Url>,
+                }
+                let info = (matches.iter())
+                    .map(|x| AdvisoryInfo {
+                        package_name: x.package(),
+                        version: x.version(),
+                        id: x.advisory().id(),
+                        severity: x.advisory().severity().to_string(),
+                        description: x.advisory().description(),
+                        url: x.advisory().url(),
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", serde_json::to_string(&info).unwrap());
+            }
+            v => exit_with!("unsupported json version: {v}"),
+        }
+
+        if !matches.is_empty() {
+            exit(1);
+        }
+    }
+}
+
+/// Show the folders and files in the project declared as legacy by packages
+///
+/// Every package in the known repositories is checked, and the packages
+/// which would replace the legacy folders and files are shown.
+/// This doesn't change the project.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct ScanLegacy {
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+    /// Include prerelease
+    #[arg(long = "prerelease")]
+    prerelease: bool,
+
+    /// With this option, output is printed in json format
+    #[arg(long = "json-format")]
+    json_format: Option<NonZeroU32>,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl ScanLegacy {
+    pub async fn run(self) {
+        let client = crate::create_client(self.env_args.offline);
+        let io = DefaultEnvironmentIo::new_default();
+        let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+        let unity = load_unity(self.project).await;
+
+        let assets = unity.scan_legacy_assets(&collection, self.prerelease).await;
+
+        match self.json_format.map(|x| x.get()).unwrap_or(0) {
+            0 => {
+                for asset in &assets {
+                    let separator = if asset.is_file() { "" } else { "/" };
+                    let packages = (asset.packages().iter())
+                        .map(|x| {
+                            let installed = if unity.get_locked(x.name()).is_some() {
+                                " (installed)"
+                            } else {
+                                ""
+                            };
+                            format!("{}@{}{installed}", x.name(), x.version())
+                        })
+                        .join(", ");
+                    println!(
+                        "{}{separator}: replaced by {packages}",
+                        asset.path().display()
+                    );
+                }
+                if assets.is_empty() {
+                    println!("no legacy folders or files found");
+                }
+            }
+            1 => {
+                #[derive(Serialize)]
+                struct AssetInfo<'a> {
+                    path: String,
+                    is_file: bool,
+                    packages: Vec<ReplacingPackage<'a>>,
+                }
+                #[derive(Serialize)]
+                struct ReplacingPackage<'a> {
+                    name: &'a str,
+                    version: &'a Version,
+                    installed: bool,
+                }
+                let info = (assets.iter())
+                    .map(|x| AssetInfo {
+                        path: x.path().to_string_lossy().into_owned(),
+                        is_file: x.is_file(),
+                        packages: (x.packages().iter())
+                            .map(|x| ReplacingPackage {
+                                name: x.name(),
+                                version: x.version(),
+                                installed: unity.get_locked(x.name()).is_some(),
+                            })
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", serde_json::to_string(&info).unwrap());
+            }
+            v => exit_with!("unsupported json version: {v}"),
+        }
+    }
+}
+
+fn print_yanked_warnings(yanked: &[YankedLockedPackage]) {
+    for package in yanked {
+        match package.yank_reason() {
+            Some(reason) => warn!(
+                "{} version {} is yanked: {reason}",
+                package.name(),
+                package.version()
+            ),
+            None => warn!("{} version {} is yanked", package.name(), package.version()),
+        }
+    }
+    if !yanked.is_empty() {
+        warn!("run `vrc-get upgrade --away-from-yanked` to move to non-yanked versions");
+    }
+}
+
 /// Upgrade specified package or all packages to latest or specified version.
 ///
 /// With install command, you'll add to dependencies. With upgrade command,
@@ -787,12 +1136,18 @@ pub struct Upgrade {
     /// Include prerelease
     #[arg(long = "prerelease")]
     prerelease: bool,
+    /// Move every locked package whose version is yanked to the nearest non-yanked version
+    /// satisfying the ranges on the package, instead of upgrading to the latest versions
+    #[arg(long, conflicts_with_all = ["name", "VERSION"])]
+    away_from_yanked: bool,
 
     /// Path to project dir. by default CWD or parents of CWD will be used
     #[arg(short = 'p', long = "project")]
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
@@ -804,8 +1159,17 @@ impl Upgrade {
         let io = DefaultEnvironmentIo::new_default();
         let client = crate::create_client(self.env_args.offline);
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
-        let installer = PackageInstaller::new(&io, client.as_ref());
-        let mut unity = load_unity(self.project).await;
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
+        let mut unity = load_unity(self.project.clone()).await;
+        apply_resolver_mode(&mut unity, &settings, &self.env_args);
+        unity.set_advisories(load_advisories(&io, &collection).await);
+
+        if self.away_from_yanked {
+            return self
+                .away_from_yanked(&io, &collection, &installer, unity)
+                .await;
+        }
 
         let updates = if let Some(name) = &self.name {
             let version_selector = match self.version {
@@ -835,7 +1199,7 @@ impl Upgrade {
             .await
             .exit_context("collecting packages to be upgraded");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan);
 
         let require_prompt = if let Some(name) = &self.name {
             require_prompt_for_install(&changes, name.as_str(), None)
@@ -854,7 +1218,11 @@ impl Upgrade {
             .collect::<Vec<_>>();
 
         unity
-            .apply_pending_changes(&installer, changes)
+            .apply_pending_changes_with_progress(
+                &installer,
+                changes,
+                &InstallProgressDisplay::new(),
+            )
             .await
             .exit_context("upgrading packages");
 
@@ -866,6 +1234,79 @@ impl Upgrade {
     }
 }
 
+impl Upgrade {
+    async fn away_from_yanked(
+        &self,
+        io: &DefaultEnvironmentIo,
+        collection: &PackageCollection,
+        installer: &PackageInstaller<'_, reqwest::Client>,
+        mut unity: UnityProject,
+    ) {
+        let yanked = unity.yanked_locked_packages(collection, self.prerelease);
+        if yanked.is_empty() {
+            println!("no locked packages are yanked");
+            return;
+        }
+
+        let mut updates = Vec::new();
+        for package in &yanked {
+            let reason = (package.yank_reason())
+                .map(|x| format!(": {x}"))
+                .unwrap_or_default();
+            match package.replacement() {
+                Some(replacement) => {
+                    println!(
+                        "{} version {} is yanked{reason}, moving to {}",
+                        package.name(),
+                        package.version(),
+                        replacement.version()
+                    );
+                    updates.push(replacement);
+                }
+                None => warn!(
+                    "{} version {} is yanked{reason}, but no other version satisfies the requirements",
+                    package.name(),
+                    package.version()
+                ),
+            }
+        }
+
+        if updates.is_empty() {
+            exit_with!("no yanked packages can be moved to other versions");
+        }
+
+        let changes = unity
+            .add_package_request(
+                collection,
+                &updates,
+                AddPackageOperation::AutoDetected,
+                self.prerelease,
+            )
+            .await
+            .exit_context("collecting packages to be upgraded");
+
+        print_prompt_install(&changes, &self.plan);
+        prompt_install(self.yes);
+
+        let updates = (changes.package_changes().iter())
+            .filter_map(|(_, x)| x.as_install())
+            .filter_map(|x| x.install_package())
+            .map(|x| (x.name().to_owned(), x.version().clone()))
+            .collect::<Vec<_>>();
+
+        unity
+            .apply_pending_changes_with_progress(installer, changes, &InstallProgressDisplay::new())
+            .await
+            .exit_context("upgrading packages");
+
+        for (name, version) in updates {
+            println!("moved {name} to {version}");
+        }
+
+        update_project_last_modified(io, unity.project_dir()).await;
+    }
+}
+
 /// Downgrade the specified package specified version.
 ///
 /// With install command, you'll add to dependencies. With upgrade command,
@@ -888,6 +1329,8 @@ pub struct Downgrade {
     project: Option<Box<Path>>,
     #[command(flatten)]
     env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
 
     /// skip confirm
     #[arg(short, long)]
@@ -899,8 +1342,11 @@ impl Downgrade {
         let client = crate::create_client(self.env_args.offline);
         let io = DefaultEnvironmentIo::new_default();
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
-        let installer = PackageInstaller::new(&io, client.as_ref());
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
         let mut unity = load_unity(self.project).await;
+        apply_resolver_mode(&mut unity, &settings, &self.env_args);
+        unity.set_advisories(load_advisories(&io, &collection).await);
 
         let updates = [get_package(
             &collection,
@@ -918,7 +1364,7 @@ impl Downgrade {
             .await
             .exit_context("collecting packages to be upgraded");
 
-        print_prompt_install(&changes);
+        print_prompt_install(&changes, &self.plan);
 
         if require_prompt_for_install(&changes, self.name.as_str(), None) {
             prompt_install(self.yes)
@@ -931,7 +1377,11 @@ impl Downgrade {
             .collect::<Vec<_>>();
 
         unity
-            .apply_pending_changes(&installer, changes)
+            .apply_pending_changes_with_progress(
+                &installer,
+                changes,
+                &InstallProgressDisplay::new(),
+            )
             .await
             .exit_context("upgrading packages");
 
@@ -943,6 +1393,142 @@ impl Downgrade {
     }
 }
 
+/// Show the history of package operations on the project
+///
+/// The history is recorded in `.vrc-get/history` in the project
+/// and each entry can be restored with the undo command.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct History {
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+}
+
+impl History {
+    pub async fn run(self) {
+        let unity = load_unity(self.project).await;
+
+        let history = unity.history().await.exit_context("loading history");
+
+        if history.is_empty() {
+            println!("no history recorded");
+            return;
+        }
+
+        for entry in history {
+            println!("{} ({})", entry.id(), format_elapsed(entry.created_at()));
+            for change in entry.changes() {
+                match (change.before(), change.after()) {
+                    (Some(before), Some(after)) => {
+                        println!("- {}: {before} -> {after}", change.name())
+                    }
+                    (Some(before), None) => println!("- {}: removed {before}", change.name()),
+                    (None, Some(after)) => println!("- {}: installed {after}", change.name()),
+                    (None, None) => {}
+                }
+            }
+            if entry.changes().is_empty() {
+                println!("- dependencies changed");
+            }
+        }
+    }
+}
+
+fn format_elapsed(time: SystemTime) -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs();
+    match elapsed {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} minutes ago", elapsed / 60),
+        3600..86400 => format!("{} hours ago", elapsed / 3600),
+        _ => format!("{} days ago", elapsed / 86400),
+    }
+}
+
+/// Restore the project to the state before the last or specified operation
+///
+/// The undo itself is recorded in the history, so running undo again reverts the undo.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Undo {
+    /// The id of the history entry to restore. by default the last operation will be reverted
+    #[arg()]
+    id: Option<String>,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+    #[command(flatten)]
+    env_args: EnvArgs,
+    #[command(flatten)]
+    plan: PlanArgs,
+
+    /// skip confirm
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Undo {
+    pub async fn run(self) {
+        let client = crate::create_client(self.env_args.offline);
+        let io = DefaultEnvironmentIo::new_default();
+        let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
+        let mut unity = load_unity(self.project).await;
+
+        let entry = match &self.id {
+            Some(id) => unity
+                .get_history(id)
+                .await
+                .exit_context("loading history")
+                .unwrap_or_else(|| exit_with!("history entry {id} not found")),
+            None => (unity.history().await.exit_context("loading history"))
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| exit_with!("no history to undo")),
+        };
+
+        let changes = unity
+            .restore_history_request(&collection, &entry)
+            .await
+            .exit_context("collecting packages to be restored");
+
+        if self.plan.is_json() {
+            print_plan_json(&changes);
+            exit(0);
+        }
+
+        println!("Restoring the project to the state before {}", entry.id());
+
+        if changes.package_changes().is_empty() {
+            println!("No packages will be changed. Only dependencies will be restored.");
+            if self.plan.is_dry_run() {
+                println!("--dry-run is set. not applying changes");
+                exit(0);
+            }
+        } else {
+            print_prompt_install(&changes, &self.plan);
+        }
+
+        prompt_install(self.yes);
+
+        unity
+            .apply_pending_changes_with_progress(
+                &installer,
+                changes,
+                &InstallProgressDisplay::new(),
+            )
+            .await
+            .exit_context("restoring packages");
+
+        update_project_last_modified(&io, unity.project_dir()).await;
+    }
+}
+
 /// Search package by the query
 ///
 /// Search for packages that includes query in either name, displayName, or description.
@@ -1007,6 +1593,66 @@ impl Search {
     }
 }
 
+/// Show why the package is installed in the project
+///
+/// Prints every path from the dependencies of the project to the package.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Why {
+    /// Name of Package
+    #[arg()]
+    name: String,
+
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+}
+
+impl Why {
+    pub async fn run(self) {
+        let unity = load_unity(self.project).await;
+
+        match unity.get_locked(&self.name) {
+            Some(locked) => println!("{} version {} is locked", self.name, locked.version()),
+            None => {
+                if unity.unlocked_packages().iter().any(|(dir, pkg)| {
+                    dir.as_ref() == self.name || pkg.as_ref().map(|x| x.name()) == Some(&self.name)
+                }) {
+                    println!("{} is installed in Packages but not locked", self.name);
+                } else {
+                    exit_with!("{} is not installed in the project", self.name);
+                }
+            }
+        }
+
+        let paths = unity.dependency_paths(&self.name);
+        if paths.is_empty() {
+            println!(
+                "{} is not required by any dependency of the project",
+                self.name
+            );
+            return;
+        }
+
+        for path in paths {
+            let mut line = String::from("project");
+            for edge in path.edges() {
+                if let Some(range) = edge.range() {
+                    line.push_str(&format!(" -> {} {range}", edge.dependency()));
+                } else {
+                    line.push_str(&format!(" -> {} (legacy replacement)", edge.dependency()));
+                }
+                if edge.dependency() != self.name
+                    && let Some(locked) = unity.get_locked(edge.dependency())
+                {
+                    line.push_str(&format!(" [{}]", locked.version()));
+                }
+            }
+            println!("{line}");
+        }
+    }
+}
+
 /// Commands around repositories
 #[derive(Subcommand)]
 #[command(author, version)]
@@ -1018,9 +1664,11 @@ pub enum Repo {
     Packages(RepoPackages),
     Import(RepoImport),
     Export(RepoExport),
+    Build(Box<RepoBuild>),
+    Check(RepoCheck),
 }
 
-multi_command!(Repo is List, Add, Remove, Cleanup, Packages, Import, Export);
+multi_command!(Repo is List, Add, Remove, Cleanup, Packages, Import, Export, Build, Check);
 
 /// List all repositories
 #[derive(Parser)]
@@ -1434,6 +2082,200 @@ impl RepoExport {
     }
 }
 
+/// Check the index of a repository for problems
+///
+/// Every problem in the index is reported with the JSON pointer to the value,
+/// including package versions VCC and vrc-get skip when loading the repository.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoCheck {
+    /// URL or path of the repository index
+    #[arg()]
+    path_or_url: String,
+
+    /// Headers to download the index and zip files
+    #[arg(short='H', long, value_parser = HeaderPair::from_str)]
+    header: Vec<HeaderPair>,
+
+    /// Download the zip file of every package version and check zipSHA256
+    #[arg(long)]
+    verify_hashes: bool,
+
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl RepoCheck {
+    pub async fn run(self) {
+        use futures::AsyncReadExt;
+        use vrc_get_vpm::HttpClient;
+
+        let client = crate::create_client(self.env_args.offline);
+        let io = DefaultEnvironmentIo::new_default();
+
+        let mut headers = IndexMap::<Box<str>, Box<str>>::new();
+        for HeaderPair(name, value) in self.header {
+            headers.insert(name.as_str().into(), value.to_str().unwrap().into());
+        }
+
+        let json = if let Ok(url) = Url::parse(&self.path_or_url) {
+            let Some(client) = &client else {
+                exit_with!("offline mode");
+            };
+            let request_headers =
+                IndexMap::from_iter(headers.iter().map(|(k, v)| (k.as_ref(), v.as_ref())));
+            let (stream, _) = (HttpClient::get(client, &url, &request_headers).await)
+                .exit_context("downloading repository index");
+            let mut json = Vec::new();
+            std::pin::pin!(stream)
+                .read_to_end(&mut json)
+                .await
+                .exit_context("downloading repository index");
+            json
+        } else {
+            tokio::fs::read(absolute_path(&self.path_or_url))
+                .await
+                .exit_context("reading repository index")
+        };
+
+        let mut check = RepositoryCheck::new(&json);
+
+        let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+        check.check_dependencies(&collection);
+
+        if self.verify_hashes {
+            let Some(client) = &client else {
+                exit_with!("offline mode");
+            };
+            check
+                .check_zip_hashes(client, &headers, |package, result| match result {
+                    Ok(_) => println!(
+                        "downloaded {} version {}",
+                        package.name(),
+                        package.version()
+                    ),
+                    Err(e) => warn!(
+                        "failed to download {} version {}: {e}",
+                        package.name(),
+                        package.version()
+                    ),
+                })
+                .await;
+        }
+
+        for issue in check.issues() {
+            println!("{issue}");
+        }
+
+        let count = |severity| {
+            (check.issues().iter())
+                .filter(|x| x.severity() == severity)
+                .count()
+        };
+        println!(
+            "checked {} package version(s): {} error(s), {} warning(s)",
+            check.versions().count(),
+            count(ManifestIssueSeverity::Error),
+            count(ManifestIssueSeverity::Warning)
+        );
+
+        if check.has_errors() {
+            exit(1);
+        }
+    }
+}
+
+/// Build the index of a repository from a folder of packages
+///
+/// Each folder with package.json in SOURCE is packed to a zip file, and each zip file in SOURCE is added as is.
+/// Versions in the existing index are kept, so the index can be regenerated when a new version is added.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct RepoBuild {
+    /// The folder with package folders and package zip files
+    source: PathBuf,
+    /// The url the zip files are hosted at. The url of each package is <BASE_URL>/<zip file name>
+    #[arg(long, value_name = "BASE_URL")]
+    base_url: Url,
+    /// Path to the repository index to write. The versions in the existing index are kept
+    #[arg(short, long, default_value = "index.json")]
+    output: PathBuf,
+    /// The folder to place the zip files. by default, the folder of the index
+    #[arg(long, value_name = "DIR")]
+    zip_dir: Option<PathBuf>,
+    /// The id of the repository
+    #[arg(long)]
+    id: Option<String>,
+    /// The name of the repository
+    #[arg(long)]
+    name: Option<String>,
+    /// The url of the repository index. by default, <BASE_URL>/<file name of the index>
+    #[arg(long)]
+    url: Option<Url>,
+}
+
+impl RepoBuild {
+    pub async fn run(self) {
+        let output = absolute_path(&self.output);
+        let zip_dir = match self.zip_dir {
+            Some(zip_dir) => absolute_path(zip_dir),
+            None => output.parent().unwrap().to_owned(),
+        };
+
+        let mut builder = RepositoryBuilder::new(self.base_url.clone(), zip_dir);
+
+        match tokio::fs::read(&output).await {
+            Ok(json) => builder
+                .load_existing(&json, &output)
+                .exit_context("loading existing repository index"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => exit_with!("reading existing repository index: {e}"),
+        }
+
+        if let Some(id) = &self.id {
+            builder.set_id(id);
+        }
+        if let Some(name) = &self.name {
+            builder.set_name(name);
+        }
+        if let Some(url) = &self.url {
+            builder.set_url(url);
+        } else if !builder.has_url() {
+            let file_name = output.file_name().unwrap().to_string_lossy();
+            let mut base_url = self.base_url;
+            if !base_url.path().ends_with('/') {
+                base_url.set_path(&format!("{}/", base_url.path()));
+            }
+            match base_url.join(&file_name) {
+                Ok(url) => builder.set_url(&url),
+                Err(e) => exit_with!("creating url of the repository: {e}"),
+            }
+        }
+
+        builder
+            .add_all_in(&self.source)
+            .await
+            .exit_context("adding packages");
+
+        let json = builder
+            .to_json()
+            .exit_context("serializing repository index");
+        tokio::fs::write(&output, json)
+            .await
+            .exit_context("writing repository index");
+
+        for (name, version) in builder.added_packages() {
+            println!("added {name} version {version}");
+        }
+        println!(
+            "wrote {} with {} package version(s) from {}",
+            self.output.display(),
+            builder.added_packages().len(),
+            self.source.display()
+        );
+    }
+}
+
 /// Commands around user packages
 #[derive(Subcommand)]
 #[command(author, version)]
@@ -1524,9 +2366,12 @@ impl UserPackageRemove {
 #[command(author, version)]
 pub enum Cache {
     Clear(CacheClear),
+    Gc(CacheGc),
+    List(CacheList),
+    Prune(CachePrune),
 }
 
-multi_command!(Cache is Clear);
+multi_command!(Cache is Clear, Gc, List, Prune);
 
 /// Cleanup package cache
 #[derive(Parser)]
@@ -1545,6 +2390,188 @@ impl CacheClear {
     }
 }
 
+/// Remove packages in the package store not used by any known projects
+///
+/// The package store is used if `usePackageStore` is enabled in `vrc-get/settings.json`.
+/// Projects listed in the settings are known projects.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct CacheGc {
+    /// Additional projects using the package store
+    #[arg(long = "project", value_name = "PATH")]
+    projects: Vec<PathBuf>,
+}
+
+impl CacheGc {
+    pub async fn run(self) {
+        let io = DefaultEnvironmentIo::new_default();
+        let projects = known_projects(&io, self.projects).await;
+
+        let removed = gc_package_store(&io, &projects)
+            .await
+            .exit_context("cleaning package store");
+
+        for entry in &removed {
+            println!("removed {} version {}", entry.name(), entry.version());
+        }
+        println!(
+            "removed {} package(s) from the package store",
+            removed.len()
+        );
+    }
+}
+
+/// List packages in the package cache
+///
+/// Shows the size, whether the zip file matches the recorded hash,
+/// and whether any known project locks the version of the package.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct CacheList {
+    /// Additional projects to check whether the packages are used
+    #[arg(long = "project", value_name = "PATH")]
+    projects: Vec<PathBuf>,
+}
+
+impl CacheList {
+    pub async fn run(self) {
+        let io = DefaultEnvironmentIo::new_default();
+        let projects = known_projects(&io, self.projects).await;
+
+        let mut entries = package_cache_entries(&io, &projects)
+            .await
+            .exit_context("reading package cache");
+        entries.sort_by(|a, b| a.name().cmp(b.name()).then(b.version().cmp(a.version())));
+
+        let name_width = entries.iter().map(|x| x.name().len()).max().unwrap_or(0);
+        let version_width = (entries.iter())
+            .map(|x| x.version().to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut total = 0;
+        for entry in &entries {
+            total += entry.size();
+            let hash = match entry.hash() {
+                CachedPackageHash::Valid => "valid",
+                CachedPackageHash::Mismatch => "hash mismatch",
+                CachedPackageHash::Missing => "hash missing",
+            };
+            let used = if entry.used() { "used" } else { "unused" };
+            println!(
+                "{name:name_width$}  {version:version_width$}  {size:>10}  {used:6}  {hash}",
+                name = entry.name(),
+                version = entry.version().to_string(),
+                size = format_bytes(entry.size()),
+            );
+        }
+        println!(
+            "{} package(s), {} in total",
+            entries.len(),
+            format_bytes(total)
+        );
+    }
+}
+
+/// Remove packages in the package cache matching all the conditions
+///
+/// At least one condition is required.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct CachePrune {
+    /// Keep the latest COUNT versions of each package
+    #[arg(long, value_name = "COUNT")]
+    keep_latest: Option<usize>,
+    /// Remove packages downloaded more than DAYS days ago
+    #[arg(long, value_name = "DAYS")]
+    older_than: Option<u64>,
+    /// Remove packages not locked by any known project
+    #[arg(long)]
+    unused: bool,
+    /// Additional projects to check whether the packages are used
+    #[arg(long = "project", value_name = "PATH")]
+    projects: Vec<PathBuf>,
+    /// Show packages to remove without removing them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl CachePrune {
+    pub async fn run(self) {
+        if self.keep_latest.is_none() && self.older_than.is_none() && !self.unused {
+            exit_with!("specify at least one of --keep-latest, --older-than, or --unused");
+        }
+
+        let io = DefaultEnvironmentIo::new_default();
+        let projects = known_projects(&io, self.projects).await;
+
+        let entries = package_cache_entries(&io, &projects)
+            .await
+            .exit_context("reading package cache");
+
+        let mut policy = PackageCachePrunePolicy::new();
+        policy.set_keep_latest(self.keep_latest);
+        policy.set_older_than(
+            self.older_than
+                .map(|days| std::time::Duration::from_secs(days * 24 * 60 * 60)),
+        );
+        policy.set_unused_only(self.unused);
+
+        let targets = policy.select(&entries, SystemTime::now());
+
+        let mut total = 0;
+        for entry in &targets {
+            total += entry.size();
+            if self.dry_run {
+                println!("would remove {} version {}", entry.name(), entry.version());
+            } else {
+                remove_cached_package(&io, entry)
+                    .await
+                    .exit_context("removing cached package");
+                println!("removed {} version {}", entry.name(), entry.version());
+            }
+        }
+
+        if self.dry_run {
+            println!(
+                "would remove {} package(s), {}",
+                targets.len(),
+                format_bytes(total)
+            );
+        } else {
+            println!(
+                "removed {} package(s), {}",
+                targets.len(),
+                format_bytes(total)
+            );
+        }
+    }
+}
+
+/// Returns `extra` and the projects registered in the settings or VCC.
+async fn known_projects(io: &DefaultEnvironmentIo, extra: Vec<PathBuf>) -> Vec<PathBuf> {
+    let settings = Settings::load(io).await.exit_context("loading settings");
+
+    let mut projects = extra;
+    projects.extend(
+        (settings.user_projects().unwrap_or_default().iter()).map(|x| PathBuf::from(x.as_ref())),
+    );
+
+    #[cfg(feature = "experimental-vcc")]
+    {
+        let connection = vrc_get_vpm::environment::VccDatabaseConnection::connect(io)
+            .await
+            .exit_context("connecting to database");
+        projects.extend(
+            (connection.get_projects().iter())
+                .filter_map(|x| x.path())
+                .map(PathBuf::from),
+        );
+    }
+
+    projects
+}
+
 #[derive(Parser)]
 pub struct Completion {
     shell: Option<clap_complete::Shell>,
@@ -0,0 +1,117 @@
+use crate::commands::{
+    EnvArgs, ResultExt, absolute_path, create_installer, load_collection, load_unity,
+};
+use clap::{Parser, Subcommand};
+use log::warn;
+use std::path::{Path, PathBuf};
+use vrc_get_vpm::environment::{Settings, export_bundle, import_bundle};
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+
+/// Commands about offline bundles of packages
+///
+/// A bundle contains the packages locked in projects and the repositories providing them,
+/// so the projects can be resolved on machines without network.
+#[derive(Subcommand)]
+#[command(author, version)]
+pub enum Bundle {
+    Export(Export),
+    Import(Import),
+}
+
+multi_command!(Bundle is Export, Import);
+
+/// Export the packages locked in projects to a bundle
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Export {
+    /// Path to the bundle file to write
+    #[arg()]
+    output: PathBuf,
+    /// Path to project dir. can be specified multiple times. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    projects: Vec<Box<Path>>,
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl Export {
+    pub async fn run(self) {
+        let client = crate::create_client(self.env_args.offline);
+        let io = DefaultEnvironmentIo::new_default();
+        let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
+
+        let mut projects = Vec::new();
+        if self.projects.is_empty() {
+            projects.push(load_unity(None).await);
+        } else {
+            for project in self.projects {
+                projects.push(load_unity(Some(project)).await);
+            }
+        }
+
+        let result = export_bundle(
+            &installer,
+            &collection,
+            &projects,
+            &absolute_path(&self.output),
+        )
+        .await
+        .exit_context("exporting bundle");
+
+        for package in result.not_found() {
+            warn!(
+                "{} version {} is not found in any remote repository and not included in the bundle",
+                package.name(),
+                package.version()
+            );
+        }
+        for package in result.packages() {
+            println!("exported {} version {}", package.name(), package.version());
+        }
+        println!(
+            "exported {} package(s) from {} repository(s) to {}",
+            result.packages().len(),
+            result.repositories().len(),
+            self.output.display()
+        );
+    }
+}
+
+/// Import packages and repositories in a bundle to the package cache
+///
+/// Repositories in the bundle not added yet are added to the repository list.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Import {
+    /// Path to the bundle file to import
+    #[arg()]
+    bundle: PathBuf,
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl Import {
+    pub async fn run(self) {
+        let io = DefaultEnvironmentIo::new_default();
+        let mut settings = Settings::load(&io).await.exit_context("loading settings");
+
+        let result = import_bundle(&mut settings, &io, &absolute_path(&self.bundle))
+            .await
+            .exit_context("importing bundle");
+
+        settings.save(&io).await.exit_context("saving settings");
+
+        for url in result.added_repositories() {
+            println!("added repository {url}");
+        }
+        for url in result.updated_repositories() {
+            println!("updated repository cache of {url}");
+        }
+        println!(
+            "imported {} package(s) to the package cache",
+            result.packages().len()
+        );
+    }
+}
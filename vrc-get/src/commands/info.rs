@@ -1,13 +1,15 @@
 use super::{UnityProject, load_collection};
 use crate::commands::load_unity;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Write as _};
 use std::num::NonZeroU32;
 use std::path::Path;
 use vrc_get_vpm::PackageCollection;
 use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::unity_project::YankedLockedPackage;
 use vrc_get_vpm::version::{UnityVersion, Version, VersionRange};
 
 /// Shows information for other program.
@@ -24,6 +26,7 @@ multi_command!(Info is Project, Package);
 ///
 /// Without --json-format, this will emit human readable information
 /// With --json-format, this will emit machine-readable information with json
+/// With --tree or --format, this will emit the dependency graph of the project
 #[derive(Parser)]
 #[command(author, version)]
 pub struct Project {
@@ -34,24 +37,71 @@ pub struct Project {
     /// Output json format
     #[arg(long = "json-format")]
     json_format: Option<NonZeroU32>,
+
+    /// Show the dependency graph of the project as a tree
+    #[arg(long, conflicts_with_all = ["json_format", "format"])]
+    tree: bool,
+
+    /// Output the dependency graph of the project in the specified format
+    #[arg(long, conflicts_with = "json_format")]
+    format: Option<GraphFormat>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT language
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+impl Display for GraphFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphFormat::Dot => f.write_str("dot"),
+            GraphFormat::Mermaid => f.write_str("mermaid"),
+        }
+    }
 }
 
 impl Project {
     pub async fn run(self) {
         let unity = load_unity(self.project).await;
 
+        if self.tree {
+            DependencyGraph::new(&unity).print_tree(&unity);
+            return;
+        }
+
+        match self.format {
+            Some(GraphFormat::Dot) => {
+                print!("{}", DependencyGraph::new(&unity).dot());
+                return;
+            }
+            Some(GraphFormat::Mermaid) => {
+                print!("{}", DependencyGraph::new(&unity).mermaid());
+                return;
+            }
+            None => {}
+        }
+
+        // yank states are from the cached repositories to keep this command offline
+        let io = DefaultEnvironmentIo::new_default();
+        let collection = load_collection(&io, None, true).await;
+        let yanked = unity.yanked_locked_packages(&collection, false);
+
         match self.json_format.map(|x| x.get()).unwrap_or_default() {
             0 => {
-                Self::human_readable(&unity).await;
+                Self::human_readable(&unity, &yanked).await;
             }
             1 => {
-                Self::version1(&unity).await;
+                Self::version1(&unity, &yanked).await;
             }
             unsupported => exit_with!("unsupported json version: {unsupported}"),
         };
     }
 
-    pub async fn human_readable(unity: &UnityProject) {
+    pub async fn human_readable(unity: &UnityProject, yanked: &[YankedLockedPackage<'_>]) {
         eprintln!("Project at {}", unity.project_dir().display());
         eprintln!("Using unity {}", unity.unity_version());
         eprintln!();
@@ -74,6 +124,15 @@ impl Project {
                     version = locked.version(),
                 );
             }
+            if let Some(yanked) = yanked.iter().find(|x| x.name() == locked.name()) {
+                match yanked.yank_reason() {
+                    Some(reason) => eprintln!(
+                        "  warning: version {} is yanked: {reason}",
+                        yanked.version()
+                    ),
+                    None => eprintln!("  warning: version {} is yanked", yanked.version()),
+                }
+            }
         }
 
         eprintln!();
@@ -89,7 +148,7 @@ impl Project {
         }
     }
 
-    pub async fn version1(unity: &UnityProject) {
+    pub async fn version1(unity: &UnityProject, yanked: &[YankedLockedPackage<'_>]) {
         #[derive(Serialize)]
         struct Project<'a> {
             unity_version: Option<UnityVersion>,
@@ -102,11 +161,15 @@ impl Project {
             installed: Option<&'a Version>,
             locked: Option<&'a Version>,
             requested: Vec<&'a VersionRange>,
+            /// true if the locked version is yanked
+            yanked: bool,
+            yank_reason: Option<&'a str>,
         }
 
         let mut packages = vec![];
 
         for locked in unity.locked_packages() {
+            let yanked = yanked.iter().find(|x| x.name() == locked.name());
             packages.push(PackageInfo {
                 name: locked.name(),
                 installed: unity
@@ -114,6 +177,8 @@ impl Project {
                     .map(|x| x.version()),
                 locked: Some(locked.version()),
                 requested: vec![], // TODO: add requests from locked packages
+                yanked: yanked.is_some(),
+                yank_reason: yanked.and_then(|x| x.yank_reason()),
             });
         }
 
@@ -124,6 +189,8 @@ impl Project {
                     installed: Some(installed.version()),
                     locked: None,
                     requested: vec![],
+                    yanked: false,
+                    yank_reason: None,
                 });
             }
         }
@@ -149,6 +216,8 @@ impl Project {
                 installed: None,
                 locked: None,
                 requested,
+                yanked: false,
+                yank_reason: None,
             });
         }
 
@@ -161,6 +230,358 @@ impl Project {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum NodeKind {
+    Project,
+    /// The package in the `locked` section of `vpm-manifest.json`
+    Locked,
+    /// The package installed in `Packages` but not locked
+    Unlocked,
+    /// The package in `Packages/manifest.json`
+    Upm,
+    /// The package required but neither locked nor installed
+    Missing,
+}
+
+struct GraphNode<'a> {
+    name: &'a str,
+    version: Option<String>,
+    kind: NodeKind,
+    /// The locked package not reachable from the dependencies of the project
+    orphaned: bool,
+    /// The locked or unlocked package also listed in `Packages/manifest.json`
+    duplicated: bool,
+}
+
+impl GraphNode<'_> {
+    fn label(&self) -> String {
+        let mut label = match &self.version {
+            Some(version) => format!("{} {version}", self.name),
+            None => self.name.to_owned(),
+        };
+        match self.kind {
+            NodeKind::Project | NodeKind::Locked => {}
+            NodeKind::Unlocked => label.push_str(" [unlocked]"),
+            NodeKind::Upm => label.push_str(" [upm]"),
+            NodeKind::Missing => label.push_str(" [missing]"),
+        }
+        if self.orphaned {
+            label.push_str(" [orphaned]");
+        }
+        if self.duplicated {
+            label.push_str(" [also in manifest.json]");
+        }
+        label
+    }
+}
+
+struct GraphEdge {
+    from: usize,
+    to: usize,
+    /// The version range requested. `None` for the packages in `Packages/manifest.json`
+    range: Option<String>,
+}
+
+/// The dependency graph of the project.
+///
+/// The node 0 is the project itself.
+struct DependencyGraph<'a> {
+    nodes: Vec<GraphNode<'a>>,
+    edges: Vec<GraphEdge>,
+    indices: HashMap<&'a str, usize>,
+}
+
+impl<'a> DependencyGraph<'a> {
+    fn new(unity: &'a UnityProject) -> Self {
+        let mut graph = DependencyGraph {
+            nodes: vec![GraphNode {
+                name: "project",
+                version: None,
+                kind: NodeKind::Project,
+                orphaned: false,
+                duplicated: false,
+            }],
+            edges: vec![],
+            indices: HashMap::new(),
+        };
+
+        for locked in unity.locked_packages() {
+            graph.add_node(
+                locked.name(),
+                Some(locked.version().to_string()),
+                NodeKind::Locked,
+            );
+        }
+
+        for (dir_name, installed) in unity.unlocked_packages() {
+            match installed {
+                Some(installed) if !graph.indices.contains_key(installed.name()) => {
+                    graph.add_node(
+                        installed.name(),
+                        Some(installed.version().to_string()),
+                        NodeKind::Unlocked,
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    graph.add_node(dir_name, None, NodeKind::Unlocked);
+                }
+            }
+        }
+
+        for (name, dependency) in unity.upm_dependencies() {
+            if let Some(&index) = graph.indices.get(name) {
+                graph.nodes[index].duplicated = true;
+            } else {
+                let index = graph.add_node(name, Some(dependency.to_string()), NodeKind::Upm);
+                graph.edges.push(GraphEdge {
+                    from: 0,
+                    to: index,
+                    range: None,
+                });
+            }
+        }
+
+        for name in unity.dependencies() {
+            let to = graph.get_or_add_missing(name);
+            let range = unity.get_dependency(name).map(|x| x.to_string());
+            graph.edges.push(GraphEdge { from: 0, to, range });
+        }
+
+        for locked in unity.locked_packages() {
+            let from = graph.indices[locked.name()];
+            for (dependency, range) in locked.dependencies() {
+                let to = graph.get_or_add_missing(dependency);
+                let range = Some(range.to_string());
+                graph.edges.push(GraphEdge { from, to, range });
+            }
+        }
+
+        for (_, installed) in unity.unlocked_packages() {
+            let Some(installed) = installed else { continue };
+            if unity.is_locked(installed.name()) {
+                continue;
+            }
+            let from = graph.indices[installed.name()];
+            for (dependency, range) in installed.vpm_dependencies() {
+                let to = graph.get_or_add_missing(dependency);
+                let range = Some(range.to_string());
+                graph.edges.push(GraphEdge { from, to, range });
+            }
+        }
+
+        let reachable = graph.reachable_from_project();
+        for (index, node) in graph.nodes.iter_mut().enumerate() {
+            node.orphaned = node.kind == NodeKind::Locked && !reachable.contains(&index);
+        }
+
+        graph
+    }
+
+    fn add_node(&mut self, name: &'a str, version: Option<String>, kind: NodeKind) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(GraphNode {
+            name,
+            version,
+            kind,
+            orphaned: false,
+            duplicated: false,
+        });
+        self.indices.insert(name, index);
+        index
+    }
+
+    fn get_or_add_missing(&mut self, name: &'a str) -> usize {
+        match self.indices.get(name) {
+            Some(&index) => index,
+            None => self.add_node(name, None, NodeKind::Missing),
+        }
+    }
+
+    fn children(&self, node: usize) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.iter().filter(move |edge| edge.from == node)
+    }
+
+    fn reachable_from_project(&self) -> HashSet<usize> {
+        let mut reachable = HashSet::from([0]);
+        let mut queue = vec![0];
+        while let Some(node) = queue.pop() {
+            for edge in self.children(node) {
+                if reachable.insert(edge.to) {
+                    queue.push(edge.to);
+                }
+            }
+        }
+        reachable
+    }
+
+    fn print_tree(&self, unity: &UnityProject) {
+        println!(
+            "{} (unity {})",
+            unity.project_dir().display(),
+            unity.unity_version()
+        );
+        print!("{}", self.tree());
+    }
+
+    /// The tree of the dependencies from the project, followed by the orphaned and unlocked packages.
+    fn tree(&self) -> String {
+        let mut out = String::new();
+        let mut expanded = HashSet::new();
+        self.write_subtree(&mut out, 0, "", &mut expanded);
+
+        let orphaned = (self.nodes.iter().enumerate())
+            .filter(|(_, node)| node.orphaned)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if !orphaned.is_empty() {
+            writeln!(out).unwrap();
+            writeln!(out, "Orphaned locked packages:").unwrap();
+            for index in orphaned {
+                self.write_root(&mut out, index, &mut expanded);
+            }
+        }
+
+        let unlocked = (self.nodes.iter().enumerate())
+            .filter(|(_, node)| node.kind == NodeKind::Unlocked)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if !unlocked.is_empty() {
+            writeln!(out).unwrap();
+            writeln!(out, "Not locked but installed packages:").unwrap();
+            for index in unlocked {
+                self.write_root(&mut out, index, &mut expanded);
+            }
+        }
+
+        out
+    }
+
+    fn write_root(&self, out: &mut String, node: usize, expanded: &mut HashSet<usize>) {
+        if expanded.contains(&node) {
+            writeln!(out, "{} (*)", self.nodes[node].label()).unwrap();
+        } else {
+            writeln!(out, "{}", self.nodes[node].label()).unwrap();
+            self.write_subtree(out, node, "", expanded);
+        }
+    }
+
+    fn write_subtree(
+        &self,
+        out: &mut String,
+        node: usize,
+        prefix: &str,
+        expanded: &mut HashSet<usize>,
+    ) {
+        expanded.insert(node);
+
+        let mut children = self.children(node).collect::<Vec<_>>();
+        children.sort_by_key(|edge| self.nodes[edge.to].name);
+
+        for (i, edge) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let child = &self.nodes[edge.to];
+            let range = match &edge.range {
+                Some(range) => format!(" (requires {range})"),
+                None => String::new(),
+            };
+
+            if expanded.contains(&edge.to) {
+                let more = if self.children(edge.to).next().is_some() {
+                    " (*)"
+                } else {
+                    ""
+                };
+                writeln!(out, "{prefix}{branch}{}{range}{more}", child.label()).unwrap();
+            } else {
+                writeln!(out, "{prefix}{branch}{}{range}", child.label()).unwrap();
+                self.write_subtree(out, edge.to, &format!("{prefix}{indent}"), expanded);
+            }
+        }
+    }
+
+    fn dot(&self) -> String {
+        fn quote(s: &str) -> String {
+            format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+
+        let mut out = String::new();
+        writeln!(out, "digraph project {{").unwrap();
+        writeln!(out, "    node [shape=box];").unwrap();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut attributes = vec![format!("label={}", quote(&node.label()))];
+            match node.kind {
+                NodeKind::Project => attributes.push("shape=doubleoctagon".into()),
+                NodeKind::Locked => {}
+                NodeKind::Unlocked => attributes.push("style=dashed".into()),
+                NodeKind::Upm => attributes.push("shape=ellipse".into()),
+                NodeKind::Missing => attributes.push("color=red".into()),
+            }
+            if node.orphaned || node.duplicated {
+                attributes.push("color=orange".into());
+            }
+            writeln!(out, "    n{index} [{}];", attributes.join(", ")).unwrap();
+        }
+        for edge in &self.edges {
+            match &edge.range {
+                Some(range) => writeln!(
+                    out,
+                    "    n{} -> n{} [label={}];",
+                    edge.from,
+                    edge.to,
+                    quote(range)
+                ),
+                None => writeln!(out, "    n{} -> n{} [style=dotted];", edge.from, edge.to),
+            }
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    fn mermaid(&self) -> String {
+        fn quote(s: &str) -> String {
+            format!("\"{}\"", s.replace('"', "#quot;"))
+        }
+
+        let mut out = String::new();
+        writeln!(out, "graph TD").unwrap();
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(out, "    n{index}[{}]", quote(&node.label())).unwrap();
+        }
+        for edge in &self.edges {
+            match &edge.range {
+                Some(range) => {
+                    writeln!(out, "    n{} -->|{}| n{}", edge.from, quote(range), edge.to)
+                }
+                None => writeln!(out, "    n{} -.-> n{}", edge.from, edge.to),
+            }
+            .unwrap();
+        }
+
+        writeln!(out, "    classDef unlocked stroke-dasharray: 5 5").unwrap();
+        writeln!(out, "    classDef upm fill:#eee").unwrap();
+        writeln!(out, "    classDef missing stroke:#f00").unwrap();
+        writeln!(out, "    classDef warning stroke:#f90").unwrap();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let class = match node.kind {
+                _ if node.orphaned || node.duplicated => "warning",
+                NodeKind::Project | NodeKind::Locked => continue,
+                NodeKind::Unlocked => "unlocked",
+                NodeKind::Upm => "upm",
+                NodeKind::Missing => "missing",
+            };
+            writeln!(out, "    class n{index} {class}").unwrap();
+        }
+        out
+    }
+}
+
 /// Show project information
 #[derive(Parser)]
 #[command(author, version)]
@@ -219,3 +640,126 @@ impl Package {
         println!("{}", serde_json::to_string(&package_info).unwrap());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vrc_get_vpm::io::DefaultProjectIo;
+
+    /// Loads the project with a shared dependency, a missing dependency, and an orphaned package.
+    fn load_project(name: &str) -> UnityProject {
+        let path = std::env::temp_dir().join(format!("vrc-get-test-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(path.join("Packages")).unwrap();
+        std::fs::create_dir_all(path.join("ProjectSettings")).unwrap();
+        std::fs::write(
+            path.join("ProjectSettings/ProjectVersion.txt"),
+            "m_EditorVersion: 2022.3.22f1\n",
+        )
+        .unwrap();
+        std::fs::write(
+            path.join("Packages/vpm-manifest.json"),
+            r#"{
+                "dependencies": {
+                    "com.anatawa12.package": { "version": "1.0.0" },
+                    "com.anatawa12.other": { "version": "1.0.0" }
+                },
+                "locked": {
+                    "com.anatawa12.package": {
+                        "version": "1.0.0",
+                        "dependencies": {
+                            "com.anatawa12.shared": "^1.0.0",
+                            "com.anatawa12.missing": "^1.0.0"
+                        }
+                    },
+                    "com.anatawa12.other": {
+                        "version": "1.0.0",
+                        "dependencies": { "com.anatawa12.shared": "^1.0.0" }
+                    },
+                    "com.anatawa12.shared": { "version": "1.0.0" },
+                    "com.anatawa12.orphaned": { "version": "1.0.0" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let project = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(UnityProject::load(DefaultProjectIo::new(
+                path.clone().into(),
+            )))
+            .unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+        project
+    }
+
+    #[test]
+    fn dependency_tree() {
+        let project = load_project("dependency-tree");
+        assert_eq!(
+            DependencyGraph::new(&project).tree(),
+            "\
+├── com.anatawa12.other 1.0.0 (requires 1.0.0)
+│   └── com.anatawa12.shared 1.0.0 (requires ^1.0.0)
+└── com.anatawa12.package 1.0.0 (requires 1.0.0)
+    ├── com.anatawa12.missing [missing] (requires ^1.0.0)
+    └── com.anatawa12.shared 1.0.0 (requires ^1.0.0)
+
+Orphaned locked packages:
+com.anatawa12.orphaned 1.0.0 [orphaned]
+"
+        );
+    }
+
+    #[test]
+    fn dependency_graph_dot() {
+        let project = load_project("dependency-graph-dot");
+        assert_eq!(
+            DependencyGraph::new(&project).dot(),
+            r#"digraph project {
+    node [shape=box];
+    n0 [label="project", shape=doubleoctagon];
+    n1 [label="com.anatawa12.package 1.0.0"];
+    n2 [label="com.anatawa12.other 1.0.0"];
+    n3 [label="com.anatawa12.shared 1.0.0"];
+    n4 [label="com.anatawa12.orphaned 1.0.0 [orphaned]", color=orange];
+    n5 [label="com.anatawa12.missing [missing]", color=red];
+    n0 -> n1 [label="1.0.0"];
+    n0 -> n2 [label="1.0.0"];
+    n1 -> n3 [label="^1.0.0"];
+    n1 -> n5 [label="^1.0.0"];
+    n2 -> n3 [label="^1.0.0"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn dependency_graph_mermaid() {
+        let project = load_project("dependency-graph-mermaid");
+        assert_eq!(
+            DependencyGraph::new(&project).mermaid(),
+            r#"graph TD
+    n0["project"]
+    n1["com.anatawa12.package 1.0.0"]
+    n2["com.anatawa12.other 1.0.0"]
+    n3["com.anatawa12.shared 1.0.0"]
+    n4["com.anatawa12.orphaned 1.0.0 [orphaned]"]
+    n5["com.anatawa12.missing [missing]"]
+    n0 -->|"1.0.0"| n1
+    n0 -->|"1.0.0"| n2
+    n1 -->|"^1.0.0"| n3
+    n1 -->|"^1.0.0"| n5
+    n2 -->|"^1.0.0"| n3
+    classDef unlocked stroke-dasharray: 5 5
+    classDef upm fill:#eee
+    classDef missing stroke:#f00
+    classDef warning stroke:#f90
+    class n4 warning
+    class n5 missing
+"#
+        );
+    }
+}
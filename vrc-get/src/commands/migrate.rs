@@ -1,23 +1,32 @@
 use crate::commands::{
-    EnvArgs, ResultExt, confirm_prompt, load_collection, load_unity, update_project_last_modified,
+    EnvArgs, ResultExt, apply_resolver_mode, confirm_prompt, create_installer, load_advisories,
+    load_collection, load_unity, update_project_last_modified,
 };
 use clap::{Parser, Subcommand};
+use itertools::Itertools;
 use log::info;
+use serde::Serialize;
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use tokio::process::Command;
-use vrc_get_vpm::environment::PackageInstaller;
+use vrc_get_vpm::environment::Settings;
 use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::unity_project::{MigrationIssueReason, UnityMigrationTarget};
+use vrc_get_vpm::version::{UnityVersion, Version};
+use vrc_get_vpm::{ProjectType, VRCHAT_RECOMMENDED_2022_UNITY};
 
 /// Migrate Unity Project
 #[derive(Subcommand)]
 #[command(author, version)]
 pub enum Migrate {
     Unity2022(Unity2022),
+    Unity(Unity),
+    CheckUnity(CheckUnity),
     Vpm(Vpm),
 }
 
-multi_command!(Migrate is Unity2022, Vpm);
+multi_command!(Migrate is Unity2022, Unity, CheckUnity, Vpm);
 
 /// Migrate your project to Unity 2022
 #[derive(Parser)]
@@ -39,81 +48,255 @@ pub struct Unity2022 {
 
 impl Unity2022 {
     pub async fn run(self) {
-        println!("You're migrating your project to Unity 2022 in-place.");
-        println!("It's hard to undo this command.");
-        println!("You MUST create backup of your project before running this command.");
-        if !confirm_prompt("Do you want to continue?") {
-            exit(1);
-        }
+        migrate_unity(
+            VRCHAT_RECOMMENDED_2022_UNITY,
+            self.project,
+            self.unity,
+            self.env_args,
+        )
+        .await
+    }
+}
 
-        let mut project = load_unity(self.project).await;
+/// Migrate your project to the specified Unity version
+///
+/// The packages are upgraded based on the requirements of the target Unity version,
+/// and the migration fails if some packages are not compatible with the target.
+#[derive(Parser)]
+pub struct Unity {
+    /// The Unity version to migrate to. e.g. 2022.3.22f1
+    #[arg(value_parser = parse_unity_version)]
+    target: UnityVersion,
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+    #[cfg(not(feature = "experimental-vcc"))]
+    /// Path to the unity executable of the target version.
+    #[arg(long)]
+    unity: PathBuf,
+    #[cfg(feature = "experimental-vcc")]
+    /// Path to the unity executable of the target version.
+    #[arg(long)]
+    unity: Option<PathBuf>,
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl Unity {
+    pub async fn run(self) {
+        migrate_unity(self.target, self.project, self.unity, self.env_args).await
+    }
+}
 
+/// Show the packages to be changed to migrate your project to the specified Unity version
+///
+/// This doesn't change the project.
+/// Exits with a non-zero code if some packages prevent the migration.
+#[derive(Parser)]
+pub struct CheckUnity {
+    /// The Unity version to migrate to. e.g. 2022.3.22f1
+    #[arg(value_parser = parse_unity_version)]
+    target: UnityVersion,
+    /// Path to project dir. by default CWD or parents of CWD will be used
+    #[arg(short = 'p', long = "project")]
+    project: Option<Box<Path>>,
+    /// With this option, output is printed in json format
+    #[arg(long = "json-format")]
+    json_format: Option<NonZeroU32>,
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl CheckUnity {
+    pub async fn run(self) {
         let client = crate::create_client(self.env_args.offline);
         let io = DefaultEnvironmentIo::new_default();
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
-        let installer = PackageInstaller::new(&io, client.as_ref());
+        let project = load_unity(self.project).await;
 
-        #[cfg(feature = "experimental-vcc")]
-        let connection = vrc_get_vpm::environment::VccDatabaseConnection::connect(&io)
-            .await
-            .exit_context("connecting to database");
-
-        project
-            .migrate_unity_2022(&collection, &installer)
-            .await
-            .exit_context("migrating unity project");
+        let issues = project
+            .check_unity_migration(self.target, &collection)
+            .exit_context("checking migration");
 
-        info!(
-            "Updating manifest file finished successfully. Launching Unity to finalize migration..."
-        );
-
-        #[cfg(not(feature = "experimental-vcc"))]
-        let unity = self.unity;
-
-        #[cfg(feature = "experimental-vcc")]
-        let unity = match self.unity {
-            Some(path) => path,
-            None => {
-                use vrc_get_vpm::VRCHAT_RECOMMENDED_2022_UNITY;
-                let Some(found) =
-                    connection.find_most_suitable_unity(VRCHAT_RECOMMENDED_2022_UNITY)
-                else {
-                    exit_with!(
-                        "Unity 2022 not found. please load from unity hub with `vrc-get vcc unity update` or specify path with `--unity` option."
-                    )
-                };
-
-                if found.version() != Some(VRCHAT_RECOMMENDED_2022_UNITY) {
-                    // since we know it's unity 2022, we can safely unwrap
-                    log::warn!(
-                        "Recommended Unity 2022 version is not found. Using found version: {}",
-                        found.version().unwrap()
+        match self.json_format.map(|x| x.get()).unwrap_or(0) {
+            0 => {
+                let (upgraded, manual): (Vec<_>, Vec<_>) =
+                    issues.iter().partition(|x| x.upgraded_by_migration());
+                if !upgraded.is_empty() {
+                    println!("Packages upgraded by migration:");
+                    for issue in &upgraded {
+                        println!("- {issue}");
+                    }
+                }
+                if !manual.is_empty() {
+                    println!("Packages to be upgraded or removed before migration:");
+                    for issue in &manual {
+                        let locked = if issue.is_locked() {
+                            ""
+                        } else {
+                            " [not locked]"
+                        };
+                        println!("- {issue}{locked}");
+                    }
+                }
+                if issues.is_empty() {
+                    println!(
+                        "no packages need to be changed to migrate to Unity {}",
+                        self.target
                     );
                 }
+            }
+            1 => {
+                #[derive(Serialize)]
+                struct IssueInfo<'a> {
+                    package_name: &'a str,
+                    version: &'a Version,
+                    locked: bool,
+                    reason: &'static str,
+                    description: String,
+                    minimum_version: Option<&'a Version>,
+                    minimum_unity: Option<String>,
+                    replaced_with: Option<&'a str>,
+                    proposed_version: Option<&'a Version>,
+                    upgraded_by_migration: bool,
+                    blocks_migration: bool,
+                }
+                let info = (issues.iter())
+                    .map(|x| {
+                        let (reason, minimum_version, minimum_unity, replaced_with) =
+                            match x.reason() {
+                                MigrationIssueReason::BelowMinimum(minimum) => {
+                                    ("below-minimum", Some(minimum), None, None)
+                                }
+                                MigrationIssueReason::RequiresNewerUnity((major, minor)) => (
+                                    "requires-newer-unity",
+                                    None,
+                                    Some(format!("{major}.{minor}")),
+                                    None,
+                                ),
+                                MigrationIssueReason::OnlyForUnity2019 => {
+                                    ("only-for-unity-2019", None, None, None)
+                                }
+                                MigrationIssueReason::ReplacedWith(name) => {
+                                    ("replaced", None, None, Some(name.as_ref()))
+                                }
+                                _other => ("other", None, None, None),
+                            };
+                        IssueInfo {
+                            package_name: x.name(),
+                            version: x.version(),
+                            locked: x.is_locked(),
+                            reason,
+                            description: x.reason().to_string(),
+                            minimum_version,
+                            minimum_unity,
+                            replaced_with,
+                            proposed_version: x.proposed_version(),
+                            upgraded_by_migration: x.upgraded_by_migration(),
+                            blocks_migration: x.blocks_migration(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", serde_json::to_string(&info).unwrap());
+            }
+            v => exit_with!("unsupported json version: {v}"),
+        }
+
+        if issues.iter().any(|x| x.blocks_migration()) {
+            exit(1);
+        }
+    }
+}
+
+fn parse_unity_version(version: &str) -> Result<UnityVersion, String> {
+    UnityVersion::parse(version).ok_or_else(|| format!("invalid unity version: {version}"))
+}
+
+async fn migrate_unity(
+    target: UnityVersion,
+    project: Option<Box<Path>>,
+    #[cfg(not(feature = "experimental-vcc"))] unity: PathBuf,
+    #[cfg(feature = "experimental-vcc")] unity: Option<PathBuf>,
+    env_args: EnvArgs,
+) {
+    if UnityMigrationTarget::find(target).is_none() {
+        let supported = (UnityMigrationTarget::all().iter())
+            .map(|x| format!("{}.{}", x.unity().0, x.unity().1))
+            .join(", ");
+        exit_with!("migrating to Unity {target} is not supported. supported versions: {supported}");
+    }
+
+    println!("You're migrating your project to Unity {target} in-place.");
+    println!("It's hard to undo this command.");
+    println!("You MUST create backup of your project before running this command.");
+    if !confirm_prompt("Do you want to continue?") {
+        exit(1);
+    }
 
-                PathBuf::from(found.path().unwrap())
+    let mut project = load_unity(project).await;
+
+    let client = crate::create_client(env_args.offline);
+    let io = DefaultEnvironmentIo::new_default();
+    let collection = load_collection(&io, client.as_ref(), env_args.no_update).await;
+    let settings = Settings::load(&io).await.exit_context("loading settings");
+    let installer = create_installer(&io, client.as_ref(), &settings, &env_args);
+    apply_resolver_mode(&mut project, &settings, &env_args);
+    project.set_advisories(load_advisories(&io, &collection).await);
+
+    #[cfg(feature = "experimental-vcc")]
+    let connection = vrc_get_vpm::environment::VccDatabaseConnection::connect(&io)
+        .await
+        .exit_context("connecting to database");
+
+    project
+        .migrate_unity(target, &collection, &installer)
+        .await
+        .exit_context("migrating unity project");
+
+    info!("Updating manifest file finished successfully. Launching Unity to finalize migration...");
+
+    #[cfg(feature = "experimental-vcc")]
+    let unity = match unity {
+        Some(path) => path,
+        None => {
+            let Some(found) = connection.find_most_suitable_unity(target) else {
+                exit_with!(
+                    "Unity {}.{} not found. please load from unity hub with `vrc-get vcc unity update` or specify path with `--unity` option.",
+                    target.major(),
+                    target.minor()
+                )
+            };
+
+            if found.version() != Some(target) {
+                // since it is found by the version, we can safely unwrap
+                log::warn!(
+                    "Unity {target} is not found. Using found version: {}",
+                    found.version().unwrap()
+                );
             }
-        };
-
-        let status = Command::new(&unity)
-            .args([
-                "-quit".as_ref(),
-                "-batchmode".as_ref(),
-                "-projectPath".as_ref(),
-                project.project_dir().as_os_str(),
-            ])
-            .status()
-            .await
-            .exit_context("launching unity to finalize migration");
 
-        if !status.success() {
-            exit_with!("Unity exited with status {}", status);
+            PathBuf::from(found.path().unwrap())
         }
+    };
 
-        info!("Unity exited successfully. Migration finished.");
+    let status = Command::new(&unity)
+        .args([
+            "-quit".as_ref(),
+            "-batchmode".as_ref(),
+            "-projectPath".as_ref(),
+            project.project_dir().as_os_str(),
+        ])
+        .status()
+        .await
+        .exit_context("launching unity to finalize migration");
 
-        update_project_last_modified(&io, project.project_dir()).await;
+    if !status.success() {
+        exit_with!("Unity exited with status {}", status);
     }
+
+    info!("Unity exited successfully. Migration finished.");
+
+    update_project_last_modified(&io, project.project_dir()).await;
 }
 
 /// Migrate your legacy (unitypackage) VRCSDK project to VPM project
@@ -140,10 +323,48 @@ impl Vpm {
         let client = crate::create_client(self.env_args.offline);
         let io = DefaultEnvironmentIo::new_default();
         let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
-        let installer = PackageInstaller::new(&io, client.as_ref());
+        let settings = Settings::load(&io).await.exit_context("loading settings");
+        let installer = create_installer(&io, client.as_ref(), &settings, &self.env_args);
+        apply_resolver_mode(&mut project, &settings, &self.env_args);
+        project.set_advisories(load_advisories(&io, &collection).await);
+
+        // check before asking for each package
+        let project_type = project.detect_project_type().await;
+        if !matches!(
+            project_type,
+            ProjectType::LegacyWorlds | ProjectType::LegacyAvatars
+        ) {
+            exit_with!("migrating {project_type} project to vpm is not supported");
+        }
+
+        // packages in any repository can replace the copies imported with unitypackage
+        let legacy_packages = project
+            .find_legacy_imported_packages(&collection, false)
+            .await;
+        let mut replacing = vec![];
+        for legacy in &legacy_packages {
+            let package = legacy.package();
+            println!(
+                "Found files imported from unitypackage of {} version {}:",
+                package.display_name().unwrap_or(package.name()),
+                package.version()
+            );
+            for folder in legacy.folders() {
+                println!("- {}/", folder.display());
+            }
+            for file in legacy.files() {
+                println!("- {}", file.display());
+            }
+            if confirm_prompt(&format!(
+                "Do you want to remove them and install {}?",
+                package.name()
+            )) {
+                replacing.push(package);
+            }
+        }
 
         project
-            .migrate_vpm(&collection, &installer, false)
+            .migrate_vpm_with_packages(&collection, &installer, false, &replacing)
             .await
             .exit_context("migrating unity project");
 
@@ -0,0 +1,137 @@
+use crate::commands::{EnvArgs, ResultExt, absolute_path, load_collection};
+use clap::Parser;
+use reqwest::Url;
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vrc_get_vpm::io::DefaultEnvironmentIo;
+use vrc_get_vpm::repository::pack_package;
+use vrc_get_vpm::{ManifestIssueSeverity, PackageManifestLint};
+
+/// Pack a package folder to a zip file for distribution
+///
+/// The package.json is validated, and junk files like .DS_Store are excluded.
+/// The zip file is deterministic, so packing the same folder results the same SHA-256.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct Pack {
+    /// Path to the package folder with package.json. e.g. Packages/com.anatawa12.example
+    #[arg()]
+    folder: PathBuf,
+    /// Path to the zip file to write. by default, <name>-<version>.zip in CWD
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// The url the zip file will be hosted at, used for the repository entry.
+    /// The url of the zip file is <BASE_URL>/<zip file name>
+    #[arg(long, value_name = "BASE_URL")]
+    base_url: Option<Url>,
+}
+
+impl Pack {
+    pub async fn run(self) {
+        let folder = absolute_path(&self.folder);
+        // we don't know the name and version until package.json is loaded
+        let output = match &self.output {
+            Some(output) => absolute_path(output),
+            None => absolute_path(temp_file_name()),
+        };
+
+        let packed = pack_package(&folder, &output)
+            .await
+            .exit_context("packing package");
+        let manifest = packed.manifest();
+
+        let file_name = format!("{}-{}.zip", manifest.name(), manifest.version());
+        let output = if self.output.is_none() {
+            let renamed = absolute_path(&file_name);
+            tokio::fs::rename(&output, &renamed)
+                .await
+                .exit_context("renaming zip file");
+            renamed
+        } else {
+            output
+        };
+
+        let url = self.base_url.map(|mut base_url| {
+            if !base_url.path().ends_with('/') {
+                base_url.set_path(&format!("{}/", base_url.path()));
+            }
+            base_url.join(&file_name).unwrap()
+        });
+
+        println!(
+            "packed {} version {} to {}",
+            manifest.name(),
+            manifest.version(),
+            output.display()
+        );
+        println!("SHA-256: {}", packed.sha256());
+        println!();
+        println!("repository entry:");
+        println!(
+            "\"{}\": {}",
+            manifest.version(),
+            serde_json::to_string_pretty(&packed.repository_entry(url.as_ref())).unwrap()
+        );
+        if url.is_none() {
+            println!();
+            println!("note: specify --base-url to include url of the zip file in the entry");
+        }
+    }
+}
+
+/// The name of the temporary zip file unique to this invocation
+fn temp_file_name() -> String {
+    let nanos = (SystemTime::now().duration_since(UNIX_EPOCH))
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+    format!(".vrc-get-pack-{}-{nanos}.zip.tmp", std::process::id())
+}
+
+/// Check package.json of a package for problems
+///
+/// Every value VCC and vrc-get cannot load is reported with the JSON pointer to the value,
+/// and dependencies are checked with the packages in the repositories.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct LintPackage {
+    /// Path to the package folder, package.json, or the zip file of the package
+    #[arg()]
+    path: PathBuf,
+    #[command(flatten)]
+    env_args: EnvArgs,
+}
+
+impl LintPackage {
+    pub async fn run(self) {
+        let mut lint = PackageManifestLint::load(&absolute_path(&self.path))
+            .await
+            .exit_context("loading package.json");
+
+        if lint.manifest().is_some() {
+            let client = crate::create_client(self.env_args.offline);
+            let io = DefaultEnvironmentIo::new_default();
+            let collection = load_collection(&io, client.as_ref(), self.env_args.no_update).await;
+            lint.check_dependencies(&collection);
+        }
+
+        for issue in lint.issues() {
+            println!("{}: {issue}", self.path.display());
+        }
+
+        let count = |severity| {
+            (lint.issues().iter())
+                .filter(|x| x.severity() == severity)
+                .count()
+        };
+        println!(
+            "{} error(s), {} warning(s)",
+            count(ManifestIssueSeverity::Error),
+            count(ManifestIssueSeverity::Warning)
+        );
+
+        if lint.has_errors() {
+            exit(1);
+        }
+    }
+}
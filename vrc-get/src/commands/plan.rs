@@ -0,0 +1,173 @@
+use crate::commands::ResultExt;
+use clap::Args;
+use serde::Serialize;
+use std::path::Path;
+use vrc_get_vpm::unity_project::PendingProjectChanges;
+use vrc_get_vpm::unity_project::pending_project_changes::{PackageChange, RemoveReason};
+use vrc_get_vpm::version::{DependencyRange, Version};
+
+/// The version of the json schema printed with `--json`.
+///
+/// Bump this when an incompatible change is made to the schema.
+/// Adding new fields is not an incompatible change.
+const PLAN_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Args, Default)]
+pub(super) struct PlanArgs {
+    /// show the changes without applying them
+    #[arg(long)]
+    dry_run: bool,
+    /// print the changes in json instead of applying them. implies --dry-run
+    #[arg(long)]
+    json: bool,
+}
+
+impl PlanArgs {
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run || self.json
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+}
+
+#[derive(Serialize)]
+struct Plan<'a> {
+    schema_version: u32,
+    install: Vec<InstallPlan<'a>>,
+    add_to_dependencies: Vec<AddToDependenciesPlan<'a>>,
+    remove: Vec<RemovePlan<'a>>,
+    remove_legacy_files: Vec<LegacyAssetPlan<'a>>,
+    remove_legacy_folders: Vec<LegacyAssetPlan<'a>>,
+    conflicts: Vec<ConflictPlan<'a>>,
+    /// The explanation of the conflicts from the backtracking resolver
+    resolution_conflict: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct InstallPlan<'a> {
+    name: &'a str,
+    version: &'a Version,
+    yanked: bool,
+    /// true if the package will be added to the locked section
+    adding_to_locked: bool,
+    source: PackageSource<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PackageSource<'a> {
+    Remote {
+        repository_id: Option<&'a str>,
+        repository_url: Option<&'a str>,
+        repository_name: Option<&'a str>,
+    },
+    Local,
+}
+
+#[derive(Serialize)]
+struct AddToDependenciesPlan<'a> {
+    name: &'a str,
+    range: &'a DependencyRange,
+}
+
+#[derive(Serialize)]
+struct RemovePlan<'a> {
+    name: &'a str,
+    reason: &'static str,
+}
+
+#[derive(Serialize)]
+struct LegacyAssetPlan<'a> {
+    path: &'a Path,
+    /// The package which declares the asset as legacy
+    package: &'a str,
+}
+
+#[derive(Serialize)]
+struct ConflictPlan<'a> {
+    package: &'a str,
+    conflicting_packages: &'a [Box<str>],
+    conflicts_with_unity: bool,
+    /// The directories in `Packages` that will be removed
+    unlocked_names: &'a [Box<str>],
+}
+
+pub(super) fn print_plan_json(changes: &PendingProjectChanges) {
+    let mut plan = Plan {
+        schema_version: PLAN_SCHEMA_VERSION,
+        install: vec![],
+        add_to_dependencies: vec![],
+        remove: vec![],
+        remove_legacy_files: legacy_assets(changes.remove_legacy_files()),
+        remove_legacy_folders: legacy_assets(changes.remove_legacy_folders()),
+        conflicts: vec![],
+        resolution_conflict: changes
+            .resolution_conflict()
+            .map(|conflict| conflict.steps().iter().map(ToString::to_string).collect()),
+    };
+
+    for (name, change) in changes.package_changes() {
+        match change {
+            PackageChange::Install(change) => {
+                if let Some(package) = change.install_package() {
+                    let source = match package.repo() {
+                        Some(repo) => PackageSource::Remote {
+                            repository_id: repo.id(),
+                            repository_url: repo.url().map(|x| x.as_str()),
+                            repository_name: repo.name(),
+                        },
+                        None => PackageSource::Local,
+                    };
+                    plan.install.push(InstallPlan {
+                        name: package.name(),
+                        version: package.version(),
+                        yanked: package.is_yanked(),
+                        adding_to_locked: change.is_adding_to_locked(),
+                        source,
+                    });
+                }
+                if let Some(range) = change.to_dependencies() {
+                    plan.add_to_dependencies
+                        .push(AddToDependenciesPlan { name, range });
+                }
+            }
+            PackageChange::Remove(change) => {
+                plan.remove.push(RemovePlan {
+                    name,
+                    reason: match change.reason() {
+                        RemoveReason::Requested => "requested",
+                        RemoveReason::Legacy => "legacy",
+                        RemoveReason::Unused => "unused",
+                    },
+                });
+            }
+        }
+    }
+
+    for (package, conflict) in changes.conflicts() {
+        plan.conflicts.push(ConflictPlan {
+            package,
+            conflicting_packages: conflict.conflicting_packages(),
+            conflicts_with_unity: conflict.conflicts_with_unity(),
+            unlocked_names: conflict.unlocked_names(),
+        });
+    }
+
+    // package_changes and conflicts are HashMap so sort to make the output stable
+    plan.install.sort_by_key(|x| x.name);
+    plan.add_to_dependencies.sort_by_key(|x| x.name);
+    plan.remove.sort_by_key(|x| x.name);
+    plan.conflicts.sort_by_key(|x| x.package);
+
+    let json = serde_json::to_string(&plan).exit_context("serializing the plan");
+    println!("{json}");
+}
+
+fn legacy_assets<'a>(assets: &'a [(Box<Path>, &'a str)]) -> Vec<LegacyAssetPlan<'a>> {
+    assets
+        .iter()
+        .map(|(path, package)| LegacyAssetPlan { path, package })
+        .collect()
+}
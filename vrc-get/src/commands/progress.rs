@@ -0,0 +1,139 @@
+use indexmap::IndexMap;
+use std::io::{IsTerminal, Write, stderr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use vrc_get_vpm::{InstallProgress, PackageInfo};
+
+/// The minimum interval to redraw the progress for downloading.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Shows the progress of installing packages with one line per package on stderr.
+///
+/// Nothing is shown if stderr is not a terminal.
+pub(super) struct InstallProgressDisplay {
+    enabled: bool,
+    state: Mutex<DisplayState>,
+}
+
+struct DisplayState {
+    packages: IndexMap<Box<str>, PackageState>,
+    /// The number of lines printed by the last redraw
+    printed_lines: usize,
+    last_redraw: Option<Instant>,
+}
+
+enum PackageState {
+    Downloading { downloaded: u64, total: Option<u64> },
+    Extracting { extracted: usize, total: usize },
+    Installed,
+}
+
+impl InstallProgressDisplay {
+    pub fn new() -> Self {
+        Self {
+            enabled: stderr().is_terminal(),
+            state: Mutex::new(DisplayState {
+                packages: IndexMap::new(),
+                printed_lines: 0,
+                last_redraw: None,
+            }),
+        }
+    }
+
+    fn update(&self, package: PackageInfo, new_state: PackageState, force_redraw: bool) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let key = format!("{}@{}", package.name(), package.version());
+        state.packages.insert(key.into(), new_state);
+
+        let now = Instant::now();
+        if !force_redraw
+            && (state.last_redraw).is_some_and(|last| now.duration_since(last) < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        state.last_redraw = Some(now);
+        state.redraw();
+    }
+}
+
+impl DisplayState {
+    fn redraw(&mut self) {
+        let mut stderr = stderr().lock();
+
+        // move the cursor to the first line of the last redraw
+        if self.printed_lines != 0 {
+            write!(stderr, "\x1b[{}A", self.printed_lines).ok();
+        }
+
+        for (package, state) in &self.packages {
+            write!(stderr, "\r\x1b[2K{package}: ").ok();
+            match *state {
+                PackageState::Downloading {
+                    downloaded,
+                    total: Some(total),
+                } if total != 0 => {
+                    let percent = downloaded * 100 / total;
+                    write!(
+                        stderr,
+                        "downloading {percent:>3}% ({} / {})",
+                        format_bytes(downloaded),
+                        format_bytes(total)
+                    )
+                    .ok();
+                }
+                PackageState::Downloading { downloaded, .. } => {
+                    write!(stderr, "downloading {}", format_bytes(downloaded)).ok();
+                }
+                PackageState::Extracting { extracted, total } => {
+                    write!(stderr, "extracting {extracted} / {total} files").ok();
+                }
+                PackageState::Installed => {
+                    write!(stderr, "done").ok();
+                }
+            }
+            writeln!(stderr).ok();
+        }
+
+        self.printed_lines = self.packages.len();
+        stderr.flush().ok();
+    }
+}
+
+impl InstallProgress for InstallProgressDisplay {
+    fn downloading(&self, package: PackageInfo<'_>, downloaded: u64, total: Option<u64>) {
+        let finished = total == Some(downloaded);
+        self.update(
+            package,
+            PackageState::Downloading { downloaded, total },
+            finished,
+        );
+    }
+
+    fn extracting(&self, package: PackageInfo<'_>, extracted: usize, total: usize) {
+        self.update(
+            package,
+            PackageState::Extracting { extracted, total },
+            extracted == total,
+        );
+    }
+
+    fn installed(&self, package: PackageInfo<'_>) {
+        self.update(package, PackageState::Installed, true);
+    }
+}
+
+pub(super) fn format_bytes(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{bytes} B")
+    }
+}
@@ -3,7 +3,7 @@ use clap::{Parser, Subcommand, ValueEnum};
 use log::warn;
 use std::cmp::Reverse;
 use std::fmt::{Display, Formatter};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use vrc_get_vpm::environment::{Settings, VccDatabaseConnection, find_unity_hub};
 use vrc_get_vpm::io::{DefaultEnvironmentIo, DefaultProjectIo};
 use vrc_get_vpm::{UnityProject, unity_hub};
@@ -183,9 +183,10 @@ pub enum Unity {
     Add(UnityAdd),
     Remove(UnityRemove),
     Update(UnityUpdate),
+    Scan(UnityScan),
 }
 
-multi_command!(Unity is List, Add, Remove, Update);
+multi_command!(Unity is List, Add, Remove, Update, Scan);
 
 /// List registered Unity installations
 #[derive(Parser)]
@@ -317,10 +318,10 @@ impl UnityUpdate {
 
         let unity_hub_path = find_unity_hub(&mut settings, &io)
             .await
-            .exit_context("loading unity hub path")
-            .unwrap_or_else(|| exit_with!("Unity Hub not found"));
+            .exit_context("loading unity hub path");
 
         let unity_list = match self.method {
+            // config files of Unity Hub are read only if exists, so Unity Hub is not required
             UnityHubAccessMethod::ReadConfig => unity_hub::load_unity_by_loading_unity_hub_files()
                 .await
                 .exit_context("loading list of unity from config file")
@@ -328,6 +329,8 @@ impl UnityUpdate {
                 .map(|x| (x.version, x.path))
                 .collect::<Vec<_>>(),
             UnityHubAccessMethod::CallHub => {
+                let unity_hub_path =
+                    unity_hub_path.unwrap_or_else(|| exit_with!("Unity Hub not found"));
                 unity_hub::load_unity_by_calling_unity_hub(unity_hub_path.as_ref())
                     .await
                     .exit_context("loading unity list from unity hub")
@@ -342,7 +345,80 @@ impl UnityUpdate {
             .await
             .exit_context("updating unity from unity hub");
 
+        add_unity_from_search_paths(&mut connection, settings.unity_search_paths()).await;
+
         connection.save(&io).await.exit_context("saving database");
         settings.save(&io).await.exit_context("saving settings");
     }
 }
+
+/// Find Unity installations in the folders and add them to the list.
+///
+/// The folders are searched a few levels deep for Unity not installed with Unity Hub,
+/// and the version is read from the files of Unity without launching it.
+/// If no folder is specified, `unitySearchPaths` in the settings of vrc-get is used.
+#[derive(Parser)]
+#[command(author, version)]
+pub struct UnityScan {
+    #[command(flatten)]
+    env_args: super::EnvArgs,
+    /// The folders to search for Unity installations
+    paths: Vec<PathBuf>,
+}
+
+impl UnityScan {
+    pub async fn run(self) {
+        let io = DefaultEnvironmentIo::new_default();
+
+        let search_paths = if self.paths.is_empty() {
+            let settings = Settings::load(&io).await.exit_context("loading settings");
+            settings.unity_search_paths().to_vec()
+        } else {
+            self.paths.iter().map(absolute_path).collect()
+        };
+
+        for path in &search_paths {
+            if !path.is_dir() {
+                exit_with!("{} is not a directory", path.display());
+            }
+        }
+
+        let mut connection = VccDatabaseConnection::connect(&io)
+            .await
+            .exit_context("connecting to database");
+
+        add_unity_from_search_paths(&mut connection, &search_paths).await;
+
+        connection.save(&io).await.exit_context("saving database");
+    }
+}
+
+async fn add_unity_from_search_paths(
+    connection: &mut VccDatabaseConnection,
+    search_paths: &[PathBuf],
+) {
+    for unity in unity_hub::find_unity_editors_in_search_paths(search_paths).await {
+        let path = unity_hub::get_executable_path(&unity.path);
+        let Some(path) = path.to_str() else {
+            warn!("Unity at {} is ignored: non-utf8 path", path.display());
+            continue;
+        };
+        let architecture = (unity.architecture)
+            .map(|x| format!(" ({x})"))
+            .unwrap_or_default();
+
+        let added = match connection.add_unity_installation_if_absent(path, unity.version) {
+            Ok(added) => added,
+            Err(e) => {
+                warn!("Unity at {path} is ignored: {e}");
+                continue;
+            }
+        };
+
+        if added {
+            println!("Added version {}{architecture} at {path}", unity.version);
+        } else {
+            println!("Found version {}{architecture} at {path}", unity.version);
+        }
+    }
+}